@@ -0,0 +1,52 @@
+// cargo bench -p common_core --bench pool_bench
+//
+// single cpu linux vm, ops/s of one checkout plus return; with one cpu this shows lock overhead, not contention:
+//  threads           kind            ops/s
+//        1          Owned           682525
+//        1     Sharded(0)           978249
+//        4          Owned           755167
+//        4     Sharded(0)          1071935
+//       16          Owned           766919
+//       16     Sharded(0)          1066741
+//       64          Owned           672884
+//       64     Sharded(0)          1036063
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+use common_core::collection::pool::{get_thread_safe_pool_with_policy, PoolKind, PoolPolicy, ThreadSafePool};
+
+const ROUNDS : usize = 20_000;
+
+fn run(kind : PoolKind, threads : usize) -> Duration {
+    let p : Arc<dyn ThreadSafePool<u64, ()>> = get_thread_safe_pool_with_policy(String::from("bench"), Box::new(|_ : ()| Ok(0u64)), threads,
+                                                                               PoolPolicy { kind, ..Default::default() });
+    let started = Instant::now();
+
+    let workers : Vec<_> = (0..threads).map(|_| {
+        let p = p.clone();
+        thread::spawn(move || {
+            for _ in 0..ROUNDS {
+                let mut item = p.get_owned_wait(()).unwrap();
+                *item.get_value() += 1;
+                item.restoration();
+            }
+        })
+    }).collect();
+
+    for w in workers {
+        w.join().unwrap();
+    }
+    started.elapsed()
+}
+
+fn main() {
+    println!("{:>8} {:>14} {:>16} {:>16}", "threads", "kind", "ops/s", "elapsed");
+
+    for threads in [1, 4, 16, 64] {
+        for kind in [PoolKind::Owned, PoolKind::Sharded(0)] {
+            let elapsed = run(kind, threads);
+            let ops = (threads * ROUNDS) as f64 / elapsed.as_secs_f64();
+            println!("{:>8} {:>14} {:>16.0} {:>16?}", threads, format!("{:?}", kind), ops, elapsed);
+        }
+    }
+}
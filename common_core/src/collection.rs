@@ -1,2 +1,3 @@
 pub mod pool;
-mod observer;
\ No newline at end of file
+pub mod lru;
+pub mod observer;
\ No newline at end of file
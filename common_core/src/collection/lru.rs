@@ -0,0 +1,103 @@
+use std::borrow::Borrow;
+use std::collections::{BTreeMap, HashMap};
+use std::hash::Hash;
+
+pub struct LruCache<K, V> where K : Hash + Eq + Clone {
+    capacity : usize,
+    items : HashMap<K, (V, u64)>,
+    order : BTreeMap<u64, K>,
+    tick : u64,
+    hit : u64,
+    miss : u64
+}
+
+impl<K, V> LruCache<K, V> where K : Hash + Eq + Clone {
+    pub fn new(capacity : usize) -> Self {
+        LruCache {
+            capacity,
+            items : HashMap::with_capacity(capacity),
+            order : BTreeMap::new(),
+            tick : 0,
+            hit : 0,
+            miss : 0
+        }
+    }
+
+    fn next_tick(&mut self) -> u64 {
+        self.tick += 1;
+        self.tick
+    }
+
+    pub fn get<Q>(&mut self, key : &'_ Q) -> Option<&'_ V> where K : Borrow<Q>, Q : Hash + Eq + ?Sized {
+        self.get_mut(key).map(|v| &*v)
+    }
+
+    pub fn get_mut<Q>(&mut self, key : &'_ Q) -> Option<&'_ mut V> where K : Borrow<Q>, Q : Hash + Eq + ?Sized {
+        let tick = self.next_tick();
+
+        match self.items.get_mut(key) {
+            Some((v, t)) => {
+                let k = self.order.remove(t).unwrap();
+                self.order.insert(tick, k);
+                *t = tick;
+                self.hit += 1;
+                Some(v)
+            },
+            None => {
+                self.miss += 1;
+                None
+            }
+        }
+    }
+
+    pub fn contains<Q>(&self, key : &'_ Q) -> bool where K : Borrow<Q>, Q : Hash + Eq + ?Sized {
+        self.items.contains_key(key)
+    }
+
+    // returns the evicted entry, or the value itself when capacity is zero
+    pub fn put(&mut self, key : K, value : V) -> Option<(K, V)> {
+        if self.capacity == 0 {
+            return Some((key, value));
+        }
+
+        let tick = self.next_tick();
+
+        if let Some((_, t)) = self.items.remove(&key) {
+            self.order.remove(&t);
+        }
+
+        let evicted = if self.items.len() >= self.capacity {
+            self.pop_lru()
+        } else {
+            None
+        };
+
+        self.order.insert(tick, key.clone());
+        self.items.insert(key, (value, tick));
+
+        evicted
+    }
+
+    pub fn pop_lru(&mut self) -> Option<(K, V)> {
+        let (_, k) = self.order.pop_first()?;
+        let (v, _) = self.items.remove(&k)?;
+        Some((k, v))
+    }
+
+    pub fn remove<Q>(&mut self, key : &'_ Q) -> Option<V> where K : Borrow<Q>, Q : Hash + Eq + ?Sized {
+        let (v, t) = self.items.remove(key)?;
+        self.order.remove(&t);
+        Some(v)
+    }
+
+    pub fn clear(&mut self) {
+        self.items.clear();
+        self.order.clear();
+    }
+
+    pub fn len(&self) -> usize { self.items.len() }
+    pub fn is_empty(&self) -> bool { self.items.is_empty() }
+    pub fn capacity(&self) -> usize { self.capacity }
+    pub fn hit_count(&self) -> u64 { self.hit }
+    pub fn miss_count(&self) -> u64 { self.miss }
+}
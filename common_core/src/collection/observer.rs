@@ -1,23 +1,28 @@
 use std::sync::atomic::{AtomicU64, Ordering};
-use std::sync::Arc;
+use std::sync::{Arc, RwLock};
 pub struct Subject<T : 'static + Clone> {
-    current : Option<T>,
+    current : RwLock<Option<T>>,
     seq     : AtomicU64
 }
 
 impl<T : 'static + Clone> Subject<T> {
     pub fn new() -> Subject<T> {
-        Subject { current : None, seq: AtomicU64::new(0) }
+        Subject { current : RwLock::new(None), seq: AtomicU64::new(0) }
     }
 
     pub fn new_arc() -> Arc<Subject<T>> {
-        Arc::new(Subject { current : None, seq: AtomicU64::new(0) })
+        Arc::new(Subject { current : RwLock::new(None), seq: AtomicU64::new(0) })
     }
 
-    pub fn notify(&mut self, val : T) {
-        self.current.replace(val);
+    // shared subjects are notified through the Arc, observers only see the latest value
+    pub fn notify(&self, val : T) {
+        self.current.write().unwrap().replace(val);
         self.seq.fetch_add(1, Ordering::SeqCst);
     }
+
+    pub fn current(&self) -> Option<T> {
+        self.current.read().unwrap().clone()
+    }
 }
 
 pub struct Observer<'a, T : 'static + Clone> {
@@ -34,12 +39,13 @@ impl<'a, T : 'static + Clone> Observer<'a, T> {
     }
 
     pub fn update(&self) -> Option<T> {
-        if self.observer.seq.load(Ordering::SeqCst) == self.seq.load(Ordering::Relaxed) {
-           None
+        let seq = self.observer.seq.load(Ordering::SeqCst);
+        if seq == self.seq.load(Ordering::Relaxed) {
+            None
         } else {
-            let ret = self.observer.current.as_ref().cloned();
-            self.seq.store(self.observer.seq.load(Ordering::SeqCst), Ordering::Relaxed);
-            ret
+            // notify stores the value before bumping seq
+            self.seq.store(seq, Ordering::Relaxed);
+            self.observer.current()
         }
     }
 }
@@ -59,12 +65,12 @@ impl<T : 'static + Clone> ThreadSafeObserver<T> {
     }
 
     pub fn update(&self) -> Option<T> {
-        if self.observer.seq.load(Ordering::SeqCst) == self.seq.load(Ordering::Relaxed) {
+        let seq = self.observer.seq.load(Ordering::SeqCst);
+        if seq == self.seq.load(Ordering::Relaxed) {
             None
         } else {
-            let ret = self.observer.current.as_ref().cloned();
-            self.seq.store(self.observer.seq.load(Ordering::SeqCst), Ordering::Relaxed);
-            ret
+            self.seq.store(seq, Ordering::Relaxed);
+            self.observer.current()
         }
     }
 }
@@ -1,22 +1,238 @@
 mod owned_pool;
+mod keyed_pool;
+mod sharded_pool;
+mod async_pool;
 
+use std::future::Future;
+use std::hash::Hash;
+use std::pin::Pin;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
+use async_trait::async_trait;
 use common_err::CommonError;
 
+#[derive(Clone, Copy, Debug)]
+pub struct PoolItemMeta {
+    pub created_at : Instant,
+    pub last_used : Instant,
+    pub use_count : u64
+}
+
+impl PoolItemMeta {
+    pub(crate) fn new() -> Self {
+        let now = Instant::now();
+        PoolItemMeta { created_at : now, last_used : now, use_count : 0 }
+    }
+}
+
+pub const POOL_DURATION_BUCKETS : [Duration; 7] = [
+    Duration::from_millis(1), Duration::from_millis(5), Duration::from_millis(10), Duration::from_millis(50),
+    Duration::from_millis(100), Duration::from_millis(500), Duration::from_secs(1)
+];
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PoolDurationHistogram {
+    // counts[i] holds durations up to POOL_DURATION_BUCKETS[i], the last slot everything slower
+    pub counts : [u64; POOL_DURATION_BUCKETS.len() + 1],
+    pub total : Duration,
+    pub max : Duration
+}
+
+impl PoolDurationHistogram {
+    pub fn record(&mut self, d : Duration) {
+        let idx = POOL_DURATION_BUCKETS.iter().position(|b| d <= *b).unwrap_or(POOL_DURATION_BUCKETS.len());
+        self.counts[idx] += 1;
+        self.total += d;
+        self.max = self.max.max(d);
+    }
+
+    pub fn count(&self) -> u64 {
+        self.counts.iter().sum()
+    }
+
+    pub(crate) fn merge(&mut self, other : &PoolDurationHistogram) {
+        for (c, o) in self.counts.iter_mut().zip(other.counts.iter()) {
+            *c += *o;
+        }
+        self.total += other.total;
+        self.max = self.max.max(other.max);
+    }
+
+    pub fn mean(&self) -> Duration {
+        match self.count() {
+            0 => Duration::ZERO,
+            c => self.total / c as u32
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PoolStats {
+    pub idle : usize,
+    pub in_use : usize,
+    pub waiting : usize,
+    pub max_size : usize,
+    pub created : u64,
+    pub create_failed : u64,
+    pub checkouts : u64,
+    pub returns : u64,
+    // explicit disposes plus items dropped by the policy
+    pub disposed : u64,
+    pub wait_timeouts : u64,
+    // time spent getting an item, and time it was held before coming back
+    pub wait : PoolDurationHistogram,
+    pub hold : PoolDurationHistogram
+}
+
+impl PoolStats {
+    // sums the counters and histograms, gauges are left as they are
+    pub(crate) fn merge_counters(&mut self, other : &PoolStats) {
+        self.created += other.created;
+        self.create_failed += other.create_failed;
+        self.checkouts += other.checkouts;
+        self.returns += other.returns;
+        self.disposed += other.disposed;
+        self.wait_timeouts += other.wait_timeouts;
+        self.wait.merge(&other.wait);
+        self.hold.merge(&other.hold);
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum PoolKind {
+    // one lock, strict FIFO for blocked borrowers
+    #[default]
+    Owned,
+    // idle items spread over shards with stealing, for many threads; 0 shards means one per cpu
+    Sharded(usize)
+}
+
+pub type PoolHook<T> = Box<dyn Fn(&T, &PoolItemMeta) + Send + Sync>;
+pub type PoolErrorHook = Box<dyn Fn(&CommonError) + Send + Sync>;
+
+// hooks run under the pool lock, possibly on the maintenance thread, and must not call back into the pool
+pub struct PoolHooks<T> {
+    pub on_create : Option<PoolHook<T>>,
+    pub on_checkout : Option<PoolHook<T>>,
+    pub on_return : Option<PoolHook<T>>,
+    pub on_dispose : Option<PoolHook<T>>,
+    // also called for background creations, where no caller sees the error
+    pub on_create_failed : Option<PoolErrorHook>
+}
+
+impl<T> Default for PoolHooks<T> {
+    fn default() -> Self {
+        PoolHooks { on_create : None, on_checkout : None, on_return : None, on_dispose : None, on_create_failed : None }
+    }
+}
+
+// every limit is optional, the default policy keeps items forever
+pub struct PoolPolicy<T> {
+    // test-on-borrow, an idle item failing it is disposed and the next one is tried
+    pub validate : Option<Box<dyn Fn(&mut T) -> bool + Send + Sync>>,
+    // run on return with no lock held, an item failing it is disposed instead of pooled again
+    pub reset : Option<Box<dyn Fn(&mut T) -> bool + Send + Sync>>,
+    pub idle_timeout : Option<Duration>,
+    pub max_lifetime : Option<Duration>,
+    pub max_uses : Option<u64>,
+    // created at construction and kept topped up by the maintenance thread
+    pub min_idle : usize,
+    pub maintenance_interval : Duration,
+    pub kind : PoolKind,
+    pub hooks : PoolHooks<T>
+}
+
+impl<T> Default for PoolPolicy<T> {
+    fn default() -> Self {
+        PoolPolicy {
+            validate : None,
+            reset : None,
+            idle_timeout : None,
+            max_lifetime : None,
+            max_uses : None,
+            min_idle : 0,
+            maintenance_interval : Duration::from_secs(1),
+            kind : PoolKind::Owned,
+            hooks : PoolHooks::default()
+        }
+    }
+}
+
+impl<T> PoolPolicy<T> {
+    pub fn is_idle_expired(&self, meta : &PoolItemMeta, now : Instant) -> bool {
+        matches!(self.idle_timeout, Some(t) if now.saturating_duration_since(meta.last_used) >= t)
+    }
+
+    // lifetime or use count is over, the item is not pooled again
+    pub fn is_retired(&self, meta : &PoolItemMeta, now : Instant) -> bool {
+        matches!(self.max_lifetime, Some(t) if now.saturating_duration_since(meta.created_at) >= t)
+            || matches!(self.max_uses, Some(m) if meta.use_count >= m)
+    }
+}
+
 pub trait PoolItem<T> {
     fn get_value<'b>(&'b mut self) -> &'b mut T;
+    fn get_value_ref<'b>(&'b self) -> &'b T;
     fn dispose(self: Box<Self>);
     fn restoration(self: Box<Self>);
+    fn meta(&self) -> Option<PoolItemMeta> {
+        None
+    }
 }
 pub trait ThreadSafePool<T,P>  : Send + Sync  where T : 'static, P: 'static {
     fn get_owned(&self, param : P) -> Result<Box<dyn PoolItem<T>>, CommonError>;
+    // waits in arrival order for a restored or disposed item, LimitSize once timeout passes
+    fn get_owned_timeout(&self, param : P, timeout : Duration) -> Result<Box<dyn PoolItem<T>>, CommonError>;
+    fn get_owned_wait(&self, param : P) -> Result<Box<dyn PoolItem<T>>, CommonError>;
+    // disposes idle items past the policy limits, returns how many
+    fn evict_idle(&self) -> usize;
+    fn stats(&self) -> PoolStats;
+    // rejects new checkouts, disposes idle items and waits for the checked out ones, LimitSize once timeout passes
+    fn close(&self, timeout : Duration) -> Result<(), CommonError>;
+    // disposes idle items now and checked out ones on return, returns how many were idle
+    fn drain(&self) -> usize;
+    // shrinking disposes idle items now and checked out ones on return until alloc_size fits
+    fn resize(&self, max_size : usize);
     fn alloc_size(&self) -> usize;
     fn max_size(&self) -> usize;
 }
 
-pub fn get_thread_safe_pool<T : 'static,P : 'static>(name : String, gen : Box<dyn Fn(P) -> Result<T, CommonError>>, max_size : usize) -> Arc<dyn ThreadSafePool<T,P>> {
-    owned_pool::OwnedPool::new(name, gen, max_size)
+pub type AsyncPoolGen<T,P> = Box<dyn Fn(P) -> Pin<Box<dyn Future<Output = Result<T, CommonError>> + Send>> + Send + Sync>;
+
+#[async_trait]
+pub trait AsyncThreadSafePool<T,P> : Send + Sync where T : Send + 'static, P : Send + 'static {
+    // waits for a free slot when max_size items are checked out
+    async fn get_owned(&self, param : P) -> Result<Box<dyn PoolItem<T> + Send>, CommonError>;
+    fn alloc_size(&self) -> usize;
+    fn max_size(&self) -> usize;
 }
 
+pub fn get_thread_safe_pool<T : 'static,P : 'static>(name : String, gen : Box<dyn Fn(P) -> Result<T, CommonError>>, max_size : usize,
+                                                    kind : PoolKind) -> Arc<dyn ThreadSafePool<T,P>> {
+    match kind {
+        PoolKind::Owned => owned_pool::OwnedPool::new(name, gen, max_size),
+        PoolKind::Sharded(shards) => sharded_pool::ShardedPool::new(name, gen, max_size, shards)
+    }
+}
 
+// min_idle and the maintenance thread create and drop items off the caller's thread, so T and the generator have to be Send.
+// background creations use P::default() as the gen parameter
+pub fn get_thread_safe_pool_with_policy<T : Send + 'static,P : Default + 'static>(name : String, gen : Box<dyn Fn(P) -> Result<T, CommonError> + Send + Sync>,
+                                                                        max_size : usize, policy : PoolPolicy<T>) -> Arc<dyn ThreadSafePool<T,P>> {
+    match policy.kind {
+        PoolKind::Owned => owned_pool::OwnedPool::with_policy(name, gen, max_size, policy),
+        PoolKind::Sharded(shards) => sharded_pool::ShardedPool::with_policy(name, gen, max_size, shards, policy)
+    }
+}
+
+// one isolated sub-pool per param, idle items of the least recently used keys make room once max_size is reached.
+// blocked borrowers are served in arrival order, min_idle is refused and idle limits run on the maintenance thread
+pub fn get_keyed_thread_safe_pool<T : Send + 'static, P : Hash + Eq + Clone + Send + 'static>(name : String, gen : Box<dyn Fn(P) -> Result<T, CommonError> + Send + Sync>,
+                                                                                           max_per_key : usize, max_size : usize,
+                                                                                           policy : PoolPolicy<T>) -> Result<Arc<dyn ThreadSafePool<T,P>>, CommonError> {
+    Ok(keyed_pool::KeyedPool::new(name, gen, max_per_key, max_size, policy)?)
+}
 
+pub fn get_async_thread_safe_pool<T : Send + 'static, P : Send + 'static>(name : String, gen : AsyncPoolGen<T,P>, max_size : usize) -> Arc<dyn AsyncThreadSafePool<T,P>> {
+    async_pool::AsyncOwnedPool::new(name, gen, max_size)
+}
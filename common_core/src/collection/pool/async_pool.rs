@@ -0,0 +1,153 @@
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use async_trait::async_trait;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+use crate::collection::pool::{AsyncPoolGen, AsyncThreadSafePool, PoolItem};
+use common_err::{CommonError, gen::CommonDefaultErrorKind};
+
+struct AsyncOwnedPoolState<T> {
+    items : VecDeque<T>,
+    alloc_size : usize
+}
+
+struct InternalAsyncOwnedPool<T,P> where T : Send + 'static, P : Send + 'static {
+    gen : AsyncPoolGen<T,P>,
+    max_size : usize,
+    // one permit per checked out item, tokio hands permits out in FIFO order
+    permits : Arc<Semaphore>,
+    state : Mutex<AsyncOwnedPoolState<T>>,
+    pool_name : String
+}
+
+impl<T,P> InternalAsyncOwnedPool<T,P> where T : Send + 'static, P : Send + 'static {
+    fn dispose(&self, _ : T) {
+        let mut g = self.state.lock().unwrap();
+        g.alloc_size -= 1;
+    }
+
+    fn restoration(&self, item : T) {
+        let mut g = self.state.lock().unwrap();
+        g.items.push_back(item);
+    }
+}
+
+// a slot reserved for generation, given back on drop unless committed, also when the caller's future is dropped
+struct AsyncSlotReservation<'a, T> {
+    state : &'a Mutex<AsyncOwnedPoolState<T>>,
+    committed : bool
+}
+
+impl<T> Drop for AsyncSlotReservation<'_, T> {
+    fn drop(&mut self) {
+        if !self.committed {
+            if let Ok(mut g) = self.state.lock() {
+                g.alloc_size -= 1;
+            }
+        }
+    }
+}
+
+pub struct AsyncPoolItemOwned<T,P> where T : Send + 'static, P : Send + 'static {
+    value : Option<T>,
+    // released after the value went back to the pool
+    permit : Option<OwnedSemaphorePermit>,
+    pool : Arc<InternalAsyncOwnedPool<T,P>>
+}
+
+impl<T,P> AsyncPoolItemOwned<T,P> where T : Send + 'static, P : Send + 'static {
+    fn release(&mut self, dispose : bool) {
+        if let Some(val) = self.value.take() {
+            if dispose {
+                self.pool.dispose(val);
+            } else {
+                self.pool.restoration(val);
+            }
+        }
+        self.permit.take();
+    }
+}
+
+impl<T,P> PoolItem<T> for AsyncPoolItemOwned<T,P> where T : Send + 'static, P : Send + 'static {
+    fn get_value<'b>(&'b mut self) -> &'b mut T {
+        self.value.as_mut().unwrap()
+    }
+
+    fn get_value_ref<'b>(&'b self) -> &'b T {
+        self.value.as_ref().unwrap()
+    }
+
+    fn dispose(mut self : Box<Self>) {
+        self.release(true);
+    }
+
+    fn restoration(mut self : Box<Self>) {
+        self.release(false);
+    }
+}
+
+impl<T,P> Drop for AsyncPoolItemOwned<T,P> where T : Send + 'static, P : Send + 'static {
+    fn drop(&mut self) {
+        self.release(false);
+    }
+}
+
+pub struct AsyncOwnedPool<T,P> where T : Send + 'static, P : Send + 'static {
+    internal : Arc<InternalAsyncOwnedPool<T,P>>
+}
+
+impl<T,P> AsyncOwnedPool<T,P> where T : Send + 'static, P : Send + 'static {
+    pub fn new(name : String, gen : AsyncPoolGen<T,P>, max_size : usize) -> Arc<Self> {
+        Arc::new(AsyncOwnedPool {
+            internal : Arc::new(InternalAsyncOwnedPool {
+                gen,
+                max_size,
+                permits : Arc::new(Semaphore::new(max_size)),
+                state : Mutex::new(AsyncOwnedPoolState { items : VecDeque::new(), alloc_size : 0 }),
+                pool_name : name
+            })
+        })
+    }
+}
+
+#[async_trait]
+impl<T,P> AsyncThreadSafePool<T,P> for AsyncOwnedPool<T,P> where T : Send + 'static, P : Send + 'static {
+    async fn get_owned(&self, param : P) -> Result<Box<dyn PoolItem<T> + Send>, CommonError> {
+        let internal = &self.internal;
+        let permit = internal.permits.clone().acquire_owned().await.map_err(|e| {
+            CommonError::new(&CommonDefaultErrorKind::InvalidApiCall, format!("pool_name:{} - {}", internal.pool_name, e))
+        })?;
+
+        let idle = {
+            let mut g = internal.state.lock().unwrap();
+            let item = g.items.pop_front();
+            if item.is_none() {
+                // reserve the slot before generating outside the lock
+                g.alloc_size += 1;
+            }
+            item
+        };
+
+        let value = match idle {
+            Some(item) => item,
+            None => {
+                let mut reservation = AsyncSlotReservation { state : &internal.state, committed : false };
+                let item = (internal.gen)(param).await.map_err(|err| {
+                    CommonError::extend(&CommonDefaultErrorKind::InvalidApiCall, format!("pool_name:{}", internal.pool_name), err)
+                })?;
+                reservation.committed = true;
+                item
+            }
+        };
+
+        Ok(Box::new(AsyncPoolItemOwned { value : Some(value), permit : Some(permit), pool : internal.clone() }))
+    }
+
+    fn alloc_size(&self) -> usize {
+        self.internal.state.lock().unwrap().alloc_size
+    }
+
+    fn max_size(&self) -> usize {
+        self.internal.max_size
+    }
+}
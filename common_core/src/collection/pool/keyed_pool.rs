@@ -0,0 +1,512 @@
+use std::collections::{HashMap, VecDeque};
+use std::hash::Hash;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+use crate::collection::pool::{PoolItem, PoolPolicy, PoolStats, ThreadSafePool};
+use crate::collection::pool::owned_pool::{spawn_maintenance, PoolCommander, PoolEntry, PoolItemOwned, PoolMaintenance, PoolWaiters};
+use common_err::{CommonError, gen::CommonDefaultErrorKind};
+
+struct KeyedSubPool<T> {
+    items : VecDeque<PoolEntry<T>>,
+    alloc_size : usize,
+    // tick of the last checkout, the smallest one is evicted first
+    last_used : u64
+}
+
+struct KeyedPoolState<T,P> {
+    keys : HashMap<P, KeyedSubPool<T>>,
+    alloc_size : usize,
+    tick : u64,
+    // blocked borrowers with the key each waits for
+    waiters : PoolWaiters<P>,
+    max_size : usize,
+    closed : bool,
+    generation : u64,
+    stats : PoolStats
+}
+
+impl<T,P> KeyedPoolState<T,P> where P : Hash + Eq {
+    fn idle_count(&self) -> usize {
+        self.keys.values().map(|sub| sub.items.len()).sum()
+    }
+}
+
+enum KeyedSlot<T> {
+    // an idle item, already checked out
+    Taken(PoolEntry<T>),
+    // an idle item still to be validated, counted by alloc_size but neither idle nor checked out
+    Idle(PoolEntry<T>),
+    // alloc_size of the pool and the key count the item of this drain generation, it is created once the lock is released
+    Reserved(u64),
+    Full(String)
+}
+
+struct InternalKeyedPool<T,P> where T : 'static, P : Hash + Eq + Clone + 'static {
+    gen : Box<dyn Fn(P) -> Result<T, CommonError> + Send + Sync>,
+    max_per_key : usize,
+    policy : PoolPolicy<T>,
+    state : Mutex<KeyedPoolState<T,P>>,
+    available : Condvar,
+    pool_name : String
+}
+
+impl<T,P> InternalKeyedPool<T,P> where T : 'static, P : Hash + Eq + Clone + 'static {
+    fn closed_error(&self) -> CommonError {
+        CommonError::new(&CommonDefaultErrorKind::InvalidApiCall, format!("pool_name:{} - closed", self.pool_name))
+    }
+
+    // gives back the room of a disposed item or a failed creation
+    fn release_slot(&self, g : &mut MutexGuard<'_, KeyedPoolState<T,P>>, key : &'_ P) {
+        g.alloc_size -= 1;
+
+        let empty = match g.keys.get_mut(key) {
+            Some(sub) => {
+                sub.alloc_size -= 1;
+                sub.alloc_size == 0
+            },
+            None => false
+        };
+        // a key without items costs nothing to forget
+        if empty {
+            g.keys.remove(key);
+        }
+    }
+
+    fn dispose_entry(&self, g : &mut MutexGuard<'_, KeyedPoolState<T,P>>, key : &'_ P, entry : PoolEntry<T>) {
+        self.release_slot(g, key);
+        g.stats.disposed += 1;
+        entry.call_hook(&self.policy.hooks.on_dispose);
+    }
+
+    // frees one idle item of the least recently used other key
+    fn evict_lru_idle(&self, g : &mut MutexGuard<'_, KeyedPoolState<T,P>>, key : &'_ P) -> bool {
+        let victim = g.keys.iter()
+            .filter(|(k, sub)| *k != key && !sub.items.is_empty())
+            .min_by_key(|(_, sub)| sub.last_used)
+            .map(|(k, _)| k.clone());
+
+        match victim {
+            Some(victim) => {
+                let entry = g.keys.get_mut(&victim).unwrap().items.pop_front().unwrap();
+                self.dispose_entry(g, &victim, entry);
+                true
+            },
+            None => false
+        }
+    }
+
+    // idle items of every key, oldest first per key
+    fn dispose_idle(&self, g : &mut MutexGuard<'_, KeyedPoolState<T,P>>, limit : usize) -> usize {
+        let mut idle = Vec::new();
+        for (key, sub) in g.keys.iter_mut() {
+            while idle.len() < limit {
+                match sub.items.pop_front() {
+                    Some(entry) => idle.push((key.clone(), entry)),
+                    None => break
+                }
+            }
+        }
+
+        let disposed = idle.len();
+        for (key, entry) in idle {
+            self.dispose_entry(g, &key, entry);
+        }
+        disposed
+    }
+
+    // an idle item of the key, or room for a new one, possibly made by evicting another key
+    fn can_serve(&self, g : &KeyedPoolState<T,P>, key : &'_ P, idle : usize) -> bool {
+        match g.keys.get(key) {
+            Some(sub) if !sub.items.is_empty() => true,
+            sub => sub.map(|sub| sub.alloc_size).unwrap_or(0) < self.max_per_key && (g.alloc_size < g.max_size || idle > 0)
+        }
+    }
+
+    fn is_turn(&self, g : &KeyedPoolState<T,P>, ticket : Option<u64>) -> bool {
+        if g.waiters.is_empty() {
+            return true;
+        }
+        let idle = g.idle_count();
+        g.waiters.is_turn(ticket, |key| self.can_serve(g, key, idle))
+    }
+
+    fn try_take(&self, g : &mut MutexGuard<'_, KeyedPoolState<T,P>>, key : &'_ P, started : Instant) -> KeyedSlot<T> {
+        let now = Instant::now();
+        g.tick += 1;
+        let tick = g.tick;
+
+        let mut taken = None;
+        if let Some(sub) = g.keys.get_mut(key) {
+            sub.last_used = tick;
+        }
+        while let Some(entry) = g.keys.get_mut(key).and_then(|sub| sub.items.pop_front()) {
+            if !entry.is_expired(&self.policy, now) {
+                taken = Some(entry);
+                break;
+            }
+            self.dispose_entry(g, key, entry);
+            self.available.notify_all();
+        }
+
+        match taken {
+            Some(entry) if self.policy.validate.is_some() => return KeyedSlot::Idle(entry),
+            Some(entry) => return KeyedSlot::Taken(self.check_out(g, entry, started)),
+            None => {}
+        }
+
+        let sub_alloc = g.keys.get(key).map(|sub| sub.alloc_size).unwrap_or(0);
+        if sub_alloc >= self.max_per_key {
+            return KeyedSlot::Full(format!("pool_name:{} - key limit {}", self.pool_name, self.max_per_key));
+        }
+        if g.alloc_size >= g.max_size && !self.evict_lru_idle(g, key) {
+            return KeyedSlot::Full(format!("pool_name:{} - global limit {}", self.pool_name, g.max_size));
+        }
+
+        g.alloc_size += 1;
+        let sub = g.keys.entry(key.clone()).or_insert_with(|| KeyedSubPool { items : VecDeque::new(), alloc_size : 0, last_used : tick });
+        sub.alloc_size += 1;
+        sub.last_used = tick;
+        KeyedSlot::Reserved(g.generation)
+    }
+
+    fn check_out(&self, g : &mut MutexGuard<'_, KeyedPoolState<T,P>>, mut entry : PoolEntry<T>, started : Instant) -> PoolEntry<T> {
+        let now = Instant::now();
+        entry.check_out(now);
+        g.stats.checkouts += 1;
+        g.stats.wait.record(now.saturating_duration_since(started));
+        entry.call_hook(&self.policy.hooks.on_checkout);
+        entry
+    }
+
+    // the generator of a reserved slot returned, a failure gives the slot back
+    fn settle_created(&self, g : &mut MutexGuard<'_, KeyedPoolState<T,P>>, key : &'_ P, created : Result<T, CommonError>,
+                      generation : u64) -> Result<PoolEntry<T>, CommonError> {
+        match created {
+            Ok(item) => {
+                g.stats.created += 1;
+                let entry = PoolEntry::new(item, generation);
+                entry.call_hook(&self.policy.hooks.on_create);
+                Ok(entry)
+            },
+            Err(e) => {
+                self.release_slot(g, key);
+                let err = CommonError::extend(&CommonDefaultErrorKind::InvalidApiCall, format!("pool_name:{}", self.pool_name), e);
+                g.stats.create_failed += 1;
+                if let Some(hook) = self.policy.hooks.on_create_failed.as_ref() {
+                    hook(&err);
+                }
+                self.available.notify_all();
+                Err(err)
+            }
+        }
+    }
+
+    // None waits without limit, Some(ZERO) does not wait at all.
+    // blocked borrowers are served in arrival order, skipping those whose key can not be served yet
+    fn take(&self, key : &'_ P, timeout : Option<Duration>) -> Result<PoolEntry<T>, CommonError> {
+        let started = Instant::now();
+        let deadline = timeout.map(|t| started + t);
+        let mut g = self.state.lock().unwrap();
+        let mut ticket = None;
+
+        let ret = loop {
+            if g.closed {
+                break Err(self.closed_error());
+            }
+
+            let msg = if !self.is_turn(&g, ticket) {
+                format!("pool_name:{} - {} waiters", self.pool_name, g.waiters.len())
+            } else {
+                match self.try_take(&mut g, key, started) {
+                    KeyedSlot::Taken(entry) => break Ok(entry),
+                    KeyedSlot::Idle(mut entry) => {
+                        // test-on-borrow runs unlocked, a failed item is disposed and the key tried again
+                        drop(g);
+                        let valid = entry.validate(&self.policy);
+                        g = self.state.lock().unwrap();
+
+                        if valid && !g.closed && entry.generation == g.generation {
+                            break Ok(self.check_out(&mut g, entry, started));
+                        }
+                        self.dispose_entry(&mut g, key, entry);
+                        self.available.notify_all();
+                        continue;
+                    },
+                    KeyedSlot::Reserved(generation) => {
+                        drop(g);
+                        let created = (self.gen)(key.clone());
+                        g = self.state.lock().unwrap();
+
+                        let entry = match self.settle_created(&mut g, key, created, generation) {
+                            Ok(entry) => entry,
+                            Err(e) => break Err(e)
+                        };
+                        if g.closed {
+                            self.dispose_entry(&mut g, key, entry);
+                            self.available.notify_all();
+                            break Err(self.closed_error());
+                        }
+                        break Ok(self.check_out(&mut g, entry, started));
+                    },
+                    KeyedSlot::Full(msg) => msg
+                }
+            };
+
+            let now = Instant::now();
+            let remain = match deadline {
+                Some(deadline) if now >= deadline => {
+                    if ticket.is_some() {
+                        g.stats.wait_timeouts += 1;
+                    }
+                    break CommonError::new(&CommonDefaultErrorKind::LimitSize, msg).to_result();
+                },
+                Some(deadline) => Some(deadline - now),
+                None => None
+            };
+
+            if ticket.is_none() {
+                ticket = Some(g.waiters.push(key.clone()));
+            }
+
+            g = match remain {
+                Some(remain) => self.available.wait_timeout(g, remain).unwrap().0,
+                None => self.available.wait(g).unwrap()
+            };
+        };
+
+        if let Some(ticket) = ticket {
+            g.waiters.remove(ticket);
+            // the next waiter may be served now
+            self.available.notify_all();
+        }
+        ret
+    }
+
+    fn restore_entry(&self, g : &mut MutexGuard<'_, KeyedPoolState<T,P>>, key : &'_ P, mut entry : PoolEntry<T>) {
+        let now = Instant::now();
+        g.stats.returns += 1;
+        g.stats.hold.record(now.saturating_duration_since(entry.meta.last_used));
+
+        let stale = g.closed || entry.generation != g.generation || g.alloc_size > g.max_size;
+        if stale || self.policy.is_retired(&entry.meta, now) {
+            self.dispose_entry(g, key, entry);
+            return;
+        }
+
+        entry.meta.last_used = now;
+        entry.call_hook(&self.policy.hooks.on_return);
+        g.keys.get_mut(key).unwrap().items.push_back(entry);
+    }
+
+    fn evict_idle(&self) -> usize {
+        let now = Instant::now();
+        let mut g = self.state.lock().unwrap();
+        let mut expired = Vec::new();
+
+        for (key, sub) in g.keys.iter_mut() {
+            let items = std::mem::take(&mut sub.items);
+            for entry in items {
+                if self.policy.is_idle_expired(&entry.meta, now) || self.policy.is_retired(&entry.meta, now) {
+                    expired.push((key.clone(), entry));
+                } else {
+                    sub.items.push_back(entry);
+                }
+            }
+        }
+
+        let evicted = expired.len();
+        for (key, entry) in expired {
+            self.dispose_entry(&mut g, &key, entry);
+        }
+
+        if evicted > 0 {
+            self.available.notify_all();
+        }
+        evicted
+    }
+
+    fn close(&self, timeout : Duration) -> Result<(), CommonError> {
+        let deadline = Instant::now() + timeout;
+        let mut g = self.state.lock().unwrap();
+
+        g.closed = true;
+        self.dispose_idle(&mut g, usize::MAX);
+        self.available.notify_all();
+
+        while g.alloc_size > 0 {
+            let now = Instant::now();
+            if now >= deadline {
+                return CommonError::new(&CommonDefaultErrorKind::LimitSize,
+                                        format!("pool_name:{} - close timeout, {} items still checked out", self.pool_name, g.alloc_size)).to_result();
+            }
+            g = self.available.wait_timeout(g, deadline - now).unwrap().0;
+        }
+        Ok(())
+    }
+
+    fn drain(&self) -> usize {
+        let mut g = self.state.lock().unwrap();
+        g.generation += 1;
+        let disposed = self.dispose_idle(&mut g, usize::MAX);
+        self.available.notify_all();
+        disposed
+    }
+
+    fn resize(&self, max_size : usize) {
+        let mut g = self.state.lock().unwrap();
+        g.max_size = max_size;
+
+        let over = g.alloc_size.saturating_sub(max_size);
+        self.dispose_idle(&mut g, over);
+        self.available.notify_all();
+    }
+
+    fn stats(&self) -> PoolStats {
+        let g = self.state.lock().unwrap();
+        let mut ret = g.stats.clone();
+        ret.idle = g.idle_count();
+        ret.in_use = g.alloc_size - ret.idle;
+        ret.waiting = g.waiters.len();
+        ret.max_size = g.max_size;
+        ret
+    }
+}
+
+impl<T,P> PoolMaintenance for InternalKeyedPool<T,P> where T : Send + 'static, P : Hash + Eq + Clone + Send + 'static {
+    fn maintain(&self) -> bool {
+        if self.state.lock().unwrap().closed {
+            return false;
+        }
+        self.evict_idle();
+        true
+    }
+}
+
+// the checked out item has to find its way back to the sub-pool of its key
+struct KeyedCommander<T,P> where T : 'static, P : Hash + Eq + Clone + 'static {
+    pool : Arc<InternalKeyedPool<T,P>>,
+    key : P
+}
+
+impl<T,P> PoolCommander<T> for KeyedCommander<T,P> where T : 'static, P : Hash + Eq + Clone + 'static {
+    fn dispose(&self, item : PoolEntry<T>) {
+        let mut g = self.pool.state.lock().unwrap();
+        let now = Instant::now();
+        g.stats.returns += 1;
+        g.stats.hold.record(now.saturating_duration_since(item.meta.last_used));
+        self.pool.dispose_entry(&mut g, &self.key, item);
+        self.pool.available.notify_all();
+    }
+
+    fn disposes(&self, items : Vec<PoolEntry<T>>) {
+        for item in items {
+            self.dispose(item);
+        }
+    }
+
+    fn restoration(&self, mut item : PoolEntry<T>) {
+        if !item.reset(&self.pool.policy) {
+            return self.dispose(item);
+        }
+        let mut g = self.pool.state.lock().unwrap();
+        self.pool.restore_entry(&mut g, &self.key, item);
+        self.pool.available.notify_all();
+    }
+
+    fn restorations(&self, items : Vec<PoolEntry<T>>) {
+        for item in items {
+            self.restoration(item);
+        }
+    }
+}
+
+pub struct KeyedPool<T,P> where T : 'static, P : Hash + Eq + Clone + 'static {
+    internal : Arc<InternalKeyedPool<T,P>>
+}
+
+impl<T,P> KeyedPool<T,P> where T : Send + 'static, P : Hash + Eq + Clone + Send + 'static {
+    // min_idle has no key to create items for, idle limits are enforced by the maintenance thread
+    pub fn new(name : String, gen : Box<dyn Fn(P) -> Result<T, CommonError> + Send + Sync>, max_per_key : usize, max_size : usize,
+               policy : PoolPolicy<T>) -> Result<Arc<Self>, CommonError> {
+        if max_per_key == 0 {
+            return CommonError::new(&CommonDefaultErrorKind::InvalidApiCall, format!("pool_name:{} - max_per_key is 0", name)).to_result();
+        }
+        if policy.min_idle > 0 {
+            return CommonError::new(&CommonDefaultErrorKind::NoSupport, format!("pool_name:{} - min_idle of a keyed pool", name)).to_result();
+        }
+
+        let needs_maintenance = policy.idle_timeout.is_some() || policy.max_lifetime.is_some();
+        let interval = policy.maintenance_interval;
+        let internal = Arc::new(InternalKeyedPool {
+            gen,
+            max_per_key,
+            policy,
+            state : Mutex::new(KeyedPoolState {
+                keys : HashMap::new(),
+                alloc_size : 0,
+                tick : 0,
+                waiters : PoolWaiters::new(),
+                max_size,
+                closed : false,
+                generation : 0,
+                stats : PoolStats::default()
+            }),
+            available : Condvar::new(),
+            pool_name : name
+        });
+
+        if needs_maintenance {
+            spawn_maintenance(&internal, internal.pool_name.as_str(), interval)?;
+        }
+        Ok(Arc::new(KeyedPool { internal }))
+    }
+
+    fn get(&self, param : P, timeout : Option<Duration>) -> Result<Box<dyn PoolItem<T>>, CommonError> {
+        let entry = self.internal.take(&param, timeout)?;
+        let command = Arc::new(KeyedCommander { pool : self.internal.clone(), key : param });
+        Ok(Box::new(PoolItemOwned::new(entry, command)))
+    }
+}
+
+impl<T,P> ThreadSafePool<T,P> for KeyedPool<T,P> where T : Send + 'static, P : Hash + Eq + Clone + Send + 'static {
+    fn get_owned(&self, param : P) -> Result<Box<dyn PoolItem<T>>, CommonError> {
+        self.get(param, Some(Duration::ZERO))
+    }
+
+    fn get_owned_timeout(&self, param : P, timeout : Duration) -> Result<Box<dyn PoolItem<T>>, CommonError> {
+        self.get(param, Some(timeout))
+    }
+
+    fn get_owned_wait(&self, param : P) -> Result<Box<dyn PoolItem<T>>, CommonError> {
+        self.get(param, None)
+    }
+
+    fn evict_idle(&self) -> usize {
+        self.internal.evict_idle()
+    }
+
+    fn stats(&self) -> PoolStats {
+        self.internal.stats()
+    }
+
+    fn close(&self, timeout : Duration) -> Result<(), CommonError> {
+        self.internal.close(timeout)
+    }
+
+    fn drain(&self) -> usize {
+        self.internal.drain()
+    }
+
+    fn resize(&self, max_size : usize) {
+        self.internal.resize(max_size)
+    }
+
+    fn alloc_size(&self) -> usize {
+        self.internal.state.lock().unwrap().alloc_size
+    }
+
+    fn max_size(&self) -> usize {
+        self.internal.state.lock().unwrap().max_size
+    }
+}
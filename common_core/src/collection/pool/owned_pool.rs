@@ -1,28 +1,147 @@
-use std::sync::Mutex;
+use std::sync::{Condvar, Mutex, MutexGuard};
 use std::sync::Arc;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::collections::VecDeque;
+use std::thread;
+use std::time::{Duration, Instant};
 
-use crate::collection::pool::{PoolItem,ThreadSafePool};
+use crate::collection::pool::{PoolHook, PoolItem, PoolItemMeta, PoolPolicy, PoolStats, ThreadSafePool};
 use common_err::{CommonError, gen::CommonDefaultErrorKind};
 
+pub(super) struct PoolEntry<T> {
+    pub(super) value : T,
+    pub(super) meta : PoolItemMeta,
+    // drain generation the item was created in
+    pub(super) generation : u64
+}
+
+impl<T> PoolEntry<T> {
+    pub(super) fn new(value : T, generation : u64) -> Self {
+        PoolEntry { value, meta : PoolItemMeta::new(), generation }
+    }
+
+    #[inline]
+    pub(super) fn call_hook(&self, hook : &Option<PoolHook<T>>) {
+        if let Some(hook) = hook {
+            hook(&self.value, &self.meta);
+        }
+    }
+
+    pub(super) fn is_expired(&self, policy : &PoolPolicy<T>, now : Instant) -> bool {
+        policy.is_idle_expired(&self.meta, now) || policy.is_retired(&self.meta, now)
+    }
+
+    // test-on-borrow may do a round trip, callers run it without holding a pool lock
+    pub(super) fn validate(&mut self, policy : &PoolPolicy<T>) -> bool {
+        match policy.validate.as_ref() {
+            Some(validate) => validate(&mut self.value),
+            None => true
+        }
+    }
+
+    pub(super) fn reset(&mut self, policy : &PoolPolicy<T>) -> bool {
+        match policy.reset.as_ref() {
+            Some(reset) => reset(&mut self.value),
+            None => true
+        }
+    }
+
+    pub(super) fn check_out(&mut self, now : Instant) {
+        self.meta.last_used = now;
+        self.meta.use_count += 1;
+    }
+}
+
 pub(super) trait PoolCommander<T> {
-    fn dispose(&self, item : T);
+    fn dispose(&self, item : PoolEntry<T>);
     #[allow(dead_code)]
-    fn disposes(&self, item : Vec<T>);
-    fn restoration(&self, item : T);
+    fn disposes(&self, item : Vec<PoolEntry<T>>);
+    fn restoration(&self, item : PoolEntry<T>);
     #[allow(dead_code)]
-    fn restorations(&self, item : Vec<T>);
+    fn restorations(&self, item : Vec<PoolEntry<T>>);
+}
+
+// blocked borrowers in arrival order, each with what it waits for
+pub(super) struct PoolWaiters<K> {
+    queue : VecDeque<(u64, K)>,
+    next_ticket : u64
+}
+
+impl<K> PoolWaiters<K> {
+    pub(super) fn new() -> Self {
+        PoolWaiters { queue : VecDeque::new(), next_ticket : 0 }
+    }
+
+    pub(super) fn push(&mut self, key : K) -> u64 {
+        let ticket = self.next_ticket;
+        self.next_ticket += 1;
+        self.queue.push_back((ticket, key));
+        ticket
+    }
+
+    pub(super) fn remove(&mut self, ticket : u64) {
+        self.queue.retain(|(t, _)| *t != ticket);
+    }
+
+    #[inline]
+    pub(super) fn len(&self) -> usize {
+        self.queue.len()
+    }
+
+    #[inline]
+    pub(super) fn is_empty(&self) -> bool {
+        self.queue.is_empty()
+    }
+
+    // a borrower is served once no earlier waiter could be, None stands for one not queued yet
+    pub(super) fn is_turn(&self, ticket : Option<u64>, can_serve : impl Fn(&K) -> bool) -> bool {
+        for (t, key) in self.queue.iter() {
+            if Some(*t) == ticket {
+                return true;
+            }
+            if can_serve(key) {
+                return false;
+            }
+        }
+        ticket.is_none()
+    }
+}
+
+// creations nobody asked for, pre-warming and replenishment
+pub(super) type PoolReplenish<T> = Box<dyn Fn() -> Result<T, CommonError> + Send + Sync>;
+
+// only implemented by the pool halves that are Send and Sync on their own
+pub(super) trait PoolMaintenance : Send + Sync + 'static {
+    // one round of eviction and replenishment, false once the pool is closed
+    fn maintain(&self) -> bool;
+}
+
+// the thread ends with the pool, it only keeps a weak reference between rounds
+pub(super) fn spawn_maintenance<M : PoolMaintenance>(pool : &Arc<M>, name : &'_ str, interval : Duration) -> Result<(), CommonError> {
+    let pool = Arc::downgrade(pool);
+    let spawned = thread::Builder::new().name(format!("pool-{}", name)).spawn(move || {
+        loop {
+            thread::sleep(interval);
+            match pool.upgrade() {
+                Some(pool) if pool.maintain() => {},
+                _ => break
+            }
+        }
+    });
+
+    spawned.map(|_| ()).map_err(|e| {
+        CommonError::new(&CommonDefaultErrorKind::SystemCallFail, format!("pool_name:{} - maintenance thread - {}", name, e))
+    })
 }
 
 pub struct PoolItemOwned<T> {
-    value : Option<T>,
+    value : Option<PoolEntry<T>>,
     is_use : AtomicBool,
     command : Arc<dyn PoolCommander<T>>
 }
 
 impl<T> PoolItemOwned<T> {
-    pub(super) fn new(value : T, command : Arc<dyn PoolCommander<T>>) -> Self {
+    pub(super) fn new(value : PoolEntry<T>, command : Arc<dyn PoolCommander<T>>) -> Self {
         PoolItemOwned {
             value : Some(value),
             is_use : AtomicBool::new(true),
@@ -43,10 +162,14 @@ impl<T> PoolItemOwned<T> {
 
 impl<T> PoolItem<T> for PoolItemOwned<T> {
     fn get_value<'b>(&'b mut self) -> &'b mut T {
-        let r :&'b mut T = self.value.as_mut().unwrap();
+        let r :&'b mut T = &mut self.value.as_mut().unwrap().value;
         r
     }
 
+    fn get_value_ref<'b>(&'b self) -> &'b T {
+        &self.value.as_ref().unwrap().value
+    }
+
     fn dispose(mut self : Box<Self>) {
         let used = self.is_use.load(Ordering::Relaxed);
         self.is_use.store(false, Ordering::Relaxed);
@@ -66,6 +189,10 @@ impl<T> PoolItem<T> for PoolItemOwned<T> {
             self.command.restoration(val.unwrap());
         }
     }
+
+    fn meta(&self) -> Option<PoolItemMeta> {
+        self.value.as_ref().map(|e| e.meta)
+    }
 }
 
 impl<T> Drop for PoolItemOwned<T> {
@@ -77,127 +204,551 @@ impl<T> Drop for PoolItemOwned<T> {
 }
 
 struct OwnedPoolState<T> {
-    items: VecDeque<T>,
-    alloc_size : usize
+    items: VecDeque<PoolEntry<T>>,
+    // items handed out, idle and still being created
+    alloc_size : usize,
+    // tickets of blocked borrowers, only the front one may take an item
+    waiters : PoolWaiters<()>,
+    max_size : usize,
+    closed : bool,
+    generation : u64,
+    // counters only, the gauges are filled in by stats()
+    stats : PoolStats
 }
 
-struct InternalOwnedPool<T,P> where T : 'static, P: 'static {
-    gen : Box<dyn Fn(P) -> Result<T, CommonError>>,
-    max_size : usize,
+impl<T> OwnedPoolState<T> {
+    #[inline]
+    fn can_take(&self) -> bool {
+        !self.items.is_empty() || self.alloc_size < self.max_size
+    }
+
+    // a returned item is dropped after close, drain or a shrinking resize
+    #[inline]
+    fn is_stale(&self, entry : &PoolEntry<T>) -> bool {
+        self.closed || entry.generation != self.generation || self.alloc_size > self.max_size
+    }
+}
+
+enum OwnedSlot<T> {
+    // an idle item, already checked out
+    Taken(PoolEntry<T>),
+    // an idle item still to be validated, it is counted by alloc_size but neither idle nor checked out
+    Idle(PoolEntry<T>),
+    // alloc_size counts the item of this drain generation, it is created once the lock is released
+    Reserved(u64)
+}
+
+// the half the maintenance thread and the pool items hold, the generator stays with OwnedPool
+struct InternalOwnedPool<T> where T : 'static {
+    policy : PoolPolicy<T>,
+    replenish : Option<PoolReplenish<T>>,
     state : Mutex<OwnedPoolState<T>>,
+    available : Condvar,
     pool_name : String
 }
 
-unsafe impl<T,P> Sync for InternalOwnedPool<T,P> {}
-unsafe impl<T,P> Send for InternalOwnedPool<T,P> {}
-
-impl<T,P> InternalOwnedPool<T,P> where T : 'static, P: 'static {
-    pub fn new(name : String, gen : Box<dyn Fn(P) -> Result<T, CommonError>>, max_size : usize) -> Arc<Self> {
+impl<T> InternalOwnedPool<T> where T : 'static {
+    fn new(name : String, max_size : usize, policy : PoolPolicy<T>, replenish : Option<PoolReplenish<T>>) -> Arc<Self> {
         Arc::new(InternalOwnedPool {
-            gen,
-            state : Mutex::new(OwnedPoolState { items: VecDeque::new(), alloc_size: 0 }),
-            max_size,
+            policy,
+            replenish,
+            state : Mutex::new(OwnedPoolState {
+                items: VecDeque::new(),
+                alloc_size: 0,
+                waiters : PoolWaiters::new(),
+                max_size,
+                closed : false,
+                generation : 0,
+                stats : PoolStats::default()
+            }),
+            available : Condvar::new(),
             pool_name : name
         })
     }
 
-    fn new_alloc_if_len_zeros(&self, ps : Vec<P>) ->Result<Vec<T>,CommonError> {
+    fn acquire_now(&self, started : Instant) -> Result<OwnedSlot<T>, CommonError> {
         let mut g = self.state.lock().unwrap();
-        let l = ps.len();
-
-        for p in ps {
-            if g.items.len() < l {
-                if g.alloc_size < self.max_size {
-                    let gen_item = (self.gen)(p);
-                if gen_item.is_err() {
-                    let err_msg = gen_item.err().unwrap();
-                    return CommonError::new(&CommonDefaultErrorKind::InvalidApiCall,
-                                            format!("pool_name:{}\n{}", self.pool_name, err_msg)).to_result()
-                }
-                g.items.push_back(gen_item?);
+        self.check_open(&g)?;
+
+        // idle items are promised to the blocked borrowers
+        if !g.waiters.is_empty() {
+            return CommonError::new(&CommonDefaultErrorKind::LimitSize, format!("pool_name:{} - {} waiters", self.pool_name, g.waiters.len())).to_result()
+        }
+
+        match self.take_slot(&mut g, started) {
+            Some(slot) => Ok(slot),
+            None => CommonError::new(&CommonDefaultErrorKind::LimitSize, format!("pool_name:{}", self.pool_name)).to_result()
+        }
+    }
+
+    fn dispose_entry(&self, g : &mut MutexGuard<'_, OwnedPoolState<T>>, entry : PoolEntry<T>) {
+        g.alloc_size -= 1;
+        g.stats.disposed += 1;
+        entry.call_hook(&self.policy.hooks.on_dispose);
+    }
+
+    fn closed_error(&self) -> CommonError {
+        CommonError::new(&CommonDefaultErrorKind::InvalidApiCall, format!("pool_name:{} - closed", self.pool_name))
+    }
+
+    fn check_open(&self, g : &MutexGuard<'_, OwnedPoolState<T>>) -> Result<(), CommonError> {
+        if g.closed {
+            return Err(self.closed_error());
+        }
+        Ok(())
+    }
+
+    fn dispose_idle(&self, g : &mut MutexGuard<'_, OwnedPoolState<T>>, limit : usize) -> usize {
+        let mut disposed = 0;
+        while disposed < limit {
+            let Some(entry) = g.items.pop_front() else {
+                break;
+            };
+            self.dispose_entry(g, entry);
+            disposed += 1;
+        }
+        disposed
+    }
+
+    fn create_failed(&self, g : &mut MutexGuard<'_, OwnedPoolState<T>>, err : &CommonError) {
+        g.stats.create_failed += 1;
+        if let Some(hook) = self.policy.hooks.on_create_failed.as_ref() {
+            hook(err);
+        }
+    }
+
+    // the generator of a reserved slot returned, a failure gives the slot back
+    fn settle_created(&self, g : &mut MutexGuard<'_, OwnedPoolState<T>>, created : Result<T, CommonError>, generation : u64,
+                      context : &'_ str) -> Result<PoolEntry<T>, CommonError> {
+        match created {
+            Ok(item) => {
+                g.stats.created += 1;
+                let entry = PoolEntry::new(item, generation);
+                entry.call_hook(&self.policy.hooks.on_create);
+                Ok(entry)
+            },
+            Err(e) => {
+                g.alloc_size -= 1;
+                let err = CommonError::extend(&CommonDefaultErrorKind::InvalidApiCall, format!("pool_name:{}{}", self.pool_name, context), e);
+                self.create_failed(g, &err);
+                self.available.notify_all();
+                Err(err)
+            }
+        }
+    }
+
+    // hands out the item created for a reserved slot unless the pool closed meanwhile
+    fn settle_reserved(&self, created : Result<T, CommonError>, generation : u64, started : Instant) -> Result<PoolEntry<T>, CommonError> {
+        let mut g = self.state.lock().unwrap();
+        let entry = self.settle_created(&mut g, created, generation, "")?;
+
+        if g.closed {
+            self.dispose_entry(&mut g, entry);
+            self.available.notify_all();
+            return Err(self.closed_error());
+        }
+        Ok(self.check_out(&mut g, entry, started))
+    }
+
+    // creates one idle item while below min_idle, the slot is reserved so borrowers are not blocked meanwhile
+    fn replenish_one(&self) -> bool {
+        let Some(replenish) = self.replenish.as_ref() else {
+            return false;
+        };
+
+        let generation = {
+            let mut g = self.state.lock().unwrap();
+            if g.closed || g.items.len() >= self.policy.min_idle || g.alloc_size >= g.max_size {
+                return false;
+            }
+            g.alloc_size += 1;
+            g.generation
+        };
+
+        let created = replenish();
+        let mut g = self.state.lock().unwrap();
+        let Ok(entry) = self.settle_created(&mut g, created, generation, " - replenish") else {
+            return false;
+        };
+
+        if g.is_stale(&entry) {
+            self.dispose_entry(&mut g, entry);
+            self.available.notify_all();
+            return false;
+        }
+
+        g.items.push_back(entry);
+        self.available.notify_all();
+        true
+    }
+
+    fn fill_idle(&self) -> usize {
+        let mut created = 0;
+        while self.replenish_one() {
+            created += 1;
+        }
+        created
+    }
+
+    fn needs_maintenance(&self) -> bool {
+        self.policy.min_idle > 0 || self.policy.idle_timeout.is_some() || self.policy.max_lifetime.is_some()
+    }
+
+    fn check_in(&self, g : &mut MutexGuard<'_, OwnedPoolState<T>>, entry : &PoolEntry<T>, now : Instant) {
+        g.stats.returns += 1;
+        g.stats.hold.record(now.saturating_duration_since(entry.meta.last_used));
+    }
+
+    fn check_out(&self, g : &mut MutexGuard<'_, OwnedPoolState<T>>, mut entry : PoolEntry<T>, started : Instant) -> PoolEntry<T> {
+        let now = Instant::now();
+        entry.check_out(now);
+        g.stats.checkouts += 1;
+        g.stats.wait.record(now.saturating_duration_since(started));
+        entry.call_hook(&self.policy.hooks.on_checkout);
+        entry
+    }
+
+    // checks out a usable idle item or reserves room for a new one, None when neither is possible
+    fn take_slot(&self, g : &mut MutexGuard<'_, OwnedPoolState<T>>, started : Instant) -> Option<OwnedSlot<T>> {
+        let now = Instant::now();
+        let mut evicted = false;
+        let mut taken = None;
+
+        while let Some(entry) = g.items.pop_front() {
+            if !entry.is_expired(&self.policy, now) {
+                taken = Some(entry);
+                break;
+            }
+            self.dispose_entry(g, entry);
+            evicted = true;
+        }
+
+        if evicted {
+            self.available.notify_all();
+        }
+
+        match taken {
+            Some(entry) if self.policy.validate.is_some() => Some(OwnedSlot::Idle(entry)),
+            Some(entry) => Some(OwnedSlot::Taken(self.check_out(g, entry, started))),
+            None if g.alloc_size < g.max_size => {
                 g.alloc_size += 1;
-                } else {
-                    return CommonError::new(&CommonDefaultErrorKind::LimitSize, format!("pool_name:{}", self.pool_name)).to_result()
+                Some(OwnedSlot::Reserved(g.generation))
+            },
+            None => None
+        }
+    }
+
+    // validates an idle item after the lock was released, a failed one is disposed and the borrower takes the next slot
+    fn validate_idle(&self, mut entry : PoolEntry<T>, started : Instant) -> Result<OwnedSlot<T>, CommonError> {
+        let valid = entry.validate(&self.policy);
+        let mut g = self.state.lock().unwrap();
+
+        if valid && !g.is_stale(&entry) {
+            return Ok(OwnedSlot::Taken(self.check_out(&mut g, entry, started)));
+        }
+
+        self.dispose_entry(&mut g, entry);
+        self.available.notify_all();
+        self.check_open(&g)?;
+
+        match self.take_slot(&mut g, started) {
+            Some(slot) => Ok(slot),
+            None => CommonError::new(&CommonDefaultErrorKind::LimitSize, format!("pool_name:{}", self.pool_name)).to_result()
+        }
+    }
+
+    fn restore_entry(&self, g : &mut MutexGuard<'_, OwnedPoolState<T>>, mut entry : PoolEntry<T>) {
+        let now = Instant::now();
+        self.check_in(g, &entry, now);
+
+        if g.is_stale(&entry) || self.policy.is_retired(&entry.meta, now) {
+            self.dispose_entry(g, entry);
+            return;
+        }
+
+        entry.meta.last_used = now;
+        entry.call_hook(&self.policy.hooks.on_return);
+        g.items.push_back(entry);
+    }
+
+    // None waits without limit
+    fn acquire_wait(&self, started : Instant, timeout : Option<Duration>) -> Result<OwnedSlot<T>, CommonError> {
+        let deadline = timeout.map(|t| started + t);
+        let mut g = self.state.lock().unwrap();
+        self.check_open(&g)?;
+
+        if g.waiters.is_empty() {
+            if let Some(slot) = self.take_slot(&mut g, started) {
+                return Ok(slot);
+            }
+        }
+
+        let ticket = g.waiters.push(());
+
+        loop {
+            if g.closed {
+                g.waiters.remove(ticket);
+                return Err(self.closed_error());
+            }
+
+            if g.can_take() && g.waiters.is_turn(Some(ticket), |_| true) {
+                if let Some(slot) = self.take_slot(&mut g, started) {
+                    g.waiters.remove(ticket);
+                    // the next waiter may be served too
+                    self.available.notify_all();
+                    return Ok(slot);
                 }
             }
-        }   
 
-        let mut ret = Vec::new();
+            g = match deadline {
+                None => self.available.wait(g).unwrap(),
+                Some(deadline) => {
+                    let now = Instant::now();
+                    if now >= deadline {
+                        g.waiters.remove(ticket);
+                        g.stats.wait_timeouts += 1;
+                        self.available.notify_all();
+                        return CommonError::new(&CommonDefaultErrorKind::LimitSize,
+                                                format!("pool_name:{} - wait timeout {:?}", self.pool_name, timeout.unwrap())).to_result()
+                    }
+                    self.available.wait_timeout(g, deadline - now).unwrap().0
+                }
+            };
+        }
+    }
+
+    fn evict_idle(&self) -> usize {
+        let now = Instant::now();
+        let mut g = self.state.lock().unwrap();
+        let items = std::mem::take(&mut g.items);
+        let mut evicted = 0;
+
+        for entry in items {
+            if self.policy.is_idle_expired(&entry.meta, now) || self.policy.is_retired(&entry.meta, now) {
+                self.dispose_entry(&mut g, entry);
+                evicted += 1;
+            } else {
+                g.items.push_back(entry);
+            }
+        }
 
-        for _ in 0..l {
-            let i = g.items.pop_front().unwrap();
-            ret.push(i);
+        if evicted > 0 {
+            self.available.notify_all();
         }
-        Ok(ret)
+        evicted
     }
-    #[inline]
-    fn new_alloc_if_len_zero(&self, p : P) ->Result<T,CommonError> {
-        let v = vec![p];
-        let mut r = self.new_alloc_if_len_zeros(v)?;
-        Ok(r.pop().unwrap())
+
+    fn close(&self, timeout : Duration) -> Result<(), CommonError> {
+        let deadline = Instant::now() + timeout;
+        let mut g = self.state.lock().unwrap();
+
+        g.closed = true;
+        self.dispose_idle(&mut g, usize::MAX);
+        self.available.notify_all();
+
+        while g.alloc_size > 0 {
+            let now = Instant::now();
+            if now >= deadline {
+                return CommonError::new(&CommonDefaultErrorKind::LimitSize,
+                                        format!("pool_name:{} - close timeout, {} items still checked out", self.pool_name, g.alloc_size)).to_result();
+            }
+            g = self.available.wait_timeout(g, deadline - now).unwrap().0;
+        }
+        Ok(())
     }
 
-    pub(super) fn get_owned(self : &Arc<Self>, param : P) -> Result<Box<dyn PoolItem<T>>, CommonError> {
-        let item = self.new_alloc_if_len_zero(param)?;
-        Ok(Box::new(PoolItemOwned::new(item, self.clone())))
+    fn drain(&self) -> usize {
+        let mut g = self.state.lock().unwrap();
+        g.generation += 1;
+        let disposed = self.dispose_idle(&mut g, usize::MAX);
+        self.available.notify_all();
+        disposed
     }
 
-    pub(super) fn alloc_size(&self) -> usize {
+    fn resize(&self, max_size : usize) {
+        let mut g = self.state.lock().unwrap();
+        g.max_size = max_size;
+
+        let over = g.alloc_size.saturating_sub(max_size);
+        self.dispose_idle(&mut g, over);
+        self.available.notify_all();
+    }
+
+    fn stats(&self) -> PoolStats {
+        let g = self.state.lock().unwrap();
+        let mut ret = g.stats.clone();
+        ret.idle = g.items.len();
+        ret.in_use = g.alloc_size - g.items.len();
+        ret.waiting = g.waiters.len();
+        ret.max_size = g.max_size;
+        ret
+    }
+
+    fn alloc_size(&self) -> usize {
         let g = self.state.lock().unwrap();
         let ret = g.alloc_size;
         drop(g);
         ret
     }
-    pub(super) fn max_size(&self) -> usize {
-        self.max_size
+    fn max_size(&self) -> usize {
+        self.state.lock().unwrap().max_size
     }
 }
 
-impl<T,P> PoolCommander<T> for InternalOwnedPool<T,P> {
-    fn dispose(&self, _ : T) {
-        let mut g = self.state.lock().unwrap();
-        g.alloc_size -= 1;
+impl<T> PoolMaintenance for InternalOwnedPool<T> where T : Send + 'static {
+    fn maintain(&self) -> bool {
+        if self.state.lock().unwrap().closed {
+            return false;
+        }
+        self.evict_idle();
+        self.fill_idle();
+        true
     }
+}
 
-    fn disposes(&self, v : Vec<T>) {
+impl<T> PoolCommander<T> for InternalOwnedPool<T> {
+    fn dispose(&self, item : PoolEntry<T>) {
         let mut g = self.state.lock().unwrap();
-        g.alloc_size -= v.len();
+        self.check_in(&mut g, &item, Instant::now());
+        self.dispose_entry(&mut g, item);
+        self.available.notify_all();
     }
 
-    fn restoration(&self, item : T) {
+    fn disposes(&self, v : Vec<PoolEntry<T>>) {
         let mut g = self.state.lock().unwrap();
-        g.items.push_back(item);
+        let now = Instant::now();
+        for item in v {
+            self.check_in(&mut g, &item, now);
+            self.dispose_entry(&mut g, item);
+        }
+        self.available.notify_all();
     }
 
-    fn restorations(&self, mut items : Vec<T>) {
+    fn restoration(&self, mut item : PoolEntry<T>) {
+        if !item.reset(&self.policy) {
+            return self.dispose(item);
+        }
         let mut g = self.state.lock().unwrap();
-        let l = g.items.len();
-        for _ in 0..l {
-            g.items.push_back(items.pop().unwrap());
+        self.restore_entry(&mut g, item);
+        self.available.notify_all();
+    }
+
+    fn restorations(&self, items : Vec<PoolEntry<T>>) {
+        for item in items {
+            self.restoration(item);
         }
     }
 }
 
 pub struct OwnedPool<T,P> where T : 'static, P: 'static {
-    internal : Arc<InternalOwnedPool<T,P>>
+    internal : Arc<InternalOwnedPool<T>>,
+    gen : Box<dyn Fn(P) -> Result<T, CommonError>>,
+    // creations run one at a time unless the generator is known to be Sync
+    gen_lock : Option<Mutex<()>>
 }
 
 unsafe impl<T,P> Sync for OwnedPool<T,P> {}
 unsafe impl<T,P> Send for OwnedPool<T,P> {}
 
 impl<T,P> OwnedPool<T,P> where T : 'static, P: 'static {
+    // nothing runs in the background, neither the generator nor T has to be Send
     pub fn new(name : String, gen : Box<dyn Fn(P) -> Result<T, CommonError>>, max_size : usize) -> Arc<Self> {
-        let internal = InternalOwnedPool::new(name,gen,max_size);
+        Arc::new(OwnedPool {
+            internal : InternalOwnedPool::new(name, max_size, PoolPolicy::default(), None),
+            gen,
+            gen_lock : Some(Mutex::new(()))
+        })
+    }
+
+    fn create(&self, param : P) -> Result<T, CommonError> {
+        match self.gen_lock.as_ref() {
+            Some(lock) => {
+                let _g = lock.lock().unwrap();
+                (self.gen)(param)
+            },
+            None => (self.gen)(param)
+        }
+    }
+
+    fn hand_out(&self, mut slot : OwnedSlot<T>, param : P, started : Instant) -> Result<Box<dyn PoolItem<T>>, CommonError> {
+        let entry = loop {
+            slot = match slot {
+                OwnedSlot::Taken(entry) => break entry,
+                OwnedSlot::Idle(entry) => self.internal.validate_idle(entry, started)?,
+                OwnedSlot::Reserved(generation) => {
+                    let created = self.create(param);
+                    break self.internal.settle_reserved(created, generation, started)?;
+                }
+            };
+        };
+        Ok(Box::new(PoolItemOwned::new(entry, self.internal.clone())))
+    }
+
+    fn get(&self, param : P, timeout : Option<Duration>) -> Result<Box<dyn PoolItem<T>>, CommonError> {
+        let started = Instant::now();
+        let slot = self.internal.acquire_wait(started, timeout)?;
+        self.hand_out(slot, param, started)
+    }
+}
+
+impl<T,P> OwnedPool<T,P> where T : Send + 'static, P : Default + 'static {
+    // background creations call the generator with P::default() on the maintenance thread
+    pub fn with_policy(name : String, gen : Box<dyn Fn(P) -> Result<T, CommonError> + Send + Sync>, max_size : usize,
+                       policy : PoolPolicy<T>) -> Arc<Self> {
+        let gen = Arc::new(gen);
+        let replenish_gen = gen.clone();
+        let internal = InternalOwnedPool::new(name, max_size, policy, Some(Box::new(move || replenish_gen(P::default()))));
+
+        internal.fill_idle();
+        if internal.needs_maintenance() {
+            if let Err(e) = spawn_maintenance(&internal, internal.pool_name.as_str(), internal.policy.maintenance_interval) {
+                if let Some(hook) = internal.policy.hooks.on_create_failed.as_ref() {
+                    hook(&e);
+                }
+            }
+        }
 
         Arc::new(OwnedPool {
-            internal
+            internal,
+            gen : Box::new(move |p| gen(p)),
+            gen_lock : None
         })
     }
 }
 
 impl <T,P> ThreadSafePool<T,P> for OwnedPool<T,P> where T : 'static, P: 'static {
     fn get_owned(&self, param : P) -> Result<Box<dyn PoolItem<T>>, CommonError> {
-        self.internal.get_owned(param)
+        let started = Instant::now();
+        let slot = self.internal.acquire_now(started)?;
+        self.hand_out(slot, param, started)
+    }
+
+    fn get_owned_timeout(&self, param : P, timeout : Duration) -> Result<Box<dyn PoolItem<T>>, CommonError> {
+        self.get(param, Some(timeout))
+    }
+
+    fn get_owned_wait(&self, param : P) -> Result<Box<dyn PoolItem<T>>, CommonError> {
+        self.get(param, None)
+    }
+
+    fn evict_idle(&self) -> usize {
+        self.internal.evict_idle()
+    }
+
+    fn stats(&self) -> PoolStats {
+        self.internal.stats()
+    }
+
+    fn close(&self, timeout : Duration) -> Result<(), CommonError> {
+        self.internal.close(timeout)
+    }
+
+    fn drain(&self) -> usize {
+        self.internal.drain()
+    }
+
+    fn resize(&self, max_size : usize) {
+        self.internal.resize(max_size)
     }
 
     fn alloc_size(&self) -> usize {
@@ -208,4 +759,3 @@ impl <T,P> ThreadSafePool<T,P> for OwnedPool<T,P> where T : 'static, P: 'static
         self.internal.max_size()
     }
 }
-
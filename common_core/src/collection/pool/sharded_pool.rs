@@ -0,0 +1,533 @@
+use std::collections::VecDeque;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::collection::pool::{PoolItem, PoolPolicy, PoolStats, ThreadSafePool};
+use crate::collection::pool::owned_pool::{spawn_maintenance, PoolCommander, PoolEntry, PoolItemOwned, PoolMaintenance, PoolReplenish};
+use common_err::{CommonError, gen::CommonDefaultErrorKind};
+
+static NEXT_SHARD_HINT : AtomicUsize = AtomicUsize::new(0);
+
+thread_local! {
+    // threads are spread round robin, a thread keeps its home shard for life
+    static SHARD_HINT : usize = NEXT_SHARD_HINT.fetch_add(1, Ordering::Relaxed);
+}
+
+struct PoolShard<T> {
+    items : VecDeque<PoolEntry<T>>,
+    // counters of the checkouts and returns done through this shard
+    stats : PoolStats
+}
+
+enum ShardedSlot<T> {
+    // an idle item popped from the shard, counted by alloc_size but neither idle nor checked out
+    Idle(usize, PoolEntry<T>),
+    // alloc_size counts the item of this drain generation, it is created by the borrower
+    Reserved(u64)
+}
+
+// the half the maintenance thread and the pool items hold, the generator stays with ShardedPool
+struct InternalShardedPool<T> where T : 'static {
+    policy : PoolPolicy<T>,
+    replenish : Option<PoolReplenish<T>>,
+    shards : Vec<Mutex<PoolShard<T>>>,
+    alloc_size : AtomicUsize,
+    max_size : AtomicUsize,
+    closed : AtomicBool,
+    generation : AtomicU64,
+    waiting : AtomicUsize,
+    wait_lock : Mutex<()>,
+    available : Condvar,
+    pool_name : String
+}
+
+impl<T> InternalShardedPool<T> where T : 'static {
+    fn new(name : String, max_size : usize, shards : usize, policy : PoolPolicy<T>, replenish : Option<PoolReplenish<T>>) -> Arc<Self> {
+        let shards = match shards {
+            0 => thread::available_parallelism().map(|n| n.get()).unwrap_or(1),
+            n => n
+        };
+
+        Arc::new(InternalShardedPool {
+            policy,
+            replenish,
+            shards : (0..shards).map(|_| Mutex::new(PoolShard { items : VecDeque::new(), stats : PoolStats::default() })).collect(),
+            alloc_size : AtomicUsize::new(0),
+            max_size : AtomicUsize::new(max_size),
+            closed : AtomicBool::new(false),
+            generation : AtomicU64::new(0),
+            waiting : AtomicUsize::new(0),
+            wait_lock : Mutex::new(()),
+            available : Condvar::new(),
+            pool_name : name
+        })
+    }
+
+    #[inline]
+    fn home(&self) -> usize {
+        SHARD_HINT.with(|h| *h) % self.shards.len()
+    }
+
+    #[inline]
+    fn lock_shard(&self, idx : usize) -> MutexGuard<'_, PoolShard<T>> {
+        self.shards[idx].lock().unwrap()
+    }
+
+    fn closed_error(&self) -> CommonError {
+        CommonError::new(&CommonDefaultErrorKind::InvalidApiCall, format!("pool_name:{} - closed", self.pool_name))
+    }
+
+    fn notify(&self) {
+        if self.waiting.load(Ordering::SeqCst) > 0 {
+            let _g = self.wait_lock.lock().unwrap();
+            self.available.notify_all();
+        }
+    }
+
+    fn reserve(&self) -> bool {
+        self.alloc_size.fetch_update(Ordering::SeqCst, Ordering::SeqCst, |a| {
+            if a < self.max_size.load(Ordering::SeqCst) { Some(a + 1) } else { None }
+        }).is_ok()
+    }
+
+    fn dispose_entry(&self, shard : &mut PoolShard<T>, entry : PoolEntry<T>) {
+        self.alloc_size.fetch_sub(1, Ordering::SeqCst);
+        shard.stats.disposed += 1;
+        entry.call_hook(&self.policy.hooks.on_dispose);
+    }
+
+    // pops an unexpired idle item, the home shard first and then the others
+    fn pop_unexpired(&self, home : usize, now : Instant) -> Option<(usize, PoolEntry<T>)> {
+        let len = self.shards.len();
+        for step in 0..len {
+            let idx = (home + step) % len;
+            let mut shard = self.lock_shard(idx);
+            while let Some(entry) = shard.items.pop_front() {
+                if !entry.is_expired(&self.policy, now) {
+                    return Some((idx, entry));
+                }
+                self.dispose_entry(&mut shard, entry);
+            }
+        }
+        None
+    }
+
+    // only pops or reserves, waiters run it under wait_lock
+    fn try_slot(&self, home : usize) -> Result<Option<ShardedSlot<T>>, CommonError> {
+        if self.closed.load(Ordering::SeqCst) {
+            return Err(self.closed_error());
+        }
+
+        if let Some((idx, entry)) = self.pop_unexpired(home, Instant::now()) {
+            return Ok(Some(ShardedSlot::Idle(idx, entry)));
+        }
+        if self.reserve() {
+            return Ok(Some(ShardedSlot::Reserved(self.generation.load(Ordering::SeqCst))));
+        }
+        Ok(None)
+    }
+
+    // None waits without limit, Some(ZERO) does not wait at all; blocked borrowers are not served in order.
+    // the waiter keeps wait_lock while trying so a return can not slip in between try and wait
+    fn acquire(&self, started : Instant, timeout : Option<Duration>) -> Result<ShardedSlot<T>, CommonError> {
+        let home = self.home();
+        if let Some(slot) = self.try_slot(home)? {
+            return Ok(slot);
+        }
+
+        let deadline = timeout.map(|t| started + t);
+        if deadline.is_some_and(|d| Instant::now() >= d) {
+            return CommonError::new(&CommonDefaultErrorKind::LimitSize, format!("pool_name:{}", self.pool_name)).to_result();
+        }
+
+        let mut g = self.wait_lock.lock().unwrap();
+        self.waiting.fetch_add(1, Ordering::SeqCst);
+
+        let ret = loop {
+            match self.try_slot(home) {
+                Ok(Some(slot)) => break Ok(slot),
+                Ok(None) => {},
+                Err(e) => break Err(e)
+            }
+
+            let now = Instant::now();
+            g = match deadline {
+                Some(deadline) if now >= deadline => {
+                    self.lock_shard(home).stats.wait_timeouts += 1;
+                    break CommonError::new(&CommonDefaultErrorKind::LimitSize, format!("pool_name:{} - wait timeout {:?}", self.pool_name, timeout.unwrap())).to_result();
+                },
+                Some(deadline) => self.available.wait_timeout(g, deadline - now).unwrap().0,
+                None => self.available.wait(g).unwrap()
+            };
+        };
+
+        self.waiting.fetch_sub(1, Ordering::SeqCst);
+        drop(g);
+        ret
+    }
+
+    fn check_out(&self, idx : usize, mut entry : PoolEntry<T>, started : Instant) -> PoolEntry<T> {
+        let now = Instant::now();
+        entry.check_out(now);
+        let mut shard = self.lock_shard(idx);
+        shard.stats.checkouts += 1;
+        shard.stats.wait.record(now.saturating_duration_since(started));
+        entry.call_hook(&self.policy.hooks.on_checkout);
+        entry
+    }
+
+    // validates a popped item with no lock held, None once it failed and was disposed
+    fn check_out_idle(&self, idx : usize, mut entry : PoolEntry<T>, started : Instant) -> Option<PoolEntry<T>> {
+        if entry.validate(&self.policy) && !self.is_stale(&entry) {
+            return Some(self.check_out(idx, entry, started));
+        }
+
+        self.dispose_entry(&mut self.lock_shard(idx), entry);
+        self.notify();
+        None
+    }
+
+    // the generator of a reserved slot returned, a failure gives the slot back
+    fn settle_created(&self, created : Result<T, CommonError>, generation : u64, context : &'_ str) -> Result<(usize, PoolEntry<T>), CommonError> {
+        let home = self.home();
+        let mut shard = self.lock_shard(home);
+        match created {
+            Ok(item) => {
+                shard.stats.created += 1;
+                let entry = PoolEntry::new(item, generation);
+                entry.call_hook(&self.policy.hooks.on_create);
+                Ok((home, entry))
+            },
+            Err(e) => {
+                self.alloc_size.fetch_sub(1, Ordering::SeqCst);
+                shard.stats.create_failed += 1;
+                let err = CommonError::extend(&CommonDefaultErrorKind::InvalidApiCall, format!("pool_name:{}{}", self.pool_name, context), e);
+                if let Some(hook) = self.policy.hooks.on_create_failed.as_ref() {
+                    hook(&err);
+                }
+                drop(shard);
+                self.notify();
+                Err(err)
+            }
+        }
+    }
+
+    // hands out the item created for a reserved slot unless the pool closed meanwhile
+    fn settle_reserved(&self, created : Result<T, CommonError>, generation : u64, started : Instant) -> Result<PoolEntry<T>, CommonError> {
+        let (idx, entry) = self.settle_created(created, generation, "")?;
+
+        if self.closed.load(Ordering::SeqCst) {
+            self.dispose_entry(&mut self.lock_shard(idx), entry);
+            self.notify();
+            return Err(self.closed_error());
+        }
+        Ok(self.check_out(idx, entry, started))
+    }
+
+    fn idle_count(&self) -> usize {
+        self.shards.iter().map(|shard| shard.lock().unwrap().items.len()).sum()
+    }
+
+    // creates one idle item while below min_idle, it goes to the shard holding the fewest
+    fn replenish_one(&self) -> bool {
+        let Some(replenish) = self.replenish.as_ref() else {
+            return false;
+        };
+
+        if self.closed.load(Ordering::SeqCst) || self.idle_count() >= self.policy.min_idle || !self.reserve() {
+            return false;
+        }
+
+        let generation = self.generation.load(Ordering::SeqCst);
+        let Ok((_, entry)) = self.settle_created(replenish(), generation, " - replenish") else {
+            return false;
+        };
+
+        let idx = (0..self.shards.len()).min_by_key(|idx| self.lock_shard(*idx).items.len()).unwrap_or(0);
+        let mut shard = self.lock_shard(idx);
+        if self.is_stale(&entry) {
+            self.dispose_entry(&mut shard, entry);
+            drop(shard);
+            self.notify();
+            return false;
+        }
+
+        shard.items.push_back(entry);
+        drop(shard);
+        self.notify();
+        true
+    }
+
+    fn fill_idle(&self) -> usize {
+        let mut created = 0;
+        while self.replenish_one() {
+            created += 1;
+        }
+        created
+    }
+
+    fn needs_maintenance(&self) -> bool {
+        self.policy.min_idle > 0 || self.policy.idle_timeout.is_some() || self.policy.max_lifetime.is_some()
+    }
+
+    fn is_stale(&self, entry : &PoolEntry<T>) -> bool {
+        self.closed.load(Ordering::SeqCst)
+            || entry.generation != self.generation.load(Ordering::SeqCst)
+            || self.alloc_size.load(Ordering::SeqCst) > self.max_size.load(Ordering::SeqCst)
+    }
+
+    fn check_in(&self, mut entry : PoolEntry<T>, dispose : bool) {
+        let now = Instant::now();
+        let mut shard = self.lock_shard(self.home());
+        shard.stats.returns += 1;
+        shard.stats.hold.record(now.saturating_duration_since(entry.meta.last_used));
+
+        if dispose || self.is_stale(&entry) || self.policy.is_retired(&entry.meta, now) {
+            self.dispose_entry(&mut shard, entry);
+        } else {
+            entry.meta.last_used = now;
+            entry.call_hook(&self.policy.hooks.on_return);
+            shard.items.push_back(entry);
+        }
+        drop(shard);
+        self.notify();
+    }
+
+    // disposes idle items matching the filter, at most limit of them
+    fn dispose_idle_where(&self, limit : usize, filter : impl Fn(&PoolEntry<T>) -> bool) -> usize {
+        let mut disposed = 0;
+        for idx in 0..self.shards.len() {
+            let mut shard = self.lock_shard(idx);
+            let items = std::mem::take(&mut shard.items);
+            for entry in items {
+                if disposed < limit && filter(&entry) {
+                    self.dispose_entry(&mut shard, entry);
+                    disposed += 1;
+                } else {
+                    shard.items.push_back(entry);
+                }
+            }
+        }
+
+        if disposed > 0 {
+            self.notify();
+        }
+        disposed
+    }
+
+    fn evict_idle(&self) -> usize {
+        let now = Instant::now();
+        self.dispose_idle_where(usize::MAX, |e| e.is_expired(&self.policy, now))
+    }
+
+    fn close(&self, timeout : Duration) -> Result<(), CommonError> {
+        let deadline = Instant::now() + timeout;
+        self.closed.store(true, Ordering::SeqCst);
+        self.dispose_idle_where(usize::MAX, |_| true);
+
+        {
+            let _g = self.wait_lock.lock().unwrap();
+            self.available.notify_all();
+        }
+
+        // returns only notify when someone waits, count the closer in
+        let mut g = self.wait_lock.lock().unwrap();
+        self.waiting.fetch_add(1, Ordering::SeqCst);
+        let ret = loop {
+            let alloc_size = self.alloc_size.load(Ordering::SeqCst);
+            if alloc_size == 0 {
+                break Ok(());
+            }
+
+            let now = Instant::now();
+            if now >= deadline {
+                break CommonError::new(&CommonDefaultErrorKind::LimitSize,
+                                       format!("pool_name:{} - close timeout, {} items still checked out", self.pool_name, alloc_size)).to_result();
+            }
+            g = self.available.wait_timeout(g, deadline - now).unwrap().0;
+        };
+        self.waiting.fetch_sub(1, Ordering::SeqCst);
+        ret
+    }
+
+    fn drain(&self) -> usize {
+        self.generation.fetch_add(1, Ordering::SeqCst);
+        self.dispose_idle_where(usize::MAX, |_| true)
+    }
+
+    fn resize(&self, max_size : usize) {
+        self.max_size.store(max_size, Ordering::SeqCst);
+        let over = self.alloc_size.load(Ordering::SeqCst).saturating_sub(max_size);
+        self.dispose_idle_where(over, |_| true);
+
+        let _g = self.wait_lock.lock().unwrap();
+        self.available.notify_all();
+    }
+
+    fn stats(&self) -> PoolStats {
+        let mut ret = PoolStats::default();
+        for shard in self.shards.iter() {
+            let shard = shard.lock().unwrap();
+            ret.merge_counters(&shard.stats);
+            ret.idle += shard.items.len();
+        }
+
+        ret.in_use = self.alloc_size.load(Ordering::SeqCst).saturating_sub(ret.idle);
+        ret.waiting = self.waiting.load(Ordering::SeqCst);
+        ret.max_size = self.max_size.load(Ordering::SeqCst);
+        ret
+    }
+}
+
+impl<T> PoolMaintenance for InternalShardedPool<T> where T : Send + 'static {
+    fn maintain(&self) -> bool {
+        if self.closed.load(Ordering::SeqCst) {
+            return false;
+        }
+        self.evict_idle();
+        self.fill_idle();
+        true
+    }
+}
+
+impl<T> PoolCommander<T> for InternalShardedPool<T> where T : 'static {
+    fn dispose(&self, item : PoolEntry<T>) {
+        self.check_in(item, true);
+    }
+
+    fn disposes(&self, items : Vec<PoolEntry<T>>) {
+        for item in items {
+            self.check_in(item, true);
+        }
+    }
+
+    fn restoration(&self, mut item : PoolEntry<T>) {
+        let reset = item.reset(&self.policy);
+        self.check_in(item, !reset);
+    }
+
+    fn restorations(&self, items : Vec<PoolEntry<T>>) {
+        for item in items {
+            self.restoration(item);
+        }
+    }
+}
+
+pub struct ShardedPool<T,P> where T : 'static, P : 'static {
+    internal : Arc<InternalShardedPool<T>>,
+    gen : Box<dyn Fn(P) -> Result<T, CommonError>>,
+    // creations run one at a time unless the generator is known to be Sync
+    gen_lock : Option<Mutex<()>>
+}
+
+unsafe impl<T,P> Sync for ShardedPool<T,P> {}
+unsafe impl<T,P> Send for ShardedPool<T,P> {}
+
+impl<T,P> ShardedPool<T,P> where T : 'static, P : 'static {
+    // nothing runs in the background, neither the generator nor T has to be Send
+    pub fn new(name : String, gen : Box<dyn Fn(P) -> Result<T, CommonError>>, max_size : usize, shards : usize) -> Arc<Self> {
+        Arc::new(ShardedPool {
+            internal : InternalShardedPool::new(name, max_size, shards, PoolPolicy::default(), None),
+            gen,
+            gen_lock : Some(Mutex::new(()))
+        })
+    }
+
+    fn create(&self, param : P) -> Result<T, CommonError> {
+        match self.gen_lock.as_ref() {
+            Some(lock) => {
+                let _g = lock.lock().unwrap();
+                (self.gen)(param)
+            },
+            None => (self.gen)(param)
+        }
+    }
+
+    // validation and creation run after the slot is taken, with neither a shard lock nor wait_lock held
+    fn get(&self, param : P, timeout : Option<Duration>) -> Result<Box<dyn PoolItem<T>>, CommonError> {
+        let started = Instant::now();
+        let entry = loop {
+            match self.internal.acquire(started, timeout)? {
+                ShardedSlot::Idle(idx, entry) => {
+                    if let Some(entry) = self.internal.check_out_idle(idx, entry, started) {
+                        break entry;
+                    }
+                },
+                ShardedSlot::Reserved(generation) => {
+                    let created = self.create(param);
+                    break self.internal.settle_reserved(created, generation, started)?;
+                }
+            }
+        };
+        Ok(Box::new(PoolItemOwned::new(entry, self.internal.clone())))
+    }
+}
+
+impl<T,P> ShardedPool<T,P> where T : Send + 'static, P : Default + 'static {
+    // background creations call the generator with P::default() on the maintenance thread
+    pub fn with_policy(name : String, gen : Box<dyn Fn(P) -> Result<T, CommonError> + Send + Sync>, max_size : usize, shards : usize,
+                       policy : PoolPolicy<T>) -> Arc<Self> {
+        let gen = Arc::new(gen);
+        let replenish_gen = gen.clone();
+        let internal = InternalShardedPool::new(name, max_size, shards, policy, Some(Box::new(move || replenish_gen(P::default()))));
+
+        internal.fill_idle();
+        if internal.needs_maintenance() {
+            if let Err(e) = spawn_maintenance(&internal, internal.pool_name.as_str(), internal.policy.maintenance_interval) {
+                if let Some(hook) = internal.policy.hooks.on_create_failed.as_ref() {
+                    hook(&e);
+                }
+            }
+        }
+
+        Arc::new(ShardedPool {
+            internal,
+            gen : Box::new(move |p| gen(p)),
+            gen_lock : None
+        })
+    }
+}
+
+impl<T,P> ThreadSafePool<T,P> for ShardedPool<T,P> where T : 'static, P : 'static {
+    fn get_owned(&self, param : P) -> Result<Box<dyn PoolItem<T>>, CommonError> {
+        self.get(param, Some(Duration::ZERO))
+    }
+
+    fn get_owned_timeout(&self, param : P, timeout : Duration) -> Result<Box<dyn PoolItem<T>>, CommonError> {
+        self.get(param, Some(timeout))
+    }
+
+    fn get_owned_wait(&self, param : P) -> Result<Box<dyn PoolItem<T>>, CommonError> {
+        self.get(param, None)
+    }
+
+    fn evict_idle(&self) -> usize {
+        self.internal.evict_idle()
+    }
+
+    fn stats(&self) -> PoolStats {
+        self.internal.stats()
+    }
+
+    fn close(&self, timeout : Duration) -> Result<(), CommonError> {
+        self.internal.close(timeout)
+    }
+
+    fn drain(&self) -> usize {
+        self.internal.drain()
+    }
+
+    fn resize(&self, max_size : usize) {
+        self.internal.resize(max_size)
+    }
+
+    fn alloc_size(&self) -> usize {
+        self.internal.alloc_size.load(Ordering::SeqCst)
+    }
+
+    fn max_size(&self) -> usize {
+        self.internal.max_size.load(Ordering::SeqCst)
+    }
+}
@@ -44,6 +44,11 @@ pub fn generate_random_string(length: usize) -> String {
         .collect()
 }
 
+pub fn generate_random_duration(max : std::time::Duration) -> std::time::Duration {
+    let mut rng = rand::rng();
+    std::time::Duration::from_nanos(rng.random_range(0..=max.as_nanos().min(u64::MAX as u128) as u64))
+}
+
 fn decode_bt_frames(frames : &[BacktraceFrame], idx : usize) -> String {
     if let Some(frame) = frames.get(idx) {
         for symbol in frame.symbols() {
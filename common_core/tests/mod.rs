@@ -2,7 +2,7 @@
 #[cfg(test)]
 mod pool_tests {
     use std::error::Error;
-    use common_core::collection::pool::get_thread_safe_pool;
+    use common_core::collection::pool::{get_thread_safe_pool, PoolKind};
     use common_core::collection::pool::ThreadSafePool;
     use common_core::collection::pool::PoolItem;
     use common_err::CommonError;
@@ -13,7 +13,7 @@ mod pool_tests {
 
         let p :Arc<dyn ThreadSafePool<i32, ()>> = get_thread_safe_pool(String::from("test"),Box::new(|_x : ()| {
             return Ok(123)
-        }),5);
+        }),5, PoolKind::Owned);
 
         {
 
@@ -31,4 +31,742 @@ mod pool_tests {
 
         Ok(()) 
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod pool_wait_tests {
+    use std::sync::{Arc, Mutex};
+    use std::thread;
+    use std::time::{Duration, Instant};
+    use common_core::collection::pool::{get_thread_safe_pool, PoolKind, ThreadSafePool};
+    use common_err::CommonError;
+
+    #[test]
+    pub fn test_pool_wait_timeout() -> Result<(), CommonError> {
+        let p : Arc<dyn ThreadSafePool<i32, ()>> = get_thread_safe_pool(String::from("test"), Box::new(|_ : ()| Ok(1)), 1, PoolKind::Owned);
+
+        let item = p.get_owned(())?;
+        assert!(p.get_owned(()).is_err());
+
+        let timer = Instant::now();
+        assert!(p.get_owned_timeout((), Duration::from_millis(50)).is_err());
+        assert!(timer.elapsed() >= Duration::from_millis(50));
+
+        let waiter = {
+            let p = p.clone();
+            thread::spawn(move || {
+                let mut waited = p.get_owned_timeout((), Duration::from_secs(5)).unwrap();
+                *waited.get_value()
+            })
+        };
+        thread::sleep(Duration::from_millis(50));
+        item.restoration();
+
+        assert_eq!(waiter.join().unwrap(), 1);
+        assert_eq!(p.alloc_size(), 1);
+
+        Ok(())
+    }
+
+    #[test]
+    pub fn test_pool_wait_fifo() -> Result<(), CommonError> {
+        let p : Arc<dyn ThreadSafePool<i32, ()>> = get_thread_safe_pool(String::from("test"), Box::new(|_ : ()| Ok(1)), 1, PoolKind::Owned);
+        let order = Arc::new(Mutex::new(Vec::new()));
+
+        let item = p.get_owned(())?;
+        let waiters : Vec<_> = (0..3).map(|idx| {
+            let (p, order) = (p.clone(), order.clone());
+            let h = thread::spawn(move || {
+                let item = p.get_owned_wait(()).unwrap();
+                order.lock().unwrap().push(idx);
+                thread::sleep(Duration::from_millis(10));
+                item.restoration();
+            });
+            // let each waiter enqueue before the next one
+            thread::sleep(Duration::from_millis(30));
+            h
+        }).collect();
+
+        item.restoration();
+        // a non blocking borrower can not jump the queue
+        assert!(p.get_owned(()).is_err());
+        for h in waiters {
+            h.join().unwrap();
+        }
+
+        assert_eq!(*order.lock().unwrap(), vec![0, 1, 2]);
+        assert_eq!(p.alloc_size(), 1);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod pool_policy_tests {
+    use std::sync::{Arc, OnceLock};
+    use std::sync::atomic::{AtomicI32, Ordering};
+    use std::thread;
+    use std::time::Duration;
+    use common_core::collection::pool::{get_keyed_thread_safe_pool, get_thread_safe_pool_with_policy, PoolKind, PoolPolicy, ThreadSafePool};
+    use common_err::CommonError;
+
+    fn counting_pool(policy : PoolPolicy<i32>) -> Arc<dyn ThreadSafePool<i32, ()>> {
+        let seq = AtomicI32::new(0);
+        get_thread_safe_pool_with_policy(String::from("test"), Box::new(move |_ : ()| {
+            Ok(seq.fetch_add(1, Ordering::Relaxed))
+        }), 2, policy)
+    }
+
+    #[test]
+    pub fn test_pool_max_uses() -> Result<(), CommonError> {
+        let p = counting_pool(PoolPolicy { max_uses : Some(2), ..Default::default() });
+
+        let mut item = p.get_owned(())?;
+        assert_eq!(*item.get_value(), 0);
+        assert_eq!(item.meta().unwrap().use_count, 1);
+        item.restoration();
+
+        let mut item = p.get_owned(())?;
+        assert_eq!(*item.get_value(), 0);
+        assert_eq!(item.meta().unwrap().use_count, 2);
+        item.restoration();
+        assert_eq!(p.alloc_size(), 0);
+
+        let mut item = p.get_owned(())?;
+        assert_eq!(*item.get_value(), 1);
+        Ok(())
+    }
+
+    #[test]
+    pub fn test_pool_validate_on_borrow() -> Result<(), CommonError> {
+        let p = counting_pool(PoolPolicy { validate : Some(Box::new(|v : &mut i32| *v != 0)), ..Default::default() });
+
+        let item = p.get_owned(())?;
+        item.restoration();
+
+        let mut item = p.get_owned(())?;
+        assert_eq!(*item.get_value(), 1);
+        assert_eq!(p.alloc_size(), 1);
+        Ok(())
+    }
+
+    #[test]
+    pub fn test_pool_validate_unlocked() -> Result<(), CommonError> {
+        // validate calls back into the pool, it would deadlock if the pool lock was held meanwhile
+        let kinds = [PoolKind::Owned, PoolKind::Sharded(2)];
+        for kind in kinds {
+            let cell : Arc<OnceLock<Arc<dyn ThreadSafePool<i32, ()>>>> = Arc::new(OnceLock::new());
+            let inner = cell.clone();
+            let validate = Box::new(move |_ : &mut i32| inner.get().map(|p| p.stats().idle == 0).unwrap_or(true));
+            cell.set(counting_pool(PoolPolicy { validate : Some(validate), kind, ..Default::default() })).ok();
+            let p = cell.get().unwrap();
+
+            p.get_owned(())?.restoration();
+            let mut item = p.get_owned(())?;
+            assert_eq!(*item.get_value(), 0);
+            assert_eq!(p.stats().checkouts, 2);
+        }
+
+        let cell : Arc<OnceLock<Arc<dyn ThreadSafePool<i32, i32>>>> = Arc::new(OnceLock::new());
+        let inner = cell.clone();
+        let validate = Box::new(move |_ : &mut i32| inner.get().map(|p| p.stats().idle == 0).unwrap_or(true));
+        cell.set(get_keyed_thread_safe_pool(String::from("test"), Box::new(|k : i32| Ok(k)), 1, 2,
+                                            PoolPolicy { validate : Some(validate), ..Default::default() })?).ok();
+        let p = cell.get().unwrap();
+
+        p.get_owned(7)?.restoration();
+        let mut item = p.get_owned(7)?;
+        assert_eq!(*item.get_value(), 7);
+        assert_eq!(p.stats().checkouts, 2);
+        Ok(())
+    }
+
+    #[test]
+    pub fn test_pool_idle_eviction() -> Result<(), CommonError> {
+        let p = counting_pool(PoolPolicy { idle_timeout : Some(Duration::from_millis(30)), ..Default::default() });
+
+        let first = p.get_owned(())?;
+        let second = p.get_owned(())?;
+        first.restoration();
+        second.restoration();
+        assert_eq!(p.evict_idle(), 0);
+
+        thread::sleep(Duration::from_millis(50));
+        assert_eq!(p.evict_idle(), 2);
+        assert_eq!(p.alloc_size(), 0);
+
+        let mut item = p.get_owned(())?;
+        assert_eq!(*item.get_value(), 2);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod pool_stats_tests {
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+    use common_core::collection::pool::{get_thread_safe_pool, get_thread_safe_pool_with_policy, PoolDurationHistogram, PoolHooks, PoolKind, PoolPolicy, ThreadSafePool};
+    use common_err::CommonError;
+    use common_err::gen::CommonDefaultErrorKind;
+
+    #[test]
+    pub fn test_pool_stats() -> Result<(), CommonError> {
+        let p : Arc<dyn ThreadSafePool<i32, bool>> = get_thread_safe_pool(String::from("test"), Box::new(|ok : bool| {
+            if ok { Ok(1) } else { CommonError::new(&CommonDefaultErrorKind::ConnectFail, "gen").to_result() }
+        }), 2, PoolKind::Owned);
+
+        let first = p.get_owned(true)?;
+        let second = p.get_owned(true)?;
+        assert!(p.get_owned_timeout(true, Duration::from_millis(10)).is_err());
+        first.restoration();
+        second.dispose();
+        let third = p.get_owned(true)?;
+        assert!(p.get_owned(false).is_err());
+        third.restoration();
+
+        let stats = p.stats();
+        assert_eq!((stats.idle, stats.in_use, stats.max_size), (1, 0, 2));
+        assert_eq!((stats.created, stats.create_failed, stats.checkouts), (2, 1, 3));
+        assert_eq!((stats.returns, stats.disposed, stats.wait_timeouts), (3, 1, 1));
+        assert_eq!(stats.wait.count(), 3);
+        assert_eq!(stats.hold.count(), 3);
+        Ok(())
+    }
+
+    #[test]
+    pub fn test_pool_hooks() -> Result<(), CommonError> {
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let hook = |name : &'static str| {
+            let events = events.clone();
+            Some(Box::new(move |_ : &i32, _ : &_| events.lock().unwrap().push(name)) as Box<_>)
+        };
+        let hooks = PoolHooks { on_create : hook("create"), on_checkout : hook("checkout"), on_return : hook("return"), on_dispose : hook("dispose"), ..Default::default() };
+        let p : Arc<dyn ThreadSafePool<i32, ()>> = get_thread_safe_pool_with_policy(String::from("test"), Box::new(|_ : ()| Ok(1)), 1,
+                                                                                   PoolPolicy { max_uses : Some(2), hooks, ..Default::default() });
+
+        p.get_owned(())?.restoration();
+        p.get_owned(())?.restoration();
+
+        assert_eq!(*events.lock().unwrap(), vec!["create", "checkout", "return", "checkout", "dispose"]);
+        Ok(())
+    }
+
+    #[test]
+    pub fn test_duration_histogram() {
+        let mut h = PoolDurationHistogram::default();
+        h.record(Duration::ZERO);
+        h.record(Duration::from_millis(7));
+        h.record(Duration::from_secs(3));
+
+        assert_eq!(h.counts[0], 1);
+        assert_eq!(h.counts[2], 1);
+        assert_eq!(*h.counts.last().unwrap(), 1);
+        assert_eq!(h.max, Duration::from_secs(3));
+        assert_eq!(h.count(), 3);
+    }
+}
+
+#[cfg(test)]
+mod pool_min_idle_tests {
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+    use std::thread;
+    use std::time::Duration;
+    use common_core::collection::pool::{get_thread_safe_pool_with_policy, PoolHooks, PoolPolicy, ThreadSafePool};
+    use common_err::CommonError;
+    use common_err::gen::CommonDefaultErrorKind;
+
+    #[test]
+    pub fn test_pool_min_idle_prewarm() -> Result<(), CommonError> {
+        let p : Arc<dyn ThreadSafePool<i32, ()>> = get_thread_safe_pool_with_policy(String::from("test"), Box::new(|_ : ()| Ok(1)), 4,
+                                                                                   PoolPolicy { min_idle : 2, maintenance_interval : Duration::from_millis(10), ..Default::default() });
+        assert_eq!(p.stats().idle, 2);
+
+        let item = p.get_owned(())?;
+        item.dispose();
+        assert_eq!(p.stats().idle, 1);
+
+        thread::sleep(Duration::from_millis(100));
+        let stats = p.stats();
+        assert_eq!((stats.idle, stats.created), (2, 3));
+        Ok(())
+    }
+
+    #[test]
+    pub fn test_pool_replenish_failure() -> Result<(), CommonError> {
+        let healthy = Arc::new(AtomicBool::new(false));
+        let failures = Arc::new(AtomicUsize::new(0));
+        let gen_healthy = healthy.clone();
+        let counter = failures.clone();
+        let created = Arc::new(AtomicUsize::new(0));
+        let gen_created = created.clone();
+
+        let hooks = PoolHooks { on_create_failed : Some(Box::new(move |_ : &CommonError| { counter.fetch_add(1, Ordering::Relaxed); })), ..Default::default() };
+        let p : Arc<dyn ThreadSafePool<i32, ()>> = get_thread_safe_pool_with_policy(String::from("test"), Box::new(move |_ : ()| {
+            if gen_healthy.load(Ordering::Relaxed) {
+                gen_created.fetch_add(1, Ordering::Relaxed);
+                Ok(1)
+            } else {
+                CommonError::new(&CommonDefaultErrorKind::ConnectFail, "down").to_result()
+            }
+        }), 2, PoolPolicy { min_idle : 1, maintenance_interval : Duration::from_millis(10), hooks, ..Default::default() });
+
+        assert_eq!(p.stats().idle, 0);
+        assert!(failures.load(Ordering::Relaxed) >= 1);
+
+        healthy.store(true, Ordering::Relaxed);
+        thread::sleep(Duration::from_millis(100));
+        assert_eq!(p.stats().idle, 1);
+        assert_eq!(created.load(Ordering::Relaxed), 1);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod keyed_pool_tests {
+    use std::sync::{Arc, Mutex};
+    use std::thread;
+    use std::time::Duration;
+    use common_core::collection::pool::{get_keyed_thread_safe_pool, PoolPolicy, ThreadSafePool};
+    use common_err::CommonError;
+
+    fn tenant_pool(max_per_key : usize, max_size : usize) -> Arc<dyn ThreadSafePool<String, &'static str>> {
+        get_keyed_thread_safe_pool(String::from("test"), Box::new(|tenant : &'static str| Ok(format!("conn-{}", tenant))),
+                                   max_per_key, max_size, PoolPolicy::default()).unwrap()
+    }
+
+    #[test]
+    pub fn test_keyed_pool_isolation() -> Result<(), CommonError> {
+        let p = tenant_pool(1, 4);
+
+        let mut a = p.get_owned("a")?;
+        assert_eq!(a.get_value().as_str(), "conn-a");
+        assert!(p.get_owned("a").is_err());
+        a.restoration();
+
+        let mut b = p.get_owned("b")?;
+        assert_eq!(b.get_value().as_str(), "conn-b");
+        let mut a = p.get_owned("a")?;
+        assert_eq!(a.get_value().as_str(), "conn-a");
+
+        let stats = p.stats();
+        assert_eq!((stats.created, stats.in_use, stats.idle), (2, 2, 0));
+        Ok(())
+    }
+
+    #[test]
+    pub fn test_keyed_pool_lru_eviction() -> Result<(), CommonError> {
+        let p = tenant_pool(2, 2);
+
+        p.get_owned("a")?.restoration();
+        p.get_owned("b")?.restoration();
+        assert_eq!(p.alloc_size(), 2);
+
+        // "a" is the least recently used idle key
+        let mut c = p.get_owned("c")?;
+        assert_eq!(c.get_value().as_str(), "conn-c");
+        let stats = p.stats();
+        assert_eq!((stats.disposed, stats.idle, stats.in_use), (1, 1, 1));
+
+        let mut b = p.get_owned("b")?;
+        assert_eq!(b.get_value().as_str(), "conn-b");
+        // nothing idle is left to evict
+        assert!(p.get_owned("a").is_err());
+        Ok(())
+    }
+
+    #[test]
+    pub fn test_keyed_pool_wait() -> Result<(), CommonError> {
+        let p = tenant_pool(1, 1);
+        let item = p.get_owned("a")?;
+        assert!(p.get_owned_timeout("b", Duration::from_millis(20)).is_err());
+
+        let waiter = {
+            let p = p.clone();
+            thread::spawn(move || {
+                let mut item = p.get_owned_timeout("b", Duration::from_secs(5)).unwrap();
+                item.get_value().clone()
+            })
+        };
+        thread::sleep(Duration::from_millis(30));
+        item.dispose();
+
+        assert_eq!(waiter.join().unwrap(), "conn-b");
+        assert_eq!(p.stats().wait_timeouts, 1);
+        Ok(())
+    }
+
+    #[test]
+    pub fn test_keyed_pool_wait_order() -> Result<(), CommonError> {
+        let p = tenant_pool(1, 2);
+        let a = p.get_owned("a")?;
+        let _b = p.get_owned("b")?;
+        let order = Arc::new(Mutex::new(Vec::new()));
+
+        let waiters : Vec<_> = [("a", 1), ("c", 2), ("a", 3)].into_iter().map(|(key, id)| {
+            let (p, order) = (p.clone(), order.clone());
+            let waiter = thread::spawn(move || {
+                let item = p.get_owned_timeout(key, Duration::from_secs(5)).unwrap();
+                order.lock().unwrap().push(id);
+                thread::sleep(Duration::from_millis(20));
+                item.restoration();
+            });
+            thread::sleep(Duration::from_millis(20));
+            waiter
+        }).collect();
+
+        // the returned "a" goes to the first waiter, "c" is not served before it although a slot of "a" is evicted for it
+        assert!(p.get_owned("a").is_err());
+        a.restoration();
+        for waiter in waiters {
+            waiter.join().unwrap();
+        }
+        assert_eq!(*order.lock().unwrap(), vec![1, 2, 3]);
+        Ok(())
+    }
+
+    #[test]
+    pub fn test_keyed_pool_policy_check() {
+        let gen = || Box::new(|tenant : &'static str| Ok(format!("conn-{}", tenant)));
+        assert!(get_keyed_thread_safe_pool(String::from("test"), gen(), 0, 2, PoolPolicy::default()).is_err());
+        assert!(get_keyed_thread_safe_pool(String::from("test"), gen(), 1, 2, PoolPolicy { min_idle : 1, ..Default::default() }).is_err());
+    }
+
+    #[test]
+    pub fn test_keyed_pool_maintenance() -> Result<(), CommonError> {
+        let p = get_keyed_thread_safe_pool(String::from("test"), Box::new(|tenant : &'static str| Ok(format!("conn-{}", tenant))), 1, 2,
+                                           PoolPolicy { idle_timeout : Some(Duration::from_millis(20)), maintenance_interval : Duration::from_millis(10), ..Default::default() })?;
+        p.get_owned("a")?.restoration();
+        p.get_owned("b")?.restoration();
+        assert_eq!(p.stats().idle, 2);
+
+        thread::sleep(Duration::from_millis(100));
+        let stats = p.stats();
+        assert_eq!((stats.idle, stats.disposed), (0, 2));
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod pool_lifecycle_tests {
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicI32, Ordering};
+    use std::thread;
+    use std::time::Duration;
+    use common_core::collection::pool::{get_keyed_thread_safe_pool, get_thread_safe_pool, PoolKind, PoolPolicy, ThreadSafePool};
+    use common_err::CommonError;
+
+    fn counting_pool(max_size : usize) -> Arc<dyn ThreadSafePool<i32, ()>> {
+        let seq = AtomicI32::new(0);
+        get_thread_safe_pool(String::from("test"), Box::new(move |_ : ()| Ok(seq.fetch_add(1, Ordering::Relaxed))), max_size, PoolKind::Owned)
+    }
+
+    #[test]
+    pub fn test_pool_close() -> Result<(), CommonError> {
+        let p = counting_pool(2);
+        p.get_owned(())?.restoration();
+        let item = p.get_owned(())?;
+        let idle = p.get_owned(())?;
+        idle.restoration();
+
+        assert!(p.close(Duration::from_millis(20)).is_err());
+        assert_eq!(p.alloc_size(), 1);
+        assert!(p.get_owned(()).is_err());
+
+        let closer = {
+            let p = p.clone();
+            thread::spawn(move || p.close(Duration::from_secs(5)))
+        };
+        thread::sleep(Duration::from_millis(20));
+        item.restoration();
+
+        closer.join().unwrap()?;
+        assert_eq!(p.alloc_size(), 0);
+        assert!(p.get_owned_timeout((), Duration::from_millis(10)).is_err());
+        Ok(())
+    }
+
+    #[test]
+    pub fn test_pool_drain() -> Result<(), CommonError> {
+        let p = counting_pool(2);
+        let mut held = p.get_owned(())?;
+        p.get_owned(())?.restoration();
+        assert_eq!(*held.get_value(), 0);
+
+        assert_eq!(p.drain(), 1);
+        held.restoration();
+        assert_eq!(p.alloc_size(), 0);
+
+        let mut fresh = p.get_owned(())?;
+        assert_eq!(*fresh.get_value(), 2);
+        Ok(())
+    }
+
+    #[test]
+    pub fn test_pool_resize() -> Result<(), CommonError> {
+        let p = counting_pool(3);
+        let first = p.get_owned(())?;
+        let second = p.get_owned(())?;
+        p.get_owned(())?.restoration();
+
+        p.resize(1);
+        assert_eq!((p.max_size(), p.alloc_size()), (1, 2));
+        assert!(p.get_owned(()).is_err());
+
+        first.restoration();
+        assert_eq!(p.alloc_size(), 1);
+        second.restoration();
+        assert_eq!(p.stats().idle, 1);
+
+        p.resize(2);
+        let _a = p.get_owned(())?;
+        let _b = p.get_owned(())?;
+        Ok(())
+    }
+
+    #[test]
+    pub fn test_keyed_pool_close() -> Result<(), CommonError> {
+        let p : Arc<dyn ThreadSafePool<i32, u8>> = get_keyed_thread_safe_pool(String::from("test"), Box::new(|k : u8| Ok(k as i32)),
+                                                                             1, 2, PoolPolicy::default())?;
+        p.get_owned(1)?.restoration();
+        let item = p.get_owned(2)?;
+
+        assert_eq!(p.drain(), 1);
+        item.restoration();
+        assert_eq!(p.alloc_size(), 0);
+
+        p.close(Duration::from_millis(10))?;
+        assert!(p.get_owned(1).is_err());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod sharded_pool_tests {
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicI32, Ordering};
+    use std::thread;
+    use std::time::{Duration, Instant};
+    use common_core::collection::pool::{get_thread_safe_pool, get_thread_safe_pool_with_policy, PoolKind, PoolPolicy, ThreadSafePool};
+    use common_err::CommonError;
+
+    fn sharded_pool(max_size : usize, shards : usize) -> Arc<dyn ThreadSafePool<i32, ()>> {
+        let seq = AtomicI32::new(0);
+        get_thread_safe_pool_with_policy(String::from("test"), Box::new(move |_ : ()| Ok(seq.fetch_add(1, Ordering::Relaxed))), max_size,
+                                         PoolPolicy { kind : PoolKind::Sharded(shards), ..Default::default() })
+    }
+
+    #[test]
+    pub fn test_sharded_pool_limit_and_steal() -> Result<(), CommonError> {
+        let p = sharded_pool(2, 4);
+
+        // created and returned on another thread, so into another home shard
+        {
+            let p = p.clone();
+            thread::spawn(move || p.get_owned(()).unwrap().restoration()).join().unwrap();
+        }
+        let mut stolen = p.get_owned(())?;
+        assert_eq!(*stolen.get_value(), 0);
+
+        let second = p.get_owned(())?;
+        assert!(p.get_owned(()).is_err());
+        assert!(p.get_owned_timeout((), Duration::from_millis(10)).is_err());
+        second.dispose();
+
+        let stats = p.stats();
+        assert_eq!((stats.created, stats.checkouts, stats.disposed, stats.wait_timeouts), (2, 3, 1, 1));
+        assert_eq!((stats.in_use, stats.idle), (1, 0));
+        Ok(())
+    }
+
+    #[test]
+    pub fn test_sharded_pool_contention() -> Result<(), CommonError> {
+        let p = sharded_pool(4, 0);
+        let workers : Vec<_> = (0..16).map(|_| {
+            let p = p.clone();
+            thread::spawn(move || {
+                for _ in 0..200 {
+                    let item = p.get_owned_wait(()).unwrap();
+                    item.restoration();
+                }
+            })
+        }).collect();
+
+        for w in workers {
+            w.join().unwrap();
+        }
+
+        let stats = p.stats();
+        assert_eq!((stats.checkouts, stats.returns), (3200, 3200));
+        assert!(stats.created <= 4);
+        p.close(Duration::from_secs(1))?;
+        assert_eq!(p.alloc_size(), 0);
+        Ok(())
+    }
+
+    #[test]
+    pub fn test_sharded_pool_without_policy() -> Result<(), CommonError> {
+        let p : Arc<dyn ThreadSafePool<i32, ()>> = get_thread_safe_pool(String::from("test"), Box::new(|_ : ()| Ok(1)), 1, PoolKind::Sharded(2));
+
+        let mut item = p.get_owned(())?;
+        assert_eq!(*item.get_value(), 1);
+        assert!(p.get_owned(()).is_err());
+        item.restoration();
+        assert_eq!(p.stats().idle, 1);
+        Ok(())
+    }
+
+    #[test]
+    pub fn test_sharded_pool_min_idle() -> Result<(), CommonError> {
+        let p : Arc<dyn ThreadSafePool<i32, ()>> = get_thread_safe_pool_with_policy(String::from("test"), Box::new(|_ : ()| Ok(1)), 4,
+                                                                                   PoolPolicy { min_idle : 2, idle_timeout : Some(Duration::from_secs(60)),
+                                                                                                maintenance_interval : Duration::from_millis(10),
+                                                                                                kind : PoolKind::Sharded(2), ..Default::default() });
+        assert_eq!(p.stats().idle, 2);
+
+        p.get_owned(())?.dispose();
+        assert_eq!(p.stats().idle, 1);
+
+        thread::sleep(Duration::from_millis(100));
+        let stats = p.stats();
+        assert_eq!((stats.idle, stats.created), (2, 3));
+        Ok(())
+    }
+
+    #[test]
+    pub fn test_sharded_pool_create_outside_wait_lock() -> Result<(), CommonError> {
+        let seq = AtomicI32::new(0);
+        let p : Arc<dyn ThreadSafePool<i32, ()>> = get_thread_safe_pool_with_policy(String::from("test"), Box::new(move |_ : ()| {
+            let n = seq.fetch_add(1, Ordering::Relaxed);
+            if n == 2 {
+                thread::sleep(Duration::from_millis(300));
+            }
+            Ok(n)
+        }), 2, PoolPolicy { kind : PoolKind::Sharded(2), ..Default::default() });
+
+        let a = p.get_owned(())?;
+        let b = p.get_owned(())?;
+        let waiters : Vec<_> = (0..2).map(|_| {
+            let p = p.clone();
+            thread::spawn(move || {
+                let mut item = p.get_owned_timeout((), Duration::from_secs(5)).unwrap();
+                (*item.get_value(), Instant::now())
+            })
+        }).collect();
+
+        thread::sleep(Duration::from_millis(30));
+        // one waiter creates the slow item, the other one takes b meanwhile
+        a.dispose();
+        thread::sleep(Duration::from_millis(30));
+        let returned = Instant::now();
+        b.restoration();
+
+        let served : Vec<_> = waiters.into_iter().map(|w| w.join().unwrap()).collect();
+        let (_, at) = served.iter().find(|(v, _)| *v == 1).unwrap();
+        assert!(at.saturating_duration_since(returned) < Duration::from_millis(150));
+        assert!(served.iter().any(|(v, _)| *v == 2));
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod async_pool_tests {
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+    use common_core::collection::pool::{get_async_thread_safe_pool, AsyncThreadSafePool};
+    use common_err::CommonError;
+    use common_err::gen::CommonDefaultErrorKind;
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    pub async fn test_async_pool_wait() -> Result<(), CommonError> {
+        let seq = Arc::new(AtomicUsize::new(0));
+        let gen_seq = seq.clone();
+        let p : Arc<dyn AsyncThreadSafePool<usize, ()>> = get_async_thread_safe_pool(String::from("test"), Box::new(move |_ : ()| {
+            let id = gen_seq.fetch_add(1, Ordering::SeqCst);
+            Box::pin(async move { Ok(id) })
+        }), 2);
+
+        let a = p.get_owned(()).await?;
+        let mut b = p.get_owned(()).await?;
+        assert_eq!(p.alloc_size(), 2);
+        assert_eq!(*b.get_value(), 1);
+
+        // the third borrower waits until b is restored and receives the same item
+        let waiter = {
+            let p = p.clone();
+            tokio::spawn(async move {
+                let mut c = p.get_owned(()).await?;
+                Ok::<usize, CommonError>(*c.get_value())
+            })
+        };
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(!waiter.is_finished());
+
+        b.restoration();
+        assert_eq!(waiter.await.unwrap()?, 1);
+
+        a.dispose();
+        assert_eq!(p.alloc_size(), 1);
+        assert_eq!(seq.load(Ordering::SeqCst), 2);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    pub async fn test_async_pool_gen_fail() {
+        let p : Arc<dyn AsyncThreadSafePool<i32, bool>> = get_async_thread_safe_pool(String::from("test"), Box::new(|ok : bool| {
+            Box::pin(async move {
+                if ok { Ok(1) } else { CommonError::new(&CommonDefaultErrorKind::ConnectFail, "refused").to_result() }
+            })
+        }), 1);
+
+        assert!(p.get_owned(false).await.is_err());
+        assert_eq!(p.alloc_size(), 0);
+        assert!(p.get_owned(true).await.is_ok());
+    }
+
+    #[tokio::test]
+    pub async fn test_async_pool_gen_cancelled() -> Result<(), CommonError> {
+        let p : Arc<dyn AsyncThreadSafePool<i32, u64>> = get_async_thread_safe_pool(String::from("test"), Box::new(|delay : u64| {
+            Box::pin(async move {
+                tokio::time::sleep(Duration::from_millis(delay)).await;
+                Ok(1)
+            })
+        }), 1);
+
+        // dropping the caller's future mid generation gives the reserved slot back
+        assert!(tokio::time::timeout(Duration::from_millis(10), p.get_owned(500)).await.is_err());
+        assert_eq!(p.alloc_size(), 0);
+
+        let _item = p.get_owned(0).await?;
+        assert_eq!(p.alloc_size(), 1);
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod lru_tests {
+    use common_core::collection::lru::LruCache;
+
+    #[test]
+    pub fn test_lru_evict() {
+        let mut c : LruCache<String, i32> = LruCache::new(2);
+
+        assert!(c.put("a".to_string(), 1).is_none());
+        assert!(c.put("b".to_string(), 2).is_none());
+        assert_eq!(c.get("a"), Some(&1));
+
+        let evicted = c.put("c".to_string(), 3);
+        assert_eq!(evicted, Some(("b".to_string(), 2)));
+        assert_eq!(c.get("b"), None);
+        assert_eq!(c.len(), 2);
+
+        assert_eq!(c.hit_count(), 1);
+        assert_eq!(c.miss_count(), 1);
+
+        assert_eq!(c.remove("a"), Some(1));
+        assert_eq!(c.pop_lru(), Some(("c".to_string(), 3)));
+        assert!(c.is_empty());
+    }
+}
@@ -1,5 +1,7 @@
 use crate::CommonErrorKind;
 pub enum CommonDefaultErrorKind {
+	Cancelled,
+	CircuitOpen,
 	ConnectFail,
 	Critical,
 	Etc,
@@ -16,11 +18,15 @@ pub enum CommonDefaultErrorKind {
 	ParsingFail,
 	SystemCallFail,
 	ThirdLibCallFail,
+	Timeout,
+	Transient,
 }
 
 impl CommonErrorKind for CommonDefaultErrorKind {
 	fn message(&self) -> &'static str {
 		match self {
+			CommonDefaultErrorKind::Cancelled => "call cancelled",
+			CommonDefaultErrorKind::CircuitOpen => "circuit breaker open, call rejected",
 			CommonDefaultErrorKind::ConnectFail => "Connect failed",
 			CommonDefaultErrorKind::Critical => "critical error, need restart system",
 			CommonDefaultErrorKind::Etc => "etc error",
@@ -37,10 +43,14 @@ impl CommonErrorKind for CommonDefaultErrorKind {
 			CommonDefaultErrorKind::ParsingFail => "parsing failed",
 			CommonDefaultErrorKind::SystemCallFail => "System or Std Lib call failed",
 			CommonDefaultErrorKind::ThirdLibCallFail => "ThirdLib call failed",
+			CommonDefaultErrorKind::Timeout => "deadline exceeded",
+			CommonDefaultErrorKind::Transient => "transient error, retry may succeed",
 		}
 	}
 	fn name(&self) -> &'static str {
 		match self {
+			CommonDefaultErrorKind::Cancelled => "CommonDefaultErrorKind::Cancelled",
+			CommonDefaultErrorKind::CircuitOpen => "CommonDefaultErrorKind::CircuitOpen",
 			CommonDefaultErrorKind::ConnectFail => "CommonDefaultErrorKind::ConnectFail",
 			CommonDefaultErrorKind::Critical => "CommonDefaultErrorKind::Critical",
 			CommonDefaultErrorKind::Etc => "CommonDefaultErrorKind::Etc",
@@ -57,6 +67,8 @@ impl CommonErrorKind for CommonDefaultErrorKind {
 			CommonDefaultErrorKind::ParsingFail => "CommonDefaultErrorKind::ParsingFail",
 			CommonDefaultErrorKind::SystemCallFail => "CommonDefaultErrorKind::SystemCallFail",
 			CommonDefaultErrorKind::ThirdLibCallFail => "CommonDefaultErrorKind::ThirdLibCallFail",
+			CommonDefaultErrorKind::Timeout => "CommonDefaultErrorKind::Timeout",
+			CommonDefaultErrorKind::Transient => "CommonDefaultErrorKind::Transient",
 		}
 	}
 }
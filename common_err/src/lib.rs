@@ -16,7 +16,8 @@ pub struct CommonError {
     func : Vec<ErrDataTuple>,
 }
 
-pub trait CommonErrorKind {
+// kinds are shared as &'static, Sync keeps CommonError movable across async tasks
+pub trait CommonErrorKind : Send + Sync {
     fn message(&self) -> &'static str;
     fn name(&self) -> &'static str;
 }
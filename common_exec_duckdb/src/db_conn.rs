@@ -1,76 +1,171 @@
-use std::collections::HashMap;
 use std::time::Duration;
 use duckdb;
-use duckdb::types::ToSql;
-use duckdb::arrow::datatypes::DataType;
+use chrono::{DateTime, NaiveDate, NaiveTime, TimeDelta, Timelike};
+use duckdb::types::{Decimal, TimeUnit, ToSql, ToSqlOutput, Value};
+use duckdb::{params_from_iter, CachedStatement, DatabaseName};
+use self_cell::self_cell;
+use common_core::collection::lru::LruCache;
 use common_err::{CommonError, gen::CommonDefaultErrorKind};
-use common_pair_exec::{PairExecutor, PairValueEnum};
+use common_pair_exec::{is_pair_dml_statement, resolve_named_batch, resolve_named_param, PairCallOptions, PairColumnInfo, PairDecimal, PairExecuteOutcome, PairExecutor, PairInsertTarget, PairPlaceholderStyle, PairResultSet, PairRowCursor, PairStatementCacheStats, PairValueEnum};
 
 pub struct DuckDBConnection {
-    client : duckdb::Connection
+    client : duckdb::Connection,
+    in_transaction : bool,
+    // statements live in duckdb's own cache, this mirrors its keys for the hit/miss counters
+    stmt_keys : LruCache<String, ()>
 }
 
-macro_rules! get_row_data {
-    ($schema:expr, $idx:expr, $r_data:expr, $ret_type:ident) => {
-        match $schema.field($idx).data_type() {
-                DataType::Int8 | DataType::Int16 | DataType::Int32 | DataType::Int64 => {
-                    let conv : i64 = $r_data.get($idx).unwrap();
-                    Ok($ret_type::BigInt(conv))
-                },
-                DataType::Utf8 => {
-                    let conv : Vec<u8> = $r_data.get($idx).unwrap();
-                    Ok($ret_type::String(String::from_utf8(conv).unwrap()))
-                },
-                DataType::Float16 | DataType::Float32  => {
-                    let conv : f32 = $r_data.get($idx).unwrap();
-                    Ok($ret_type::Float(conv))
-                },
-                DataType::Float64 => {
-                    let conv : f64 = $r_data.get($idx).unwrap();
-                    Ok($ret_type::Double(conv))
-                },
-                DataType::Null => {
-                    Ok($ret_type::Null)
-                },
-                DataType::Binary => {
-                    let conv : Vec<u8> = $r_data.get($idx).unwrap();
-                    Ok($ret_type::Bin(conv))
-                },
-                _ => CommonError::new(&CommonDefaultErrorKind::ParsingFail, "DuckDBConnection - \
-                                      execute,cast - not exists col type data").to_result()
-            }
+const MICROS_PER_SEC : i64 = 1_000_000;
+
+fn convert_duckdb_value_to_pair(value : Value) -> Result<PairValueEnum, CommonError> {
+    let invalid = |t : &'_ str| {
+        CommonError::new(&CommonDefaultErrorKind::ParsingFail, format!("DuckDBConnection - execute,cast - invalid {} value", t))
     };
+
+    match value {
+        Value::Null => Ok(PairValueEnum::Null),
+        Value::Boolean(b) => Ok(PairValueEnum::Bool(b)),
+        Value::TinyInt(i) => Ok(PairValueEnum::TinyInt(i)),
+        Value::SmallInt(i) => Ok(PairValueEnum::SmallInt(i)),
+        Value::Int(i) => Ok(PairValueEnum::BigInt(i as i64)),
+        Value::BigInt(i) => Ok(PairValueEnum::BigInt(i)),
+        Value::UTinyInt(i) => Ok(PairValueEnum::BigInt(i as i64)),
+        Value::USmallInt(i) => Ok(PairValueEnum::BigInt(i as i64)),
+        Value::UInt(i) => Ok(PairValueEnum::BigInt(i as i64)),
+        Value::UBigInt(i) => Ok(PairValueEnum::Decimal(PairDecimal::new(i as i128, 0))),
+        Value::HugeInt(i) => Ok(PairValueEnum::Decimal(PairDecimal::new(i, 0))),
+        Value::Float(f) => Ok(PairValueEnum::Float(f)),
+        Value::Double(f) => Ok(PairValueEnum::Double(f)),
+        Value::Decimal(d) => Ok(PairValueEnum::Decimal(PairDecimal::new(d.value(), d.scale() as u32))),
+        Value::Timestamp(unit, v) => {
+            let t = DateTime::from_timestamp_micros(unit.to_micros(v)).ok_or_else(|| invalid("timestamp"))?;
+            Ok(PairValueEnum::Timestamp(t.fixed_offset()))
+        },
+        Value::Date32(days) => {
+            let d = NaiveDate::default().checked_add_signed(TimeDelta::days(days as i64)).ok_or_else(|| invalid("date"))?;
+            Ok(PairValueEnum::Date(d))
+        },
+        Value::Time64(unit, v) => {
+            let micros = unit.to_micros(v);
+            let t = NaiveTime::from_num_seconds_from_midnight_opt((micros / MICROS_PER_SEC) as u32, (micros % MICROS_PER_SEC) as u32 * 1000)
+                .ok_or_else(|| invalid("time"))?;
+            Ok(PairValueEnum::Time(t))
+        },
+        // uuid and json come back through arrow as plain text
+        Value::Text(t) | Value::Enum(t) => Ok(PairValueEnum::String(t)),
+        Value::Blob(b) => Ok(PairValueEnum::Bin(b)),
+        v => CommonError::new(&CommonDefaultErrorKind::ParsingFail, format!("DuckDBConnection - \
+                              execute,cast - not exists col type data({:?})", v)).to_result()
+    }
 }
 
-fn convert_pair_value_to_duckdb_param(param : &'_ [PairValueEnum]) -> Result<Vec<&dyn ToSql>, CommonError> {
-    param.iter().map(| x | {
-        let convert: Result<&dyn ToSql, CommonError> = match x {
-            PairValueEnum::BigInt(i) => Ok(i),
-            PairValueEnum::Int(i) => Ok(i),
-            PairValueEnum::Null => Ok(&Option::<i64>::None),
-            PairValueEnum::Double(f) => Ok(f),
-            PairValueEnum::Bin(v) => Ok(v),
-            PairValueEnum::String(t) => Ok(t),
-            _ => CommonError::new(&CommonDefaultErrorKind::NoSupport, format!("not support type({:?}), return null", x)).to_result()
+struct DuckParam<'a>(&'a PairValueEnum);
 
+impl ToSql for DuckParam<'_> {
+    fn to_sql(&self) -> duckdb::Result<ToSqlOutput<'_>> {
+        let v = match self.0 {
+            PairValueEnum::String(s) | PairValueEnum::Json(s) => return Ok(ToSqlOutput::from(s.as_str())),
+            PairValueEnum::Bin(b) => return Ok(ToSqlOutput::from(b.as_slice())),
+            PairValueEnum::Null => Value::Null,
+            PairValueEnum::Bool(b) => Value::Boolean(*b),
+            PairValueEnum::TinyInt(i) => Value::TinyInt(*i),
+            PairValueEnum::SmallInt(i) => Value::SmallInt(*i),
+            PairValueEnum::Int(i) => Value::Int(*i),
+            PairValueEnum::BigInt(i) => Value::BigInt(*i),
+            PairValueEnum::Float(f) => Value::Float(*f),
+            PairValueEnum::Double(f) => Value::Double(*f),
+            PairValueEnum::Timestamp(t) => Value::Timestamp(TimeUnit::Microsecond, t.timestamp_micros()),
+            PairValueEnum::Date(d) => Value::Date32(d.signed_duration_since(NaiveDate::default()).num_days() as i32),
+            PairValueEnum::Time(t) => {
+                let micros = t.num_seconds_from_midnight() as i64 * MICROS_PER_SEC + t.nanosecond() as i64 / 1000;
+                Value::Time64(TimeUnit::Microsecond, micros)
+            },
+            PairValueEnum::Decimal(d) => {
+                let scale = u8::try_from(d.scale()).map_err(|e| duckdb::Error::ToSqlConversionFailure(Box::new(e)))?;
+                Value::Decimal(Decimal::new(Decimal::MAX_WIDTH, scale, d.value())?)
+            },
+            PairValueEnum::Uuid(u) => Value::Text(u.to_string()),
+            PairValueEnum::Inet(i) => Value::Text(i.to_string()),
+            PairValueEnum::Array(_) | PairValueEnum::Map(_) => {
+                return Err(duckdb::Error::ToSqlConversionFailure(format!("not support type({})", self.0.type_name()).into()));
+            }
         };
-        convert
-    }).collect::<Result<Vec<&dyn ToSql>, CommonError>>()
+
+        Ok(ToSqlOutput::Owned(v))
+    }
+}
+
+fn convert_pair_value_to_duckdb_param(param : &'_ [PairValueEnum]) -> Result<Vec<DuckParam<'_>>, CommonError> {
+    param.iter().map(| x | {
+        match x {
+            PairValueEnum::Array(_) | PairValueEnum::Map(_) => {
+                CommonError::new(&CommonDefaultErrorKind::NoSupport, format!("not support type({:?}), return null", x)).to_result()
+            },
+            _ => Ok(DuckParam(x))
+        }
+    }).collect::<Result<Vec<DuckParam<'_>>, CommonError>>()
+}
+
+fn get_array_param(param : &'_ PairValueEnum) -> Result<&'_ [PairValueEnum], CommonError> {
+    match param {
+        PairValueEnum::Array(a) => Ok(a.as_slice()),
+        PairValueEnum::Null => Ok(&[]),
+        _ => CommonError::new(&CommonDefaultErrorKind::InvalidApiCall, "not support type").to_result()
+    }
 }
 
 impl DuckDBConnection {
-    pub(crate) fn new(addr : &'_ str) -> Result<Self, CommonError> {
-        if addr == "" {
-            let c = DuckDBConnection { client:  duckdb::Connection::open_in_memory().map_err(|e| {
-                CommonError::new(&CommonDefaultErrorKind::InvalidApiCall, format!("Cannot open DuckDBConnection: {}", e))
-            })?};
-            Ok(c)
+    pub(crate) fn new(addr : &'_ str, statement_cache_size : usize) -> Result<Self, CommonError> {
+        let client = if addr == "" {
+            duckdb::Connection::open_in_memory()
         } else {
-            let c = DuckDBConnection { client:  duckdb::Connection::open(addr).map_err(|e| {
-                CommonError::new(&CommonDefaultErrorKind::InvalidApiCall, format!("Cannot open DuckDBConnection: {}", e))
-            })?};
-            Ok(c)
+            duckdb::Connection::open(addr)
+        }.map_err(|e| {
+            CommonError::new(&CommonDefaultErrorKind::InvalidApiCall, format!("Cannot open DuckDBConnection: {}", e))
+        })?;
+
+        client.set_prepared_statement_cache_capacity(statement_cache_size);
+
+        Ok(DuckDBConnection { client, in_transaction : false, stmt_keys : LruCache::new(statement_cache_size) })
+    }
+
+    fn get_statement<'c>(client : &'c duckdb::Connection, stmt_keys : &'_ mut LruCache<String, ()>, query : &'_ str) -> Result<CachedStatement<'c>, CommonError> {
+        if stmt_keys.get(query).is_none() {
+            stmt_keys.put(query.to_string(), ());
         }
+
+        client.prepare_cached(query).map_err(|x| {
+            CommonError::new(&CommonDefaultErrorKind::InvalidApiCall, format!("DuckDBConnection - prepare - {}", x))
+        })
+    }
+
+    // each appended row is one inserted row, constraint failures surface on the flush
+    fn append_rows(&mut self, target : &'_ PairInsertTarget, rows : &'_ [PairValueEnum]) -> Result<Vec<u64>, CommonError> {
+        let schema = target.schema.clone().unwrap_or_else(|| DatabaseName::Main.to_string());
+        let columns = target.columns.iter().map(|c| c.as_str()).collect::<Vec<&str>>();
+        let mut appender = self.client.appender_with_columns_to_db(target.table.as_str(), schema.as_str(), columns.as_slice()).map_err(|e| {
+            CommonError::new(&CommonDefaultErrorKind::InvalidApiCall, format!("DuckDBConnection - appender - {}", e))
+        })?;
+
+        for (idx, row) in rows.iter().enumerate() {
+            let duck_param = convert_pair_value_to_duckdb_param(get_array_param(row)?)?;
+            let duck_ref = duck_param.iter().map(|p| p as &dyn ToSql).collect::<Vec<&dyn ToSql>>();
+            appender.append_row(duck_ref.as_slice()).map_err(|e| {
+                CommonError::new(&CommonDefaultErrorKind::ExecuteFail, format!("DuckDBConnection - execute_batch - row {} - {}", idx, e))
+            })?;
+        }
+
+        appender.flush().map_err(|e| {
+            CommonError::new(&CommonDefaultErrorKind::ExecuteFail, format!("DuckDBConnection - execute_batch - flush - {}", e))
+        })?;
+
+        Ok(vec![1; rows.len()])
+    }
+
+    fn run_transaction_query(&mut self, query : &'_ str) -> Result<(), CommonError> {
+        self.client.execute_batch(query).map_err(|e| {
+            CommonError::new(&CommonDefaultErrorKind::ExecuteFail, format!("DuckDBConnection - {} - {}", query, e))
+        })
     }
 
     fn get_current_duration(&mut self) -> Result<std::time::Duration, CommonError> {
@@ -98,75 +193,79 @@ impl DuckDBConnection {
                              "no data").to_result()
         }
     }
+}
 
-    fn run_query_query(mut prepare : duckdb::Statement, duck_param : Vec<&'_ dyn ToSql>) -> Result<PairValueEnum, CommonError> {
-        let mut rows = prepare.query(duck_param.as_slice()).map_err(|x| {
-            CommonError::new(&CommonDefaultErrorKind::InvalidApiCall, format!("DuckDBConnection.execute_pair - execute - {}", x.to_string()))
-        })?;
+type DuckDBRows<'s> = duckdb::Rows<'s>;
 
-        let mut cache = HashMap::new();
-        let mut cache_idx_m = HashMap::new();
-        let once = std::sync::atomic::AtomicBool::new(true);
+self_cell!(
+    // the rows read the result the statement keeps, they are dropped before it
+    struct DuckDBRowCell<'a> {
+        owner : CachedStatement<'a>,
 
-        loop {
-            let row = rows.next();
+        #[covariant]
+        dependent : DuckDBRows,
+    }
+);
 
-            if row.is_err() {
-                let e = row.err().unwrap();
-                return CommonError::new(&CommonDefaultErrorKind::InvalidApiCall,
-                                        format!("DuckDBConnection - execute,next - {}", e.to_string())).to_result()
-            }
+struct DuckDBRowCursor<'a> {
+    cols : Vec<PairColumnInfo>,
+    rows : DuckDBRowCell<'a>
+}
 
-            if let Some(r) = row.unwrap() {
-                let schema = r.as_ref().schema();
+impl<'a> DuckDBRowCursor<'a> {
+    fn new(mut prepare : CachedStatement<'a>, duck_param : Vec<DuckParam<'_>>) -> Result<Self, CommonError> {
+        if let Err(err) = prepare.execute(params_from_iter(duck_param.iter())) {
+            // a failing statement may hold a stale plan, keep it out of the cache
+            prepare.discard();
+            return CommonError::new(&CommonDefaultErrorKind::InvalidApiCall,
+                                    format!("DuckDBConnection.execute_pair - execute - {}", err)).to_result();
+        }
 
-                 if once.swap(false, std::sync::atomic::Ordering::Relaxed) {
-                    for field in 0..schema.fields.len() {
-                        cache.insert((*schema.fields[field].name()).clone(), Vec::<PairValueEnum>::new());
-                        cache_idx_m.insert(field, (*schema.fields[field].name()).clone());
-                    }
-                };
+        let cols = prepare.schema().fields().iter().map(|f| {
+            PairColumnInfo::new(f.name(), f.data_type().to_string(), f.is_nullable())
+        }).collect();
 
-                for idx in 0..schema.fields.len() {
-                    let key = cache_idx_m.get(&idx).ok_or_else(|| {
-                        CommonError::new(&CommonDefaultErrorKind::NoData, format!("not exists idx : {}", idx))
-                    })?;
+        Ok(DuckDBRowCursor { cols, rows : DuckDBRowCell::new(prepare, |stmt| stmt.raw_query()) })
+    }
+}
 
-                    let v = cache.get_mut(key).ok_or_else(|| {
-                        CommonError::new(&CommonDefaultErrorKind::NoData, format!("not exists col idx : {}", idx))
-                    })?;
+impl PairRowCursor for DuckDBRowCursor<'_> {
+    fn columns(&self) -> &'_ [PairColumnInfo] {
+        self.cols.as_slice()
+    }
 
-                    let data = get_row_data!(schema, idx, r, PairValueEnum)?;
+    fn next_row(&mut self) -> Result<Option<Vec<PairValueEnum>>, CommonError> {
+        let col_count = self.cols.len();
 
-                    v.push(data);
-                }
-            }
-            else {
-                break;
-            }
-        }
+        self.rows.with_dependent_mut(|_, rows| {
+            let row = rows.next().map_err(|e| {
+                CommonError::new(&CommonDefaultErrorKind::InvalidApiCall,
+                                 format!("DuckDBConnection - execute,next - {}", e))
+            })?;
 
+            let r = match row {
+                Some(r) => r,
+                None => return Ok(None)
+            };
 
-        let mut convert_m = HashMap::new();
-        for item in cache {
-            convert_m.insert(item.0.to_string(), PairValueEnum::Array(item.1));
-        }
+            let mut v = Vec::with_capacity(col_count);
 
-        if convert_m.is_empty() {
-            Ok(PairValueEnum::Null)
-        }
-        else {
-            Ok(PairValueEnum::Map(convert_m))
-        }
+            for idx in 0..col_count {
+                let value : Value = r.get(idx).map_err(|e| {
+                    CommonError::new(&CommonDefaultErrorKind::ParsingFail, format!("DuckDBConnection - execute,get - {}", e))
+                })?;
+                v.push(convert_duckdb_value_to_pair(value)?);
+            }
+
+            Ok(Some(v))
+        })
     }
 }
 
 impl PairExecutor for DuckDBConnection {
-    fn execute_pair(&mut self, query: &'_ str, param: &PairValueEnum) -> Result<PairValueEnum, CommonError> {
-        let mut prepare = self.client.prepare(query).map_err(|x| {
-            CommonError::new(&CommonDefaultErrorKind::InvalidApiCall, format!("DuckDBConnection - execute - {}", x.to_string()))
-        })?;
-        
+    fn execute_pair_cursor<'a>(&'a mut self, query: &'_ str, param: &PairValueEnum) -> Result<Box<dyn PairRowCursor + 'a>, CommonError> {
+        let (query, param) = resolve_named_param(query, param, PairPlaceholderStyle::Dollar)?;
+        let (query, param) = (query.as_ref(), param.as_ref());
         let p = if let PairValueEnum::Array(a) = &param {
             Ok(a.as_slice())
         } else if param == &PairValueEnum::Null {
@@ -178,12 +277,125 @@ impl PairExecutor for DuckDBConnection {
 
         let duck_param  = convert_pair_value_to_duckdb_param(p)?;
 
-        Self::run_query_query(prepare, duck_param).map_err(|e| {
-            CommonError::extend(&CommonDefaultErrorKind::InvalidApiCall, "run_query_query failed", e)
-        })
+        let prepare = Self::get_statement(&self.client, &mut self.stmt_keys, query)?;
+        let cursor = DuckDBRowCursor::new(prepare, duck_param).map_err(|e| {
+            self.stmt_keys.remove(query);
+            CommonError::extend(&CommonDefaultErrorKind::InvalidApiCall, "DuckDBRowCursor new failed", e)
+        })?;
+
+        Ok(Box::new(cursor))
+    }
+
+    fn execute_pair_outcome(&mut self, query : &'_ str, param : &PairValueEnum) -> Result<PairExecuteOutcome, CommonError> {
+        // a query answers with rows only, duckdb counts changed rows for plain DML alone
+        if !is_pair_dml_statement(query) {
+            let result_set = self.execute_pair_result_set(query, param)?;
+            return Ok(PairExecuteOutcome::new(None, result_set));
+        }
+
+        let (query, param) = resolve_named_param(query, param, PairPlaceholderStyle::Dollar)?;
+        let (query, param) = (query.as_ref(), param.as_ref());
+        let duck_param = convert_pair_value_to_duckdb_param(get_array_param(param)?)?;
+        let mut prepare = Self::get_statement(&self.client, &mut self.stmt_keys, query)?;
+
+        let cnt = match prepare.execute(params_from_iter(duck_param.iter())) {
+            Ok(ok) => ok,
+            Err(err) => {
+                prepare.discard();
+                self.stmt_keys.remove(query);
+                return CommonError::new(&CommonDefaultErrorKind::ExecuteFail,
+                                        format!("DuckDBConnection - execute_pair_outcome - {}", err)).to_result();
+            }
+        };
+
+        Ok(PairExecuteOutcome::new(Some(cnt as u64), PairResultSet::default()))
     }
 
     fn get_current_time(&mut self) -> Result<Duration, CommonError> {
         self.get_current_duration()
     }
+
+    // an interrupt fails the running query, the handle turns into a no-op once the connection is gone
+    fn execute_pair_with(&mut self, query : &'_ str, param : &PairValueEnum, options : &PairCallOptions) -> Result<PairValueEnum, CommonError> {
+        if options.is_empty() {
+            return self.execute_pair(query, param);
+        }
+
+        let guard = options.start()?;
+        let interrupt = self.client.interrupt_handle();
+        guard.set_canceller(move || interrupt.interrupt())?;
+
+        let ret = self.execute_pair(query, param);
+        guard.finish(ret)
+    }
+
+    // a plain insert goes through the appender, anything else runs the cached statement per row
+    fn execute_batch(&mut self, query : &'_ str, rows : &'_ [PairValueEnum]) -> Result<Vec<u64>, CommonError> {
+        let (query, rows) = resolve_named_batch(query, rows, PairPlaceholderStyle::Dollar)?;
+        let query = query.as_ref();
+
+        if let Some(target) = PairInsertTarget::parse(query, PairPlaceholderStyle::Dollar) {
+            return self.append_rows(&target, rows.as_ref());
+        }
+
+        let mut prepare = Self::get_statement(&self.client, &mut self.stmt_keys, query)?;
+
+        let mut counts = Vec::with_capacity(rows.len());
+        for row in rows.iter() {
+            let duck_param = convert_pair_value_to_duckdb_param(get_array_param(row)?)?;
+            let cnt = match prepare.execute(params_from_iter(duck_param.iter())) {
+                Ok(ok) => ok,
+                Err(err) => {
+                    prepare.discard();
+                    self.stmt_keys.remove(query);
+                    return CommonError::new(&CommonDefaultErrorKind::ExecuteFail,
+                                            format!("DuckDBConnection - execute_batch - row {} - {}", counts.len(), err)).to_result();
+                }
+            };
+            counts.push(cnt as u64);
+        }
+
+        Ok(counts)
+    }
+
+    fn statement_cache_stats(&self) -> Option<PairStatementCacheStats> {
+        Some(PairStatementCacheStats {
+            hit : self.stmt_keys.hit_count(),
+            miss : self.stmt_keys.miss_count(),
+            size : self.stmt_keys.len(),
+            capacity : self.stmt_keys.capacity()
+        })
+    }
+
+    fn in_transaction(&self) -> bool {
+        self.in_transaction
+    }
+
+    fn begin(&mut self) -> Result<(), CommonError> {
+        if self.in_transaction {
+            return CommonError::new(&CommonDefaultErrorKind::InvalidApiCall, "DuckDBConnection - begin - already in transaction").to_result();
+        }
+
+        self.run_transaction_query("BEGIN TRANSACTION")?;
+        self.in_transaction = true;
+        Ok(())
+    }
+
+    fn commit(&mut self) -> Result<(), CommonError> {
+        if !self.in_transaction {
+            return CommonError::new(&CommonDefaultErrorKind::InvalidApiCall, "DuckDBConnection - commit - not in transaction").to_result();
+        }
+
+        self.in_transaction = false;
+        self.run_transaction_query("COMMIT")
+    }
+
+    fn rollback(&mut self) -> Result<(), CommonError> {
+        if !self.in_transaction {
+            return CommonError::new(&CommonDefaultErrorKind::InvalidApiCall, "DuckDBConnection - rollback - not in transaction").to_result();
+        }
+
+        self.in_transaction = false;
+        self.run_transaction_query("ROLLBACK")
+    }
 }
\ No newline at end of file
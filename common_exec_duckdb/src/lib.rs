@@ -2,19 +2,23 @@ mod db_conn;
 
 use common_err::{CommonError};
 
-use common_core::collection::pool::get_thread_safe_pool;
-use common_pair_exec::{PairExecutor, PairExecutorInfo, PairExecutorPool};
+use common_core::collection::pool::get_thread_safe_pool_with_policy;
+use common_pair_exec::{create_blocking_async_pair_pool, AsyncPairExecutorPool, PairAddrSelector, PairExecutor, PairExecutorInfo, PairExecutorPool};
 use db_conn::DuckDBConnection;
 
 pub fn create_duckdb_pair_conn_pool(name : String, info : PairExecutorInfo, alloc_size : usize) -> PairExecutorPool {
-    let gen_fn : Box<dyn Fn(()) -> Result<Box<dyn PairExecutor>, CommonError>> = (|info : PairExecutorInfo| {
+    let policy = info.pool.to_pool_policy();
+    // addr holds database paths, require_primary does not apply
+    let selector = PairAddrSelector::new(info.addr.clone(), info.failover);
+
+    let gen_fn : Box<dyn Fn(()) -> Result<Box<dyn PairExecutor + Send>, CommonError> + Send + Sync> = (|info : PairExecutorInfo| {
         let global_info = info;
 
         let real_fn  = move |_ : ()| {
-            let conn = DuckDBConnection::new(global_info.clone().addr[0].as_str());
+            let conn = selector.connect(|addr| DuckDBConnection::new(addr, global_info.statement_cache_size));
 
             match conn {
-                Ok(ok) => Ok(Box::new(ok) as Box<dyn PairExecutor>),
+                Ok(ok) => Ok(Box::new(ok) as Box<dyn PairExecutor + Send>),
                 Err(err) => {Err(err)}
             }
         };
@@ -22,5 +26,15 @@ pub fn create_duckdb_pair_conn_pool(name : String, info : PairExecutorInfo, allo
         Box::new(real_fn)
     })(info);
 
-    get_thread_safe_pool(name, gen_fn, alloc_size)
-}
\ No newline at end of file
+    get_thread_safe_pool_with_policy(name, gen_fn, alloc_size, policy)
+}
+
+// duckdb has no async api, calls run on the tokio blocking thread pool
+pub fn create_duckdb_async_pair_conn_pool(name : String, info : PairExecutorInfo, alloc_size : usize) -> AsyncPairExecutorPool {
+    let selector = PairAddrSelector::new(info.addr.clone(), info.failover);
+
+    create_blocking_async_pair_pool(name, Box::new(move |_ : ()| {
+        let conn = selector.connect(|addr| DuckDBConnection::new(addr, info.statement_cache_size))?;
+        Ok(Box::new(conn) as Box<dyn PairExecutor + Send>)
+    }), alloc_size)
+}
@@ -3,10 +3,10 @@ use toml;
 use common_core::utils::func::generate_random_string;
 use common_err::CommonError;
 use common_err::gen::CommonDefaultErrorKind;
-use common_exec_duckdb::create_duckdb_pair_conn_pool;
-use common_pair_exec::{PairExecutor, PairExecutorInfo, PairValueEnum};
+use common_exec_duckdb::{create_duckdb_async_pair_conn_pool, create_duckdb_pair_conn_pool};
+use common_pair_exec::{with_transaction, PairDecimal, PairExecutor, PairExecutorInfo, PairValueEnum};
 
-fn connect_duckdb_db() -> Result<common_pair_exec::PairExecutorPool, CommonError> {
+fn read_duckdb_info() -> Result<PairExecutorInfo, CommonError> {
     let read_toml : HashMap<String, String> = toml::from_str(include_str!("./tests.asset.toml")).map_err(|e| {
         CommonError::new(&CommonDefaultErrorKind::Etc, e.to_string())
     })?;
@@ -16,14 +16,21 @@ fn connect_duckdb_db() -> Result<common_pair_exec::PairExecutorPool, CommonError
         user: "".to_string(),
         password: "".to_string(),
         timeout_sec: 3600,
-        extend: None
+        statement_cache_size: 64,
+        pool: Default::default(),
+        failover: Default::default(),
+        tls: None,
+        backend: None
     };
+    Ok(info)
+}
 
-    let p = create_duckdb_pair_conn_pool("test".to_string(), info, 5);
+fn connect_duckdb_db() -> Result<common_pair_exec::PairExecutorPool, CommonError> {
+    let p = create_duckdb_pair_conn_pool("test".to_string(), read_duckdb_info()?, 5);
     Ok(p)
 }
 
-fn create_large_table(conn : &mut Box<dyn PairExecutor>) -> Result<(), CommonError> {
+fn create_large_table(conn : &mut Box<dyn PairExecutor + Send>) -> Result<(), CommonError> {
 
     const TABLE : &'static str = "create table if not exists large_data(
     id bigint, name text, hash varchar(32), data text,
@@ -35,7 +42,7 @@ fn create_large_table(conn : &mut Box<dyn PairExecutor>) -> Result<(), CommonErr
     Ok(())
 }
 
-fn insert_large_data(conn : &mut Box<dyn PairExecutor>, count : usize) -> Result<(), CommonError> {
+fn insert_large_data(conn : &mut Box<dyn PairExecutor + Send>, count : usize) -> Result<(), CommonError> {
     for i in 0..count {
         conn.execute_pair("insert into large_data(id, name, hash, data) values($1,$2,$3,$4)",
                           &PairValueEnum::Array(vec![
@@ -47,7 +54,7 @@ fn insert_large_data(conn : &mut Box<dyn PairExecutor>, count : usize) -> Result
     Ok(())
 }
 
-fn drop_large_table(conn : &mut Box<dyn PairExecutor>) -> Result<(), CommonError> {
+fn drop_large_table(conn : &mut Box<dyn PairExecutor + Send>) -> Result<(), CommonError> {
     const TABLE : &'static str = "drop table if exists large_data";
 
     conn.execute_pair(TABLE, &PairValueEnum::Null)?;
@@ -140,4 +147,252 @@ fn test_connect_select_large() -> Result<(), CommonError> {
     drop_large_table(conn)?;
 
     Ok(())
-}
\ No newline at end of file
+}
+#[test]
+fn test_connect_select_cursor() -> Result<(), CommonError> {
+    let p = connect_duckdb_db()?;
+    let mut item = p.get_owned(())?;
+    let conn = item.get_value();
+
+    create_large_table(conn)?;
+
+    let ret = insert_large_data(conn, 1000);
+    if ret.is_err() {
+        drop_large_table(conn)?;
+
+        return ret.err().unwrap().to_result();
+    }
+
+    let mut row_cnt = 0;
+    {
+        let timer = std::time::SystemTime::now();
+        let mut cursor = conn.execute_pair_cursor("select id, name from large_data", &PairValueEnum::Null)?;
+        let cols : Vec<&str> = cursor.columns().iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(cols, vec!["id", "name"]);
+
+        while let Some(row) = cursor.next_row()? {
+            assert_eq!(row.len(), 2);
+            row_cnt += 1;
+        }
+        let elap = timer.elapsed().unwrap();
+        println!("##elap time : {:?}", elap.as_millis());
+    }
+
+    drop_large_table(conn)?;
+    assert_eq!(row_cnt, 1000);
+
+    Ok(())
+}
+
+#[test]
+fn test_connect_select_result_set() -> Result<(), CommonError> {
+    let p = connect_duckdb_db()?;
+    let mut item = p.get_owned(())?;
+    let conn = item.get_value();
+
+    create_large_table(conn)?;
+
+    let ret = insert_large_data(conn, 10);
+    if ret.is_err() {
+        drop_large_table(conn)?;
+
+        return ret.err().unwrap().to_result();
+    }
+
+    let ret = conn.execute_pair_result_set("select name, id from large_data order by id", &PairValueEnum::Null);
+    drop_large_table(conn)?;
+
+    let result_set = ret?;
+    assert_eq!(result_set.column_names(), vec!["name", "id"]);
+    assert_eq!(result_set.row_count(), 10);
+    assert_eq!(result_set.get_by_name(0, "id"), Some(&PairValueEnum::BigInt(0)));
+    assert_eq!(result_set.get(9, 0), Some(&PairValueEnum::String("hello".to_string())));
+
+    Ok(())
+}
+
+#[test]
+fn test_connect_transaction() -> Result<(), CommonError> {
+    let p = connect_duckdb_db()?;
+    let mut item = p.get_owned(())?;
+    let conn = item.get_value();
+
+    create_large_table(conn)?;
+
+    let rollback_ret : Result<(), CommonError> = with_transaction(conn.as_mut(), |c| {
+        c.execute_pair("insert into large_data(id, name, hash, data) values(1, 'rollback', 'hash', 'data')", &PairValueEnum::Null)?;
+        CommonError::new(&CommonDefaultErrorKind::Etc, "force rollback").to_result()
+    });
+    assert!(rollback_ret.is_err());
+
+    let commit_ret = with_transaction(conn.as_mut(), |c| {
+        c.execute_pair("insert into large_data(id, name, hash, data) values(2, 'commit', 'hash', 'data')", &PairValueEnum::Null)?;
+        Ok(())
+    });
+
+    let ret = conn.execute_pair_result_set("select name from large_data", &PairValueEnum::Null);
+    drop_large_table(conn)?;
+
+    commit_ret?;
+    let result_set = ret?;
+    assert_eq!(result_set.row_count(), 1);
+    assert_eq!(result_set.get(0, 0), Some(&PairValueEnum::String("commit".to_string())));
+
+    Ok(())
+}
+
+#[test]
+fn test_connect_insert_batch() -> Result<(), CommonError> {
+    let p = connect_duckdb_db()?;
+    let mut item = p.get_owned(())?;
+    let conn = item.get_value();
+
+    create_large_table(conn)?;
+
+    let rows : Vec<PairValueEnum> = (0..5000).map(|i| {
+        PairValueEnum::Array(vec![
+            PairValueEnum::BigInt(i as i64), PairValueEnum::String("hello".to_string()),
+            PairValueEnum::String(generate_random_string(32)),
+            PairValueEnum::String(generate_random_string(100))
+        ])
+    }).collect();
+
+    let timer = std::time::SystemTime::now();
+    let ret = conn.execute_batch("insert into large_data(id, name, hash, data) values($1,$2,$3,$4)", rows.as_slice());
+    let elap = timer.elapsed().unwrap();
+    println!("##elap time : {:?}", elap.as_millis());
+
+    let update_ret = conn.execute_batch("update large_data set name = $1 where id = $2", &[
+        PairValueEnum::Array(vec![PairValueEnum::String("world".to_string()), PairValueEnum::BigInt(0)]),
+        PairValueEnum::Array(vec![PairValueEnum::String("world".to_string()), PairValueEnum::BigInt(-1)])
+    ]);
+    let loaded = conn.execute_pair_result_set("select count(*) as cnt from large_data where name = 'hello'", &PairValueEnum::Null);
+
+    drop_large_table(conn)?;
+
+    let counts = ret?;
+    assert_eq!(counts.len(), 5000);
+    assert!(counts.iter().all(|c| *c == 1));
+    assert_eq!(update_ret?, vec![1, 0]);
+    assert_eq!(loaded?.get_by_name(0, "cnt"), Some(&PairValueEnum::BigInt(4999)));
+
+    Ok(())
+}
+
+#[test]
+fn test_connect_statement_cache() -> Result<(), CommonError> {
+    let p = connect_duckdb_db()?;
+    let mut item = p.get_owned(())?;
+    let conn = item.get_value();
+
+    let before = conn.statement_cache_stats().unwrap();
+    for _ in 0..3 {
+        conn.execute_pair("select 1 as one", &PairValueEnum::Null)?;
+    }
+    let after = conn.statement_cache_stats().unwrap();
+
+    assert_eq!(after.capacity, 64);
+    assert_eq!(after.miss - before.miss, 1);
+    assert_eq!(after.hit - before.hit, 2);
+
+    Ok(())
+}
+
+#[test]
+fn test_connect_outcome() -> Result<(), CommonError> {
+    let p = connect_duckdb_db()?;
+    let mut item = p.get_owned(())?;
+    let conn = item.get_value();
+
+    create_large_table(conn)?;
+
+    let ret = insert_large_data(conn, 10);
+    if ret.is_err() {
+        drop_large_table(conn)?;
+
+        return ret.err().unwrap().to_result();
+    }
+
+    let update = conn.execute_pair_outcome("update large_data set name = $1 where id < $2",
+                                           &PairValueEnum::Array(vec![PairValueEnum::String("world".to_string()), PairValueEnum::BigInt(3)]));
+    let missed = conn.execute_pair_outcome("update large_data set name = $1 where id < $2",
+                                           &PairValueEnum::Array(vec![PairValueEnum::String("world".to_string()), PairValueEnum::BigInt(0)]));
+    let returning = conn.execute_pair_outcome("delete from large_data where id = $1 returning id",
+                                              &PairValueEnum::Array(vec![PairValueEnum::BigInt(5)]));
+    let count = conn.execute_pair_outcome("select count(*) as \"Count\" from large_data", &PairValueEnum::Null);
+
+    drop_large_table(conn)?;
+
+    let update = update?;
+    assert_eq!(update.rows_affected, Some(3));
+    assert_eq!(update.result_set.row_count(), 0);
+
+    assert_eq!(missed?.rows_affected, Some(0));
+
+    let returning = returning?;
+    assert_eq!(returning.rows_affected, None);
+    assert_eq!(returning.result_set.get_by_name(0, "id"), Some(&PairValueEnum::BigInt(5)));
+
+    let count = count?;
+    assert_eq!(count.rows_affected, None);
+    assert_eq!(count.result_set.get_by_name(0, "Count"), Some(&PairValueEnum::BigInt(9)));
+
+    Ok(())
+}
+
+#[test]
+fn test_connect_extended_types() -> Result<(), CommonError> {
+    let p = connect_duckdb_db()?;
+    let mut item = p.get_owned(())?;
+    let conn = item.get_value();
+
+    conn.execute_pair("create table if not exists typed_data(
+    ts timestamp, d date, t time, n decimal(30,4), s smallint, c tinyint, u uuid
+    )", &PairValueEnum::Null)?;
+
+    let ts = chrono::DateTime::parse_from_rfc3339("2024-02-29T12:34:56.789+09:00").unwrap();
+    let uid = uuid::Uuid::parse_str("67e55044-10b1-426f-9247-bb680e5fe0c8").unwrap();
+    let row = vec![
+        PairValueEnum::Timestamp(ts),
+        PairValueEnum::Date(chrono::NaiveDate::from_ymd_opt(1969, 12, 31).unwrap()),
+        PairValueEnum::Time(chrono::NaiveTime::from_hms_micro_opt(23, 59, 58, 123456).unwrap()),
+        PairValueEnum::Decimal("-12345678901234567890.0123".parse::<PairDecimal>()?),
+        PairValueEnum::SmallInt(-300), PairValueEnum::TinyInt(7),
+        PairValueEnum::Uuid(uid)
+    ];
+
+    let insert = conn.execute_pair("insert into typed_data values($1,$2,$3,$4,$5,$6,$7)", &PairValueEnum::Array(row.clone()));
+    let ret = conn.execute_pair_result_set("select * from typed_data", &PairValueEnum::Null);
+    conn.execute_pair("drop table if exists typed_data", &PairValueEnum::Null)?;
+
+    insert?;
+    let result_set = ret?;
+    assert_eq!(result_set.row_count(), 1);
+    for (idx, expect) in row[..6].iter().enumerate() {
+        assert_eq!(result_set.get(0, idx), Some(expect));
+    }
+    assert_eq!(result_set.get(0, 6), Some(&PairValueEnum::String(uid.to_string())));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_connect_async_blocking() -> Result<(), CommonError> {
+    let p = create_duckdb_async_pair_conn_pool("test".to_string(), read_duckdb_info()?, 1);
+    let mut item = p.get_owned(()).await?;
+    let conn = item.get_value();
+
+    conn.execute_pair("create table if not exists async_data(id bigint, name text)", &PairValueEnum::Null).await?;
+    let insert = conn.execute_batch("insert into async_data(id, name) values(:id, :name)", &[
+        PairValueEnum::Map(HashMap::from([("id".to_string(), PairValueEnum::BigInt(1)), ("name".to_string(), PairValueEnum::String("a".to_string()))])),
+        PairValueEnum::Map(HashMap::from([("id".to_string(), PairValueEnum::BigInt(2)), ("name".to_string(), PairValueEnum::String("b".to_string()))]))
+    ]).await;
+    let ret = conn.execute_pair_result_set("select name from async_data order by id", &PairValueEnum::Null).await;
+    conn.execute_pair("drop table if exists async_data", &PairValueEnum::Null).await?;
+
+    assert_eq!(insert?, vec![1, 1]);
+    assert_eq!(ret?.rows(), &[vec![PairValueEnum::String("a".to_string())], vec![PairValueEnum::String("b".to_string())]]);
+    assert!(conn.statement_cache_stats().is_some());
+
+    Ok(())
+}
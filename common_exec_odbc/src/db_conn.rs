@@ -1,21 +1,34 @@
 use std::ffi::c_void;
+use std::marker::PhantomData;
 use std::time::Duration;
 use std::ptr;
-use std::collections::HashMap;
 use common_err::CommonError;
-use common_pair_exec::{PairExecutor, PairValueEnum};
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
+use common_pair_exec::{resolve_named_batch, resolve_named_param, PairCallOptions, PairColumnInfo, PairDecimal, PairExecuteOutcome, PairExecutor, PairInsertTarget, PairPlaceholderStyle, PairResultSet, PairRowCursor, PairValueEnum};
 use odbc_sys::*;
 use common_err::gen::CommonDefaultErrorKind;
 use common_pair_exec::PairValueEnum::Null;
 
+const ODBC_DATE_FORMAT : &'static str = "%Y-%m-%d";
+const ODBC_TIME_FORMAT : &'static str = "%H:%M:%S%.f";
+const ODBC_TIMESTAMP_FORMAT : &'static str = "%Y-%m-%d %H:%M:%S%.f";
+// sign, decimal point and terminator of the character conversion
+const ODBC_CONVERT_PAD : usize = 3;
+
 pub struct OdbcConnection {
     env_h : Handle,
     conn_h : Handle,
 
     current_time_query : String,
-    current_time_col_name : String
+    current_time_col_name : String,
+    in_transaction : bool,
+    // the driver answers a parameter array with one count per row
+    param_array_counts : bool
 }
 
+// the handles are only used through &mut self, the driver manager lets a connection move between threads
+unsafe impl Send for OdbcConnection {}
+
 unsafe fn get_odbc_diagnostics(handle_type: HandleType, handle: Handle) -> String {
     let mut rec_number: SmallInt = 1;
     let mut string_buffer = String::with_capacity(1024);
@@ -61,6 +74,153 @@ unsafe fn get_odbc_diagnostics(handle_type: HandleType, handle: Handle) -> Strin
 
     string_buffer
 }
+
+const ODBC_TRANSIENT_STATES : [&'static str; 3] = ["SQLSTATE=40001", "SQLSTATE=40P01", "SQLSTATE=HYT00"];
+
+// SQLSTATE class 08 is a connection exception, the connection is not reusable
+fn diag_kind(kind : &'static CommonDefaultErrorKind, diag : &'_ str) -> &'static CommonDefaultErrorKind {
+    if diag.contains("SQLSTATE=08") || diag.contains("SQLSTATE=HYT01") {
+        &CommonDefaultErrorKind::ConnectFail
+    } else if ODBC_TRANSIENT_STATES.iter().any(|state| diag.contains(state)) {
+        // serialization failure, deadlock and query timeout
+        &CommonDefaultErrorKind::Transient
+    } else {
+        kind
+    }
+}
+// values without a matching C type are bound as text the driver converts
+fn odbc_text_param(param : &'_ PairValueEnum) -> Option<(SqlDataType, i32, String)> {
+    match param {
+        PairValueEnum::Timestamp(t) => Some((SqlDataType::TIMESTAMP, 6, t.naive_utc().format("%Y-%m-%d %H:%M:%S%.6f").to_string())),
+        PairValueEnum::Date(d) => Some((SqlDataType::DATE, 0, d.format(ODBC_DATE_FORMAT).to_string())),
+        PairValueEnum::Time(t) => Some((SqlDataType::TIME, 6, t.format("%H:%M:%S%.6f").to_string())),
+        PairValueEnum::Decimal(d) => Some((SqlDataType::DECIMAL, d.scale() as i32, d.to_string())),
+        PairValueEnum::Uuid(u) => Some((SqlDataType::VARCHAR, 0, u.to_string())),
+        PairValueEnum::Inet(i) => Some((SqlDataType::VARCHAR, 0, i.to_string())),
+        PairValueEnum::Json(j) => Some((SqlDataType::VARCHAR, 0, j.clone())),
+        _ => None
+    }
+}
+
+fn get_array_param(param : &'_ PairValueEnum) -> Result<&'_ [PairValueEnum], CommonError> {
+    match param {
+        PairValueEnum::Array(a) => Ok(a.as_slice()),
+        PairValueEnum::Null => Ok(&[]),
+        _ => CommonError::new(&CommonDefaultErrorKind::InvalidApiCall, "not support type").to_result()
+    }
+}
+
+// odbc-sys has no InfoType for SQL_PARAM_ARRAY_ROW_COUNTS
+const SQL_PARAM_ARRAY_ROW_COUNTS : USmallInt = 153;
+const SQL_PARC_NO_BATCH : UInteger = 2;
+const SQL_PARAM_BIND_BY_COLUMN : usize = 0;
+const SQL_PARAM_ERROR : USmallInt = 5;
+
+#[allow(clashing_extern_declarations)]
+extern "system" {
+    #[link_name = "SQLGetInfo"]
+    fn sql_get_info_raw(connection_handle : HDbc, info_type : USmallInt, info_value_ptr : Pointer,
+                        buffer_length : SmallInt, string_length_ptr : *mut SmallInt) -> SqlReturn;
+}
+
+// one parameter of a parameter array, the values of every row back to back
+struct OdbcParamColumn {
+    ctype : CDataType,
+    sql_t : SqlDataType,
+    column_size : usize,
+    digits : i32,
+    width : usize,
+    data : Vec<u8>,
+    ind : Vec<Len>
+}
+
+impl OdbcParamColumn {
+    // None when the rows mix value types or hold one only the row by row binding takes
+    fn new(values : &'_ [&'_ PairValueEnum]) -> Option<Self> {
+        let first = values.iter().find(|v| ***v != PairValueEnum::Null);
+        if let Some(first) = first {
+            if values.iter().any(|v| **v != PairValueEnum::Null && std::mem::discriminant(*v) != std::mem::discriminant(*first)) {
+                return None;
+            }
+        }
+
+        let fixed = match first {
+            Some(PairValueEnum::Double(_)) => Some((CDataType::Double, SqlDataType::DOUBLE, 8, 5)),
+            Some(PairValueEnum::Int(_)) => Some((CDataType::SLong, SqlDataType::INTEGER, 4, 0)),
+            Some(PairValueEnum::SmallInt(_)) => Some((CDataType::SShort, SqlDataType::SMALLINT, 2, 0)),
+            Some(PairValueEnum::TinyInt(_)) => Some((CDataType::STinyInt, SqlDataType::EXT_TINY_INT, 1, 0)),
+            Some(PairValueEnum::BigInt(_)) => Some((CDataType::SBigInt, SqlDataType::DECIMAL, 8, 0)),
+            Some(PairValueEnum::Float(_)) => Some((CDataType::Float, SqlDataType::FLOAT, 4, 5)),
+            _ => None
+        };
+
+        if let Some((ctype, sql_t, width, digits)) = fixed {
+            let mut data = Vec::with_capacity(values.len() * width);
+            let mut ind = Vec::with_capacity(values.len());
+            for v in values.iter() {
+                let bytes = match v {
+                    PairValueEnum::Double(d) => d.to_ne_bytes().to_vec(),
+                    PairValueEnum::Int(i) => i.to_ne_bytes().to_vec(),
+                    PairValueEnum::SmallInt(i) => i.to_ne_bytes().to_vec(),
+                    PairValueEnum::TinyInt(i) => i.to_ne_bytes().to_vec(),
+                    PairValueEnum::BigInt(i) => i.to_ne_bytes().to_vec(),
+                    PairValueEnum::Float(f) => f.to_ne_bytes().to_vec(),
+                    _ => vec![0; width]
+                };
+                data.extend_from_slice(bytes.as_slice());
+                ind.push(if **v == PairValueEnum::Null { NULL_DATA } else { 0 });
+            }
+
+            return Some(OdbcParamColumn { ctype, sql_t, column_size : 0, digits, width, data, ind });
+        }
+
+        let mut sql_t = SqlDataType::VARCHAR;
+        let mut digits = 0;
+        let mut texts = Vec::with_capacity(values.len());
+        for v in values.iter() {
+            let text = match v {
+                PairValueEnum::Null => None,
+                PairValueEnum::String(s) => Some(s.clone()),
+                _ => {
+                    let (t, d, s) = odbc_text_param(v)?;
+                    sql_t = t;
+                    digits = digits.max(d);
+                    Some(s)
+                }
+            };
+            texts.push(text);
+        }
+
+        let width = texts.iter().map(|t| t.as_ref().map_or(0, |t| t.len())).max().unwrap_or(0).max(1);
+        let mut data = vec![0; values.len() * width];
+        let mut ind = Vec::with_capacity(values.len());
+        for (idx, text) in texts.iter().enumerate() {
+            match text {
+                Some(t) => {
+                    data[idx * width..idx * width + t.len()].copy_from_slice(t.as_bytes());
+                    ind.push(t.len() as Len);
+                },
+                None => ind.push(NULL_DATA)
+            }
+        }
+
+        Some(OdbcParamColumn { ctype : CDataType::Char, sql_t, column_size : width, digits, width, data, ind })
+    }
+
+    // column wise buffers for every parameter, None when a row cannot go into an array
+    fn from_rows(rows : &'_ [&'_ [PairValueEnum]]) -> Option<Vec<Self>> {
+        let count = rows.first()?.len();
+        if count == 0 || rows.iter().any(|r| r.len() != count) {
+            return None;
+        }
+
+        (0..count).map(|idx| {
+            let values = rows.iter().map(|r| &r[idx]).collect::<Vec<&PairValueEnum>>();
+            Self::new(values.as_slice())
+        }).collect()
+    }
+}
+
 struct ODBCStmt{
     stmt_h : HStmt
 }
@@ -73,9 +233,14 @@ impl ODBCStmt{
     }
     fn cast_sql_type_to_ctype(sql_t : &SqlDataType) -> Result<CDataType, CommonError> {
         match *sql_t {
-            SqlDataType::DOUBLE | SqlDataType::DECIMAL | SqlDataType::NUMERIC => Ok(CDataType::Double),
+            SqlDataType::DOUBLE => Ok(CDataType::Double),
             SqlDataType::CHAR | SqlDataType::VARCHAR | SqlDataType::EXT_LONG_VARCHAR => Ok(CDataType::Char),
-            SqlDataType::INTEGER | SqlDataType::SMALLINT => Ok(CDataType::SLong),
+            // exact and temporal types are fetched as text and parsed
+            SqlDataType::DECIMAL | SqlDataType::NUMERIC | SqlDataType::DATE | SqlDataType::TIME |
+            SqlDataType::TIMESTAMP | SqlDataType::EXT_GUID => Ok(CDataType::Char),
+            SqlDataType::INTEGER => Ok(CDataType::SLong),
+            SqlDataType::SMALLINT => Ok(CDataType::SShort),
+            SqlDataType::EXT_TINY_INT => Ok(CDataType::STinyInt),
             SqlDataType::FLOAT => Ok(CDataType::Float),
             SqlDataType::EXT_BIG_INT => Ok(CDataType::SBigInt),
             _ => {
@@ -83,11 +248,39 @@ impl ODBCStmt{
             }
         }
     }
+    fn parse_text_value(sql_t : &SqlDataType, s : String) -> Result<PairValueEnum, CommonError> {
+        let parse_err = |e : String| {
+            CommonError::new(&CommonDefaultErrorKind::ParsingFail, format!("ODBCStmt - parse {:?} - {} - {}", *sql_t, s, e))
+        };
+
+        match *sql_t {
+            SqlDataType::DECIMAL | SqlDataType::NUMERIC => Ok(PairValueEnum::Decimal(s.parse::<PairDecimal>()?)),
+            SqlDataType::DATE => NaiveDate::parse_from_str(s.as_str(), ODBC_DATE_FORMAT)
+                .map(PairValueEnum::Date).map_err(|e| parse_err(e.to_string())),
+            SqlDataType::TIME => NaiveTime::parse_from_str(s.as_str(), ODBC_TIME_FORMAT)
+                .map(PairValueEnum::Time).map_err(|e| parse_err(e.to_string())),
+            // timestamp carries no zone, read as utc
+            SqlDataType::TIMESTAMP => NaiveDateTime::parse_from_str(s.as_str(), ODBC_TIMESTAMP_FORMAT)
+                .map(|t| PairValueEnum::Timestamp(t.and_utc().fixed_offset())).map_err(|e| parse_err(e.to_string())),
+            SqlDataType::EXT_GUID => uuid::Uuid::parse_str(s.as_str())
+                .map(PairValueEnum::Uuid).map_err(|e| parse_err(e.to_string())),
+            _ => Ok(PairValueEnum::String(s))
+        }
+    }
+
+    // text_buffers keeps the converted values alive until the statement is executed
     #[inline]
-    unsafe fn get_bind_stmt_meta_data(param : &'_ [&'_ PairValueEnum]) -> Result<Vec<(CDataType, SqlDataType, isize, i32, *mut c_void)>, CommonError> {
+    unsafe fn get_bind_stmt_meta_data(param : &'_ [&'_ PairValueEnum], text_buffers : &'_ mut Vec<String>) -> Result<Vec<(CDataType, SqlDataType, isize, i32, *mut c_void)>, CommonError> {
         let mut v = Vec::with_capacity(param.len());
 
         for idx in 0..param.len() {
+            if let Some((sql_t, digits, s)) = odbc_text_param(param[idx]) {
+                // the heap buffer does not move when text_buffers grows
+                v.push((CDataType::Char, sql_t, s.len() as isize, digits, s.as_ptr() as *mut c_void));
+                text_buffers.push(s);
+                continue;
+            }
+
             let bind_data = match param[idx] {
                 PairValueEnum::Double(d) => {
                     (CDataType::Double, SqlDataType::DOUBLE, 0, 5, d as *const f64 as *mut c_void)
@@ -95,6 +288,12 @@ impl ODBCStmt{
                 PairValueEnum::Int(i) => {
                     (CDataType::SLong, SqlDataType::INTEGER, 0, 0, i as *const i32 as *mut c_void)
                 }
+                PairValueEnum::SmallInt(i) => {
+                    (CDataType::SShort, SqlDataType::SMALLINT, 0, 0, i as *const i16 as *mut c_void)
+                }
+                PairValueEnum::TinyInt(i) => {
+                    (CDataType::STinyInt, SqlDataType::EXT_TINY_INT, 0, 0, i as *const i8 as *mut c_void)
+                }
                 PairValueEnum::BigInt(bi) => {
                     (CDataType::SBigInt, SqlDataType::DECIMAL, 0, 0, bi as *const i64 as *mut c_void)
                 }
@@ -117,7 +316,7 @@ impl ODBCStmt{
         Ok(v)
     }
 
-    unsafe fn get_cols_meta_data(stmt_h : &'_ HStmt, count : usize) -> Result<Vec<(String, SqlDataType, usize)>, CommonError> {
+    unsafe fn get_cols_meta_data(stmt_h : &'_ HStmt, count : usize) -> Result<Vec<(String, SqlDataType, usize, bool)>, CommonError> {
         let mut v = Vec::with_capacity(count);
 
         for idx in 0..count {
@@ -126,7 +325,7 @@ impl ODBCStmt{
             let mut col_len : ULen = 0;
             let mut data_type = SqlDataType::INTEGER;
             let mut dummy1 : SmallInt = 0;
-            let mut dummy2 : Nullability = Nullability::NULLABLE;
+            let mut nullable : Nullability = Nullability::NULLABLE;
             let ret = SQLDescribeCol(*stmt_h,
                            idx as USmallInt + 1,
                            col_name.as_mut_ptr(),
@@ -135,13 +334,13 @@ impl ODBCStmt{
                            &mut data_type as *mut SqlDataType,
                            &mut col_len as *mut ULen,
                            &mut dummy1 as *mut SmallInt,
-                           &mut dummy2 as *mut Nullability
+                           &mut nullable as *mut Nullability
 
             );
 
             if ret != SqlReturn::SUCCESS && ret != SqlReturn::SUCCESS_WITH_INFO {
                 let error_str = get_odbc_diagnostics(HandleType::Stmt, stmt_h.as_handle());
-                return CommonError::new(&CommonDefaultErrorKind::ThirdLibCallFail, error_str).to_result();
+                return CommonError::new(diag_kind(&CommonDefaultErrorKind::ThirdLibCallFail, &error_str), error_str).to_result();
             }
 
             let convert_arr = col_name.iter().take(col_name_length as usize).fold(Vec::with_capacity(col_name_length as usize), |mut acc,x| {
@@ -150,23 +349,13 @@ impl ODBCStmt{
             });
             let cloned = String::from_utf8_lossy(convert_arr.as_slice()).to_string();
 
-            v.push((cloned, data_type, col_len as usize));
+            v.push((cloned, data_type, col_len as usize, nullable != Nullability::NO_NULLS));
         }
 
         Ok(v)
     }
 
-    unsafe fn execute(&mut self, query : &'_ str, param : Option<&'_ [PairValueEnum]>) -> Result<PairValueEnum, CommonError> {
-        let mut binds = if param.is_some() {
-            let p_ref : Vec<&PairValueEnum> = param.unwrap().iter().map(|p| p).collect();
-
-            Self::get_bind_stmt_meta_data(p_ref.as_slice()).map_err(|e| {
-                CommonError::extend(&CommonDefaultErrorKind::ExecuteFail, "bind failed", e)
-            })?
-        } else {
-            Vec::new()
-        };
-
+    unsafe fn bind_params(&mut self, binds : &'_ mut [(CDataType, SqlDataType, isize, i32, *mut c_void)]) -> Result<(), CommonError> {
         for bind_idx in 0..binds.len() {
             let ret = SQLBindParameter(
                 self.stmt_h,
@@ -183,149 +372,282 @@ impl ODBCStmt{
 
             if ret != SqlReturn::SUCCESS && ret != SqlReturn::SUCCESS_WITH_INFO {
                 let error_str = get_odbc_diagnostics(HandleType::Stmt, self.stmt_h.as_handle());
-                return CommonError::new(&CommonDefaultErrorKind::ThirdLibCallFail, error_str).to_result();
+                return CommonError::new(diag_kind(&CommonDefaultErrorKind::ThirdLibCallFail, &error_str), error_str).to_result();
             }
         }
 
-        let mut col_buffer = HashMap::with_capacity(3);
+        Ok(())
+    }
+
+    unsafe fn execute(&mut self, query : &'_ str, param : Option<&'_ [PairValueEnum]>) -> Result<Vec<(String, SqlDataType, usize, bool)>, CommonError> {
+        let mut text_buffers = Vec::new();
+        let mut binds = if param.is_some() {
+            let p_ref : Vec<&PairValueEnum> = param.unwrap().iter().map(|p| p).collect();
+
+            Self::get_bind_stmt_meta_data(p_ref.as_slice(), &mut text_buffers).map_err(|e| {
+                CommonError::extend(&CommonDefaultErrorKind::ExecuteFail, "bind failed", e)
+            })?
+        } else {
+            Vec::new()
+        };
+
+        self.bind_params(binds.as_mut_slice())?;
 
         let ret = SQLExecDirect(self.stmt_h, query.as_ptr(), query.len() as Integer);
 
         if ret != SqlReturn::SUCCESS && ret != SqlReturn::SUCCESS_WITH_INFO {
             let error_str = get_odbc_diagnostics(HandleType::Stmt, self.stmt_h.as_handle());
-            return CommonError::new(&CommonDefaultErrorKind::ExecuteFail, error_str).to_result();
+            return CommonError::new(diag_kind(&CommonDefaultErrorKind::ExecuteFail, &error_str), error_str).to_result();
         }
         let mut cols_count = 0 as SmallInt;
         let ret = SQLNumResultCols(self.stmt_h, &mut cols_count as *mut SmallInt);
 
         if  ret != SqlReturn::SUCCESS && ret != SqlReturn::SUCCESS_WITH_INFO {
             let error_str = get_odbc_diagnostics(HandleType::Stmt, self.stmt_h.as_handle());
-            return CommonError::new(&CommonDefaultErrorKind::ExecuteFail, error_str).to_result();
+            return CommonError::new(diag_kind(&CommonDefaultErrorKind::ExecuteFail, &error_str), error_str).to_result();
         }
 
         if cols_count <= 0 {
-            return Ok(PairValueEnum::Null);
+            return Ok(Vec::new());
         }
 
-        let cols = Self::get_cols_meta_data(&self.stmt_h, cols_count as usize).map_err(|e| {
+        Self::get_cols_meta_data(&self.stmt_h, cols_count as usize).map_err(|e| {
             CommonError::extend(&CommonDefaultErrorKind::ExecuteFail, "get cols failed", e)
+        })
+    }
+
+    unsafe fn prepare(&mut self, query : &'_ str) -> Result<(), CommonError> {
+        let ret = SQLPrepare(self.stmt_h, query.as_ptr(), query.len() as Integer);
+
+        if ret != SqlReturn::SUCCESS && ret != SqlReturn::SUCCESS_WITH_INFO {
+            let error_str = get_odbc_diagnostics(HandleType::Stmt, self.stmt_h.as_handle());
+            return CommonError::new(diag_kind(&CommonDefaultErrorKind::ExecuteFail, &error_str), error_str).to_result();
+        }
+        Ok(())
+    }
+
+    unsafe fn execute_prepared(&mut self, param : &'_ [PairValueEnum]) -> Result<u64, CommonError> {
+        let p_ref : Vec<&PairValueEnum> = param.iter().collect();
+        let mut text_buffers = Vec::new();
+        let mut binds = Self::get_bind_stmt_meta_data(p_ref.as_slice(), &mut text_buffers).map_err(|e| {
+            CommonError::extend(&CommonDefaultErrorKind::ExecuteFail, "bind failed", e)
         })?;
 
-        for col in &cols {
-            let a = col.0.clone();
-            col_buffer.insert(a, Vec::with_capacity(5));
+        self.bind_params(binds.as_mut_slice())?;
+
+        let ret = SQLExecute(self.stmt_h);
+        if ret != SqlReturn::SUCCESS && ret != SqlReturn::SUCCESS_WITH_INFO && ret != SqlReturn::NO_DATA {
+            let error_str = get_odbc_diagnostics(HandleType::Stmt, self.stmt_h.as_handle());
+            let _ = SQLFreeStmt(self.stmt_h, FreeStmtOption::ResetParams);
+            return CommonError::new(diag_kind(&CommonDefaultErrorKind::ExecuteFail, &error_str), error_str).to_result();
+        }
+
+        let row_count = self.row_count();
+        let _ = SQLFreeStmt(self.stmt_h, FreeStmtOption::Close);
+        let _ = SQLFreeStmt(self.stmt_h, FreeStmtOption::ResetParams);
+
+        Ok(row_count?.unwrap_or(0))
+    }
+
+    // every row in one SQLExecute, None when the driver refuses parameter arrays
+    unsafe fn execute_param_array(&mut self, columns : &'_ mut [OdbcParamColumn], rows : usize) -> Result<Option<Vec<u64>>, CommonError> {
+        let mut status : Vec<USmallInt> = vec![0; rows];
+        let mut processed : ULen = 0;
+
+        let ret = SQLSetStmtAttr(self.stmt_h, StatementAttribute::ParamBindType, SQL_PARAM_BIND_BY_COLUMN as Pointer, 0);
+        if ret != SqlReturn::SUCCESS && ret != SqlReturn::SUCCESS_WITH_INFO {
+            return Ok(None);
         }
+        let ret = SQLSetStmtAttr(self.stmt_h, StatementAttribute::ParamsetSize, rows as Pointer, 0);
+        if ret != SqlReturn::SUCCESS && ret != SqlReturn::SUCCESS_WITH_INFO {
+            return Ok(None);
+        }
+        let _ = SQLSetStmtAttr(self.stmt_h, StatementAttribute::ParamStatusPtr, status.as_mut_ptr() as Pointer, 0);
+        let _ = SQLSetStmtAttr(self.stmt_h, StatementAttribute::ParamsProcessedPtr, &mut processed as *mut ULen as Pointer, 0);
+
+        let ret = self.bind_param_array(columns).and_then(|_| {
+            let ret = SQLExecute(self.stmt_h);
+            if ret != SqlReturn::SUCCESS && ret != SqlReturn::SUCCESS_WITH_INFO && ret != SqlReturn::NO_DATA {
+                let error_str = get_odbc_diagnostics(HandleType::Stmt, self.stmt_h.as_handle());
+                let row = status.iter().position(|s| *s == SQL_PARAM_ERROR).unwrap_or(processed.saturating_sub(1) as usize);
+                return CommonError::new(diag_kind(&CommonDefaultErrorKind::ExecuteFail, &error_str),
+                                        format!("batch row {} failed\n{}", row, error_str)).to_result();
+            }
 
-        loop {
-            let ret = SQLFetch(self.stmt_h);
-            if ret == SqlReturn::NO_DATA {
-                if col_buffer.is_empty() {
-                    return Ok(PairValueEnum::Null);
+            // drivers counting each parameter set answer with one result per row
+            let mut counts = Vec::with_capacity(rows);
+            loop {
+                counts.push(self.row_count()?.unwrap_or(0));
+                let ret = SQLMoreResults(self.stmt_h);
+                if ret == SqlReturn::NO_DATA {
+                    break;
+                }
+                if ret != SqlReturn::SUCCESS && ret != SqlReturn::SUCCESS_WITH_INFO {
+                    let error_str = get_odbc_diagnostics(HandleType::Stmt, self.stmt_h.as_handle());
+                    return CommonError::new(diag_kind(&CommonDefaultErrorKind::ExecuteFail, &error_str), error_str).to_result();
                 }
-                break;
             }
+            Ok(counts)
+        });
+
+        let _ = SQLFreeStmt(self.stmt_h, FreeStmtOption::Close);
+        let _ = SQLFreeStmt(self.stmt_h, FreeStmtOption::ResetParams);
+        let _ = SQLSetStmtAttr(self.stmt_h, StatementAttribute::ParamsetSize, 1 as Pointer, 0);
+        let _ = SQLSetStmtAttr(self.stmt_h, StatementAttribute::ParamStatusPtr, ptr::null_mut(), 0);
+        let _ = SQLSetStmtAttr(self.stmt_h, StatementAttribute::ParamsProcessedPtr, ptr::null_mut(), 0);
+
+        ret.map(Some)
+    }
+
+    unsafe fn bind_param_array(&mut self, columns : &'_ mut [OdbcParamColumn]) -> Result<(), CommonError> {
+        for (idx, column) in columns.iter_mut().enumerate() {
+            let ret = SQLBindParameter(
+                self.stmt_h,
+                (idx + 1) as USmallInt,
+                ParamType::Input,
+                column.ctype,
+                column.sql_t,
+                column.column_size as ULen,
+                column.digits as SmallInt,
+                column.data.as_mut_ptr() as Pointer,
+                column.width as Len,
+                column.ind.as_mut_ptr()
+            );
 
             if ret != SqlReturn::SUCCESS && ret != SqlReturn::SUCCESS_WITH_INFO {
                 let error_str = get_odbc_diagnostics(HandleType::Stmt, self.stmt_h.as_handle());
-                return CommonError::new(&CommonDefaultErrorKind::FetchFailed, error_str).to_result();
+                return CommonError::new(diag_kind(&CommonDefaultErrorKind::ThirdLibCallFail, &error_str), error_str).to_result();
             }
+        }
 
-            for idx in 0..cols.len() {
-                let ctype = Self::cast_sql_type_to_ctype(&cols[idx].1).map_err(|e|{
-                    CommonError::extend(&CommonDefaultErrorKind::Etc, "", e)
-                })?;
-                let mut is_chk_null_len = Len::default();
-
-                let mut int_buffer : libc::c_int = 0;
-                let mut double_buffer : libc::c_double = 0.0;
-                let mut float_buffer : libc::c_float = 0.0;
-                let mut bigint_buffer : libc::c_longlong = 0;
-                let mut string_buffer : Vec<libc::c_char> = vec![0; cols[idx].2];
-
-                let buffer_ptr = match ctype {
-                    CDataType::SLong => {
-                        Ok((&mut int_buffer as *mut libc::c_int) as Pointer)
-                    },
-                    CDataType::Float => {
-                        Ok((&mut float_buffer as *mut libc::c_float) as Pointer)
-                    },
-                    CDataType::Double => {
-                        Ok((&mut double_buffer as *mut libc::c_double) as Pointer)
-                    },
+        Ok(())
+    }
+
+    unsafe fn row_count(&mut self) -> Result<Option<u64>, CommonError> {
+        let mut row_count : Len = 0;
+        let ret = SQLRowCount(self.stmt_h, &mut row_count as *mut Len);
+
+        if ret != SqlReturn::SUCCESS && ret != SqlReturn::SUCCESS_WITH_INFO {
+            let error_str = get_odbc_diagnostics(HandleType::Stmt, self.stmt_h.as_handle());
+            return CommonError::new(diag_kind(&CommonDefaultErrorKind::ExecuteFail, &error_str), error_str).to_result();
+        }
+
+        // -1 is returned when the driver cannot tell the count
+        Ok(if row_count < 0 { None } else { Some(row_count as u64) })
+    }
+
+    unsafe fn fetch_row(&mut self, cols : &'_ [(String, SqlDataType, usize, bool)]) -> Result<Option<Vec<PairValueEnum>>, CommonError> {
+        let ret = SQLFetch(self.stmt_h);
+        if ret == SqlReturn::NO_DATA {
+            return Ok(None);
+        }
+
+        if ret != SqlReturn::SUCCESS && ret != SqlReturn::SUCCESS_WITH_INFO {
+            let error_str = get_odbc_diagnostics(HandleType::Stmt, self.stmt_h.as_handle());
+            return CommonError::new(diag_kind(&CommonDefaultErrorKind::FetchFailed, &error_str), error_str).to_result();
+        }
+
+        let mut row = Vec::with_capacity(cols.len());
+
+        for idx in 0..cols.len() {
+            let ctype = Self::cast_sql_type_to_ctype(&cols[idx].1).map_err(|e|{
+                CommonError::extend(&CommonDefaultErrorKind::Etc, "", e)
+            })?;
+            let mut is_chk_null_len = Len::default();
+
+            let mut int_buffer : libc::c_int = 0;
+            let mut short_buffer : libc::c_short = 0;
+            let mut tiny_buffer : libc::c_schar = 0;
+            let mut double_buffer : libc::c_double = 0.0;
+            let mut float_buffer : libc::c_float = 0.0;
+            let mut bigint_buffer : libc::c_longlong = 0;
+            let buffer_len = match cols[idx].1 {
+                SqlDataType::CHAR | SqlDataType::VARCHAR | SqlDataType::EXT_LONG_VARCHAR => cols[idx].2,
+                _ => cols[idx].2 + ODBC_CONVERT_PAD
+            };
+            let mut string_buffer : Vec<libc::c_char> = vec![0; buffer_len];
+
+            let buffer_ptr = match ctype {
+                CDataType::SLong => {
+                    Ok((&mut int_buffer as *mut libc::c_int) as Pointer)
+                },
+                CDataType::SShort => {
+                    Ok((&mut short_buffer as *mut libc::c_short) as Pointer)
+                },
+                CDataType::STinyInt => {
+                    Ok((&mut tiny_buffer as *mut libc::c_schar) as Pointer)
+                },
+                CDataType::Float => {
+                    Ok((&mut float_buffer as *mut libc::c_float) as Pointer)
+                },
+                CDataType::Double => {
+                    Ok((&mut double_buffer as *mut libc::c_double) as Pointer)
+                },
+                CDataType::Char => {
+                    Ok(string_buffer.as_mut_slice().as_mut_ptr()  as Pointer)
+                },
+                CDataType::SBigInt => {
+                    Ok((&mut bigint_buffer as *mut libc::c_longlong) as Pointer)
+                },
+                _ => {
+                    CommonError::new(&CommonDefaultErrorKind::Etc, format!("not support {:?}", ctype)).to_result()
+                }
+            }?;
+
+            let data_ret = SQLGetData(
+                self.stmt_h,
+                idx  as USmallInt + 1,
+                ctype,
+                buffer_ptr,
+                buffer_len as Len,
+                &mut is_chk_null_len as *mut Len);
+
+            if data_ret != SqlReturn::SUCCESS && data_ret != SqlReturn::SUCCESS_WITH_INFO {
+                let error_str = get_odbc_diagnostics(HandleType::Stmt, self.stmt_h.as_handle());
+                return CommonError::new(diag_kind(&CommonDefaultErrorKind::FetchFailed, &error_str), error_str).to_result();
+            }
+
+            if is_chk_null_len == NULL_DATA {
+                row.push(PairValueEnum::Null);
+            } else {
+                match ctype {
+                    CDataType::SLong => row.push(PairValueEnum::Int(int_buffer)),
+                    CDataType::SShort => row.push(PairValueEnum::SmallInt(short_buffer)),
+                    CDataType::STinyInt => row.push(PairValueEnum::TinyInt(tiny_buffer)),
+                    CDataType::Float => row.push(PairValueEnum::Float(float_buffer)),
+                    CDataType::Double => row.push(PairValueEnum::Double(double_buffer)),
+                    CDataType::SBigInt => row.push(PairValueEnum::BigInt(bigint_buffer)),
                     CDataType::Char => {
-                        Ok(string_buffer.as_mut_slice().as_mut_ptr()  as Pointer)
-                    },
-                    CDataType::SBigInt => {
-                        Ok((&mut bigint_buffer as *mut libc::c_longlong) as Pointer)
+                        let s = String::from_utf8(
+                            string_buffer.iter()
+                                .take_while(|&&c| c != 0)
+                                .map(|&c| c as u8).collect::<Vec<u8>>()).map_err(|e| {
+                            CommonError::new(&CommonDefaultErrorKind::SystemCallFail, format!("{}", e))
+                        })?;
+
+                        row.push(Self::parse_text_value(&cols[idx].1, s)?);
                     },
                     _ => {
-                        CommonError::new(&CommonDefaultErrorKind::Etc, format!("not support {:?}", ctype)).to_result()
+                        panic!("not support {:?}, broken ODBCStmt execute code", ctype);
                     }
-                }?;
+                };
+            }
+        }
 
-                let data_ret = SQLGetData(
-                    self.stmt_h,
-                    idx  as USmallInt + 1,
-                    ctype,
-                    buffer_ptr,
-                    cols[idx].2 as Len,
-                    &mut is_chk_null_len as *mut Len);
+        Ok(Some(row))
+    }
+}
 
-                if data_ret != SqlReturn::SUCCESS && data_ret != SqlReturn::SUCCESS_WITH_INFO {
-                    let error_str = get_odbc_diagnostics(HandleType::Stmt, self.stmt_h.as_handle());
-                    return CommonError::new(&CommonDefaultErrorKind::FetchFailed, error_str).to_result();
-                }
+// SQLCancel may be called from another thread while the statement executes
+struct ODBCStmtCancel(HStmt);
 
-                if is_chk_null_len == NULL_DATA {
-                    col_buffer.get_mut(&cols[idx].0)
-                        .expect(format!("broken col data name {}", &cols[idx].0).as_str())
-                        .push(PairValueEnum::Null);
-                } else {
-                    match ctype {
-                        CDataType::SLong => {
-                            col_buffer.get_mut(&cols[idx].0)
-                                .expect(format!("broken col data name {}", &cols[idx].0).as_str())
-                                .push(PairValueEnum::Int(int_buffer));
-                        },
-                        CDataType::Float => {
-                            col_buffer.get_mut(&cols[idx].0)
-                                .expect(format!("broken col data name {}", &cols[idx].0).as_str())
-                                .push(PairValueEnum::Float(float_buffer));
-                        },
-                        CDataType::Double => {
-                            col_buffer.get_mut(&cols[idx].0)
-                                .expect(format!("broken col data name {}", &cols[idx].0).as_str())
-                                .push(PairValueEnum::Double(double_buffer));
-                        },
-                        CDataType::SBigInt => {
-                            col_buffer.get_mut(&cols[idx].0)
-                                .expect(format!("broken col data name {}", &cols[idx].0).as_str())
-                                .push(PairValueEnum::BigInt(bigint_buffer));
-                        },
-                        CDataType::Char => {
-                            let s = String::from_utf8(
-                                string_buffer.iter()
-                                    .take_while(|&&c| c != 0)
-                                    .map(|&c| c as u8).collect::<Vec<u8>>()).map_err(|e| {
-                                CommonError::new(&CommonDefaultErrorKind::SystemCallFail, format!("{}", e))
-                            })?;
-
-                            col_buffer.get_mut(&cols[idx].0)
-                                .expect(format!("broken col data name {}", &cols[idx].0).as_str())
-                                .push(PairValueEnum::String(s));
-                        },
-                        _ => {
-                            panic!("not support {:?}, broken ODBCStmt execute code", ctype);
-                        }
-                    };
-                }
-            }
-        }
+unsafe impl Send for ODBCStmtCancel {}
 
-        Ok(PairValueEnum::Map(col_buffer.iter().fold(HashMap::with_capacity(col_buffer.len()), |mut acc,x| {
-            acc.insert(x.0.clone(), PairValueEnum::Array(x.1.clone()));
-            acc
-        })))
+impl ODBCStmtCancel {
+    fn cancel(&self) {
+        unsafe {
+            let _ = SQLCancel(self.0);
+        }
     }
 }
 
@@ -376,13 +698,108 @@ impl OdbcConnection {
                 return CommonError::new(&CommonDefaultErrorKind::ConnectFail, format!("odbc conn failed:\n\t{}", error_str)).to_result();
             }
         }
+        let param_array_counts = unsafe { Self::get_param_array_counts(conn_h.clone()) };
+
         Ok(OdbcConnection {
             env_h,
             conn_h,
             current_time_query,
-            current_time_col_name
+            current_time_col_name,
+            in_transaction : false,
+            param_array_counts
         })
     }
+
+    unsafe fn get_param_array_counts(conn_h : Handle) -> bool {
+        let mut value : UInteger = 0;
+        let ret = sql_get_info_raw(conn_h.as_hdbc(), SQL_PARAM_ARRAY_ROW_COUNTS, &mut value as *mut UInteger as Pointer,
+                                   size_of::<UInteger>() as SmallInt, ptr::null_mut());
+
+        (ret == SqlReturn::SUCCESS || ret == SqlReturn::SUCCESS_WITH_INFO) && value == SQL_PARC_NO_BATCH
+    }
+
+    fn execute_odbc_stmt(stmt : &'_ mut ODBCStmt, query : &'_ str, param : &PairValueEnum) -> Result<Vec<(String, SqlDataType, usize, bool)>, CommonError> {
+        let (query, param) = resolve_named_param(query, param, PairPlaceholderStyle::Question)?;
+        let (query, param) = (query.as_ref(), param.as_ref());
+
+        unsafe {
+            if let PairValueEnum::Array(arr) = param {
+                stmt.execute(query, Some(arr.as_slice()))
+            } else {
+                stmt.execute(query, None)
+            }
+        }.map_err(|e| {
+            CommonError::extend(&CommonDefaultErrorKind::ExecuteFail, "", e)
+        })
+    }
+
+    fn to_col_infos(cols : &'_ [(String, SqlDataType, usize, bool)]) -> Vec<PairColumnInfo> {
+        cols.iter().map(|c| PairColumnInfo::new(&c.0, format!("{:?}", c.1), c.3)).collect()
+    }
+
+    fn execute_odbc_cursor(&mut self, query : &'_ str, param : &PairValueEnum) -> Result<OdbcRowCursor<'_>, CommonError> {
+        let mut stmt = unsafe { self.alloc_stmt() }?;
+        let cols = Self::execute_odbc_stmt(&mut stmt, query, param)?;
+        let col_infos = Self::to_col_infos(&cols);
+
+        Ok(OdbcRowCursor { stmt, col_infos, cols, _conn : PhantomData })
+    }
+
+    // reads every row while the statement is still owned by the caller
+    fn fetch_odbc_result_set(stmt : &'_ mut ODBCStmt, query : &'_ str, param : &PairValueEnum) -> Result<PairResultSet, CommonError> {
+        let cols = Self::execute_odbc_stmt(stmt, query, param)?;
+        let mut result_set = PairResultSet::new(Self::to_col_infos(&cols));
+        if cols.is_empty() {
+            return Ok(result_set);
+        }
+
+        while let Some(row) = unsafe { stmt.fetch_row(cols.as_slice()) }.map_err(|e| {
+            CommonError::extend(&CommonDefaultErrorKind::FetchFailed, "", e)
+        })? {
+            result_set.push_row(row)?;
+        }
+        Ok(result_set)
+    }
+
+    unsafe fn alloc_stmt(&mut self) -> Result<ODBCStmt, CommonError> {
+        let mut stmt : Handle = Handle::null();
+
+        let ret = SQLAllocHandle(HandleType::Stmt, self.conn_h.clone(), &mut stmt);
+        if ret != SqlReturn::SUCCESS && ret != SqlReturn::SUCCESS_WITH_INFO {
+            return CommonError::new(&CommonDefaultErrorKind::ThirdLibCallFail, "STMT ALLOC FAILED").to_result();
+        }
+
+        Ok(ODBCStmt::new(stmt.as_hstmt()))
+    }
+
+    unsafe fn set_auto_commit(&mut self, on : bool) -> Result<(), CommonError> {
+        const AUTOCOMMIT_OFF : usize = 0;
+        const AUTOCOMMIT_ON : usize = 1;
+
+        let value = if on { AUTOCOMMIT_ON } else { AUTOCOMMIT_OFF };
+        let ret = SQLSetConnectAttr(self.conn_h.clone().as_hdbc(),
+                                    ConnectionAttribute::AUTOCOMMIT,
+                                    value as Pointer,
+                                    IS_UINTEGER);
+
+        if ret != SqlReturn::SUCCESS && ret != SqlReturn::SUCCESS_WITH_INFO {
+            let error_str = get_odbc_diagnostics(HandleType::Dbc, self.conn_h.clone());
+            return CommonError::new(diag_kind(&CommonDefaultErrorKind::ThirdLibCallFail, &error_str), error_str).to_result();
+        }
+        Ok(())
+    }
+
+    unsafe fn end_transaction(&mut self, completion : CompletionType) -> Result<(), CommonError> {
+        let ret = SQLEndTran(HandleType::Dbc, self.conn_h.clone(), completion);
+
+        if ret != SqlReturn::SUCCESS && ret != SqlReturn::SUCCESS_WITH_INFO {
+            let error_str = get_odbc_diagnostics(HandleType::Dbc, self.conn_h.clone());
+            let _ = self.set_auto_commit(true);
+            return CommonError::new(diag_kind(&CommonDefaultErrorKind::ExecuteFail, &error_str), error_str).to_result();
+        }
+
+        self.set_auto_commit(true)
+    }
 }
 
 impl Drop for OdbcConnection {
@@ -395,30 +812,34 @@ impl Drop for OdbcConnection {
     }
 }
 
-impl PairExecutor for OdbcConnection {
-    fn execute_pair(&mut self, query: &'_ str, param: &PairValueEnum) -> Result<PairValueEnum, CommonError> {
-        let mut stmt = unsafe {
-            let mut stmt : Handle = Handle::null();
+struct OdbcRowCursor<'a> {
+    stmt : ODBCStmt,
+    col_infos : Vec<PairColumnInfo>,
+    cols : Vec<(String, SqlDataType, usize, bool)>,
+    _conn : PhantomData<&'a mut OdbcConnection>
+}
 
-            let ret = SQLAllocHandle(HandleType::Stmt, self.conn_h.clone(), &mut stmt);
-            if ret != SqlReturn::SUCCESS && ret != SqlReturn::SUCCESS_WITH_INFO {
-                return CommonError::new(&CommonDefaultErrorKind::ThirdLibCallFail, "STMT ALLOC FAILED").to_result();
-            }
+impl PairRowCursor for OdbcRowCursor<'_> {
+    fn columns(&self) -> &'_ [PairColumnInfo] {
+        self.col_infos.as_slice()
+    }
 
-            ODBCStmt::new(stmt.as_hstmt())
-        };
+    fn next_row(&mut self) -> Result<Option<Vec<PairValueEnum>>, CommonError> {
+        if self.cols.is_empty() {
+            return Ok(None);
+        }
 
-        let data = unsafe {
-            if let PairValueEnum::Array(arr) = param {
-                stmt.execute(query, Some(arr.as_slice()))
-            } else {
-                stmt.execute(query, None)
-            }
+        unsafe {
+            self.stmt.fetch_row(self.cols.as_slice())
         }.map_err(|e| {
-            CommonError::extend(&CommonDefaultErrorKind::ExecuteFail, "", e)
-        })?;
+            CommonError::extend(&CommonDefaultErrorKind::FetchFailed, "", e)
+        })
+    }
+}
 
-        Ok(data)
+impl PairExecutor for OdbcConnection {
+    fn execute_pair_cursor<'a>(&'a mut self, query: &'_ str, param: &PairValueEnum) -> Result<Box<dyn PairRowCursor + 'a>, CommonError> {
+        Ok(Box::new(self.execute_odbc_cursor(query, param)?))
     }
 
     fn get_current_time(&mut self) -> Result<Duration, CommonError> {
@@ -446,4 +867,114 @@ impl PairExecutor for OdbcConnection {
             _ => CommonError::new(&CommonDefaultErrorKind::NoData, "not support data type").to_result()
         }
     }
-}
\ No newline at end of file
+
+    fn execute_pair_outcome(&mut self, query : &'_ str, param : &PairValueEnum) -> Result<PairExecuteOutcome, CommonError> {
+        let mut cursor = self.execute_odbc_cursor(query, param)?;
+        let mut result_set = PairResultSet::new(cursor.col_infos.clone());
+
+        while let Some(row) = cursor.next_row()? {
+            result_set.push_row(row)?;
+        }
+
+        let rows_affected = unsafe { cursor.stmt.row_count() }.map_err(|e| {
+            CommonError::extend(&CommonDefaultErrorKind::ExecuteFail, "row count failed", e)
+        })?;
+
+        // drivers commonly report -1 for SELECT, fall back to the fetched rows
+        let rows_affected = match rows_affected {
+            None if !result_set.columns().is_empty() => Some(result_set.row_count() as u64),
+            r => r
+        };
+
+        Ok(PairExecuteOutcome::new(rows_affected, result_set))
+    }
+
+    fn execute_pair_with(&mut self, query : &'_ str, param : &PairValueEnum, options : &PairCallOptions) -> Result<PairValueEnum, CommonError> {
+        if options.is_empty() {
+            return self.execute_pair(query, param);
+        }
+
+        // declared before the guard, the handle is freed only once the canceller is gone
+        let mut stmt = unsafe { self.alloc_stmt() }?;
+        let guard = options.start()?;
+        let cancel = ODBCStmtCancel(stmt.stmt_h);
+        guard.set_canceller(move || cancel.cancel())?;
+
+        let ret = Self::fetch_odbc_result_set(&mut stmt, query, param);
+        guard.finish(ret).map(|result_set| result_set.into_pair_map())
+    }
+
+    // one SQLExecute over a parameter array where the driver allows it, row by row otherwise
+    fn execute_batch(&mut self, query : &'_ str, rows : &'_ [PairValueEnum]) -> Result<Vec<u64>, CommonError> {
+        let (query, rows) = resolve_named_batch(query, rows, PairPlaceholderStyle::Question)?;
+        let query = query.as_ref();
+        if rows.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let params = rows.iter().map(get_array_param).collect::<Result<Vec<&[PairValueEnum]>, CommonError>>()?;
+        let mut stmt = unsafe { self.alloc_stmt() }?;
+        unsafe { stmt.prepare(query) }.map_err(|e| {
+            CommonError::extend(&CommonDefaultErrorKind::ExecuteFail, "prepare failed", e)
+        })?;
+
+        // a driver summing the array into one count still tells the counts of a plain insert
+        let insert = PairInsertTarget::parse(query, PairPlaceholderStyle::Question).is_some();
+        if self.param_array_counts || insert {
+            if let Some(mut columns) = OdbcParamColumn::from_rows(params.as_slice()) {
+                if let Some(counts) = unsafe { stmt.execute_param_array(columns.as_mut_slice(), params.len()) }? {
+                    if counts.len() == params.len() {
+                        return Ok(counts);
+                    }
+                    if insert {
+                        return Ok(vec![1; params.len()]);
+                    }
+                    return CommonError::new(&CommonDefaultErrorKind::ExecuteFail,
+                                            format!("driver reported {} counts for {} rows", counts.len(), params.len())).to_result();
+                }
+            }
+        }
+
+        let mut counts = Vec::with_capacity(params.len());
+        for p in params.iter() {
+            let cnt = unsafe { stmt.execute_prepared(p) }.map_err(|e| {
+                CommonError::extend(&CommonDefaultErrorKind::ExecuteFail, format!("batch row {} failed", counts.len()), e)
+            })?;
+            counts.push(cnt);
+        }
+
+        Ok(counts)
+    }
+
+    fn in_transaction(&self) -> bool {
+        self.in_transaction
+    }
+
+    fn begin(&mut self) -> Result<(), CommonError> {
+        if self.in_transaction {
+            return CommonError::new(&CommonDefaultErrorKind::InvalidApiCall, "OdbcConnection - begin - already in transaction").to_result();
+        }
+
+        unsafe { self.set_auto_commit(false) }?;
+        self.in_transaction = true;
+        Ok(())
+    }
+
+    fn commit(&mut self) -> Result<(), CommonError> {
+        if !self.in_transaction {
+            return CommonError::new(&CommonDefaultErrorKind::InvalidApiCall, "OdbcConnection - commit - not in transaction").to_result();
+        }
+
+        self.in_transaction = false;
+        unsafe { self.end_transaction(CompletionType::Commit) }
+    }
+
+    fn rollback(&mut self) -> Result<(), CommonError> {
+        if !self.in_transaction {
+            return CommonError::new(&CommonDefaultErrorKind::InvalidApiCall, "OdbcConnection - rollback - not in transaction").to_result();
+        }
+
+        self.in_transaction = false;
+        unsafe { self.end_transaction(CompletionType::Rollback) }
+    }
+}
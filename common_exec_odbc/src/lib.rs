@@ -2,41 +2,39 @@ mod db_conn;
 
 use common_err::CommonError;
 
-use common_core::collection::pool::get_thread_safe_pool;
+use common_core::collection::pool::get_thread_safe_pool_with_policy;
 use common_err::gen::CommonDefaultErrorKind;
-use common_pair_exec::{PairExecutor, PairExecutorInfo, PairExecutorPool};
+use common_pair_exec::{create_blocking_async_pair_pool, AsyncPairExecutorPool, PairBackendOptions, PairExecutor, PairExecutorInfo, PairExecutorPool};
 use db_conn::OdbcConnection;
 
-pub fn create_odbc_pair_conn_pool(name : String, info : PairExecutorInfo, alloc_size : usize) -> PairExecutorPool {
-    let gen_fn : Box<dyn Fn(()) -> Result<Box<dyn PairExecutor>, CommonError>> = (|info : PairExecutorInfo| {
+fn create_odbc_gen_fn(info : PairExecutorInfo) -> Box<dyn Fn(()) -> Result<Box<dyn PairExecutor + Send>, CommonError> + Send + Sync> {
+    (|info : PairExecutorInfo| {
 
         let real_fn  = move |_ : ()| {
-            let conn_info = info.clone();
-            
-            if let Some(extend) = conn_info.extend {
-                if extend.len() < 3 {
-                    return CommonError::new(&CommonDefaultErrorKind::NoData, 
-                                            format!("extend count :{}/3", extend.len())).to_result();
-                }
-                
-                let data_source = extend[0].clone();
-                let current_time_query = extend[1].clone();
-                let current_time_cols_name = extend[2].clone();
-                
-                let conn = OdbcConnection::new(data_source, 
-                                               current_time_query, 
-                                               current_time_cols_name);
-                match conn {
-                    Ok(ok) => Ok(Box::new(ok) as Box<dyn PairExecutor>),
-                    Err(err) => {Err(err)}
-                }
-            } else {
-                CommonError::new(&CommonDefaultErrorKind::InvalidApiCall, "extend is null").to_result()
+            let Some(PairBackendOptions::Odbc(options)) = &info.backend else {
+                return CommonError::new(&CommonDefaultErrorKind::InvalidApiCall,
+                                        "odbc options are missing, see PairExecutorInfo::from_url").to_result();
+            };
+
+            let conn = OdbcConnection::new(options.connection_string.clone(),
+                                           options.time_query.clone(),
+                                           options.time_column.clone());
+            match conn {
+                Ok(ok) => Ok(Box::new(ok) as Box<dyn PairExecutor + Send>),
+                Err(err) => {Err(err)}
             }
         };
 
         Box::new(real_fn)
-    })(info);
+    })(info)
+}
+
+pub fn create_odbc_pair_conn_pool(name : String, info : PairExecutorInfo, alloc_size : usize) -> PairExecutorPool {
+    let policy = info.pool.to_pool_policy();
+    get_thread_safe_pool_with_policy(name, create_odbc_gen_fn(info), alloc_size, policy)
+}
 
-    get_thread_safe_pool(name, gen_fn, alloc_size)
+// odbc calls block, they run on the tokio blocking thread pool
+pub fn create_odbc_async_pair_conn_pool(name : String, info : PairExecutorInfo, alloc_size : usize) -> AsyncPairExecutorPool {
+    create_blocking_async_pair_pool(name, create_odbc_gen_fn(info), alloc_size)
 }
\ No newline at end of file
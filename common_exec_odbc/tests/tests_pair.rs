@@ -6,17 +6,10 @@ use common_pair_exec::{PairExecutorInfo, PairValueEnum};
 
 
 fn connect_odbc_db() -> Result<common_pair_exec::PairExecutorPool, CommonError> {
-    let read_toml : HashMap<String, Vec<String>> = toml::from_str(include_str!("./tests.asset.toml")).map_err(|e| {
+    let read_toml : HashMap<String, String> = toml::from_str(include_str!("./tests.asset.toml")).map_err(|e| {
         CommonError::new(&CommonDefaultErrorKind::Etc, e.to_string())
     })?;
-    let info = PairExecutorInfo {
-        addr: vec![String::from("")],
-        name: String::from(""),
-        user: String::from(""),
-        password: String::from(""),
-        timeout_sec: 3600,
-        extend: Some(read_toml["extend"].clone())
-    };
+    let info = PairExecutorInfo::from_url(read_toml["url"].as_str())?;
 
     let p = create_odbc_pair_conn_pool("test".to_string(), info, 5);
     Ok(p)
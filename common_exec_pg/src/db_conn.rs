@@ -1,13 +1,33 @@
-use std::collections::HashMap;
+mod async_conn;
+mod pg_tls;
+mod pg_type;
+
+use std::error::Error;
+use std::net::IpAddr;
 use std::time::Duration;
-use postgres::Row;
+use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime, Utc};
+use postgres::{Row, RowIter, Statement};
+use postgres::binary_copy::BinaryCopyInWriter;
+use postgres::fallible_iterator::FallibleIterator;
 use postgres::types::ToSql;
 use postgres::types::Type;
+use postgres::error::SqlState;
+use common_core::collection::lru::LruCache;
 use common_err::{CommonError, gen::CommonDefaultErrorKind};
-use common_pair_exec::{PairExecutor, PairValueEnum};
+pub(crate) use async_conn::AsyncPostgresConnection;
+use pg_tls::PgTlsConnect;
+use pg_type::{PgJsonText, PgNumeric, PgParam};
+use common_pair_exec::{encode_url_component, resolve_named_batch, resolve_named_param, PairCallOptions, PairColumnInfo, PairExecuteOutcome, PairExecutor, PairInsertTarget, PairPlaceholderStyle, PairResultSet, PairRowCursor, PairStatementCacheStats, PairTlsOptions, PairValueEnum};
+
+const PING_TIMEOUT : Duration = Duration::from_secs(5);
+const PG_IN_RECOVERY_QUERY : &'static str = "SELECT pg_is_in_recovery()";
 
 pub struct PostgresConnection {
-    client : postgres::Client
+    client : postgres::Client,
+    in_transaction : bool,
+    stmt_cache : LruCache<String, Statement>,
+    // the cancel request opens its own connection with the same tls
+    tls : Option<PgTlsConnect>
 }
 
 macro_rules! get_pg_data {
@@ -20,36 +40,85 @@ macro_rules! get_pg_data {
             }
         }
     };
+    ($row_col : expr, $idx : expr, $origin_t : ty, $common_ident :ident, $common_t :ident, $convert : expr) => {
+        {
+            let opt : Option<$origin_t> = $row_col.get($idx);
+            match opt {
+                None => $common_ident::Null,
+                Some(s) => $common_ident::$common_t($convert(s))
+            }
+        }
+    };
 }
 
-fn convert_common_pair_value_to_pg_param(param : &'_ [PairValueEnum]) -> Result<Vec<&(dyn ToSql + Sync)>, CommonError> {
+fn convert_common_pair_value_to_pg_param(param : &'_ [PairValueEnum]) -> Result<Vec<PgParam<'_>>, CommonError> {
     param.iter().map(| x | {
-        let convert: Result<&(dyn ToSql + Sync), CommonError> = match x {
-            PairValueEnum::BigInt(i) => Ok(i),
-            PairValueEnum::Int(i) => Ok(i),
-            PairValueEnum::Null => Ok(&Option::<i64>::None),
-            PairValueEnum::Double(f) => Ok(f),
-            PairValueEnum::Bin(v) => Ok(v),
-            PairValueEnum::String(t) => Ok(t),
-            _ => {
+        match x {
+            PairValueEnum::Array(_) | PairValueEnum::Map(_) => {
                 CommonError::new(&CommonDefaultErrorKind::ParsingFail,
                                  format!("convert_common_value_to_pg_param - not support type({:?}), return null", x)).to_result()
+            },
+            _ => Ok(PgParam(x))
+        }
+    }).collect::<Result<Vec<PgParam<'_>>, CommonError>>()
+}
 
-            }
-        };
-        convert
-    }).collect::<Result<Vec<&(dyn ToSql + Sync)>, CommonError>>()
+pub(crate) fn get_array_param(param : &'_ PairValueEnum) -> Result<&'_ [PairValueEnum], CommonError> {
+    match param {
+        PairValueEnum::Array(a) => Ok(a.as_slice()),
+        PairValueEnum::Null => Ok(&[]),
+        _ => CommonError::new(&CommonDefaultErrorKind::InvalidApiCall, "not support type").to_result()
+    }
+}
+
+fn quote_pg_ident(name : &'_ str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+// the statement types its params in column order, they double as the COPY column types
+pub(crate) fn pg_copy_query(target : &'_ PairInsertTarget) -> String {
+    let table = match &target.schema {
+        Some(schema) => format!("{}.{}", quote_pg_ident(schema), quote_pg_ident(&target.table)),
+        None => quote_pg_ident(&target.table)
+    };
+    let columns = target.columns.iter().map(|c| quote_pg_ident(c)).collect::<Vec<String>>().join(", ");
+
+    format!("COPY {} ({}) FROM STDIN (FORMAT binary)", table, columns)
+}
+
+// lost connections and conflicts the server rolled back, both worth a retry
+pub(crate) fn pg_error_kind(err : &'_ postgres::Error, kind : &'static CommonDefaultErrorKind) -> &'static CommonDefaultErrorKind {
+    if err.is_closed() {
+        return &CommonDefaultErrorKind::ConnectFail;
+    }
+
+    match err.code() {
+        Some(code) if code.code().starts_with("08") => &CommonDefaultErrorKind::ConnectFail,
+        Some(&SqlState::T_R_SERIALIZATION_FAILURE) | Some(&SqlState::T_R_DEADLOCK_DETECTED) |
+        Some(&SqlState::ADMIN_SHUTDOWN) | Some(&SqlState::CRASH_SHUTDOWN) | Some(&SqlState::CANNOT_CONNECT_NOW) => &CommonDefaultErrorKind::Transient,
+        // no server code, the socket itself failed
+        None if err.source().is_some_and(|s| s.is::<std::io::Error>()) => &CommonDefaultErrorKind::ConnectFail,
+        _ => kind
+    }
 }
 
 impl PostgresConnection {
-    fn create_pg_url(username : &'_ str, password : &'_ str, addr : &'_ str, db_name : &'_ str) -> String {
-        format!("postgresql://{username}:{password}@{addr}/{db_name}")
+    fn create_pg_url(username : &'_ str, password : &'_ str, addr : &'_ str, db_name : &'_ str, tls : bool) -> String {
+        let (username, password, db_name) = (encode_url_component(username), encode_url_component(password), encode_url_component(db_name));
+        let url = format!("postgresql://{username}:{password}@{addr}/{db_name}");
+        if tls { url + "?sslmode=require" } else { url }
     }
 
-    pub(crate) fn new(app_name : &'_ str, user : &'_ str, password : &'_ str, addr : &'_ str, name : &'_ str, timeout_sec : u32) -> Result<Self, CommonError> {
-        let url = Self::create_pg_url(user, password, addr, name);
+    pub(crate) fn new(app_name : &'_ str, user : &'_ str, password : &'_ str, addr : &'_ str, name : &'_ str, timeout_sec : u32, statement_cache_size : usize,
+                      tls : Option<&'_ PairTlsOptions>) -> Result<Self, CommonError> {
+        let tls = tls.map(PgTlsConnect::new).transpose()?;
+        let url = Self::create_pg_url(user, password, addr, name, tls.is_some());
 
-        let mut conn = match postgres::Client::connect(url.as_str(), postgres::NoTls) {
+        let connected = match &tls {
+            Some(connector) => postgres::Client::connect(url.as_str(), connector.clone()),
+            None => postgres::Client::connect(url.as_str(), postgres::NoTls)
+        };
+        let mut conn = match connected {
             Ok(ok) => Ok(ok),
             Err(err) => CommonError::new(&CommonDefaultErrorKind::ConnectFail, 
                                          format!("PostgresConnection - new - {}", err.to_string())).to_result()
@@ -59,14 +128,28 @@ impl PostgresConnection {
             CommonError::new(&CommonDefaultErrorKind::ExecuteFail, e.to_string())
         })?;
 
-        conn.execute(format!("set application_name = '{app_name}'").as_str(), &[]).map_err(|e| {
+        conn.execute(format!("set application_name = '{}'", app_name.replace('\'', "''")).as_str(), &[]).map_err(|e| {
             CommonError::new(&CommonDefaultErrorKind::ExecuteFail, e.to_string())
         })?;
 
         Ok(PostgresConnection {
-            client : conn
+            client : conn,
+            in_transaction : false,
+            stmt_cache : LruCache::new(statement_cache_size),
+            tls
         })
     }
+    pub(crate) fn check_primary(&mut self) -> Result<(), CommonError> {
+        let row = self.client.query_one(PG_IN_RECOVERY_QUERY, &[]).map_err(|e| {
+            CommonError::new(&CommonDefaultErrorKind::ConnectFail, format!("PostgresConnection - check_primary - {}", e))
+        })?;
+
+        if row.get::<_, bool>(0) {
+            return CommonError::new(&CommonDefaultErrorKind::ConnectFail, "PostgresConnection - check_primary - server is in recovery").to_result();
+        }
+        Ok(())
+    }
+
     fn get_current_duration(&mut self) -> Result<std::time::Duration, CommonError> {
         let ret = self.execute_pair("SELECT (EXTRACT(EPOCH FROM clock_timestamp()) * 1000)::bigint  AS unix_timestamp", &PairValueEnum::Null).map_err(|e| {
             CommonError::extend(&CommonDefaultErrorKind::ExecuteFail, "get timestamp failed", e)
@@ -93,17 +176,16 @@ impl PostgresConnection {
         }
     }
 
-    fn run_execute_query(&mut self, query : &'_ str, param : Vec<&(dyn ToSql + Sync)>) -> Result<Vec<Row>, CommonError> {
-        match self.client.query(query, param.as_slice()) {
-            Ok(ok) => Ok(ok),
-            Err(err) => CommonError::new(&CommonDefaultErrorKind::InvalidApiCall,
-                                         format!("PostgresConnection, [query:{:.1024},dbErr:{}]", query, err.to_string())).to_result()
-        }
+    fn run_transaction_query(&mut self, query : &'_ str) -> Result<(), CommonError> {
+        self.client.batch_execute(query).map_err(|e| {
+            CommonError::new(pg_error_kind(&e, &CommonDefaultErrorKind::ExecuteFail),
+                             format!("PostgresConnection, [query:{},dbErr:{}]", query, e))
+        })
     }
-}
 
-impl PairExecutor for PostgresConnection {
-    fn execute_pair(&mut self, query: &'_ str, param: &PairValueEnum) -> Result<PairValueEnum, CommonError> {
+    fn open_cursor(&mut self, query : &'_ str, param : &PairValueEnum) -> Result<PostgresRowCursor<'_>, CommonError> {
+        let (query, param) = resolve_named_param(query, param, PairPlaceholderStyle::Dollar)?;
+        let (query, param) = (query.as_ref(), param.as_ref());
         let p = if let PairValueEnum::Array(a) = &param {
             Ok(a.as_slice())
         } else if param == &PairValueEnum::Null {
@@ -115,50 +197,253 @@ impl PairExecutor for PostgresConnection {
 
         let pg_param  = convert_common_pair_value_to_pg_param(p)?;
 
-        let rows = self.run_execute_query(query, pg_param).map_err(|e| {
+        let stmt = self.get_statement(query)?;
+
+        // RowDescription has no nullability, every column is reported as nullable
+        let cols = stmt.columns().iter().map(|c| PairColumnInfo::new(c.name(), c.type_().name(), true)).collect();
+        let col_types = stmt.columns().iter().map(|c| c.type_().clone()).collect();
+
+        let iter = Self::run_execute_query(&mut self.client, &mut self.stmt_cache, &stmt, query, &pg_param).map_err(|e| {
             CommonError::extend(&CommonDefaultErrorKind::ExecuteFail, "run_execute_query failed", e)
         })?;
 
-        if rows.len() <= 0 {
-            return Ok(PairValueEnum::Null);
-        }
-
-        let row_len = rows.len();
-        if row_len <= 0 {
-            return Ok(PairValueEnum::Null);
-        }
-        let mut map = HashMap::new();
-
-        let cols = rows[0].columns();
-        for col_idx in 0..cols.len() {
-            let ty = cols[col_idx].type_();
-            let col_name = cols[col_idx].name().to_string();
-            let mut v = Vec::new();
-
-            for row_idx in 0..row_len {
-                let d = match ty {
-                    &Type::BOOL => Ok(get_pg_data!(rows[row_idx], col_idx, bool, PairValueEnum, Bool)),
-                    &Type::CHAR | &Type::VARCHAR | &Type::TEXT => Ok(get_pg_data!(rows[row_idx], col_idx, String, PairValueEnum, String)),
-                    &Type::FLOAT4 | &Type::FLOAT8 | &Type::NUMERIC => Ok(get_pg_data!(rows[row_idx], col_idx, f64, PairValueEnum, Double)),
-                    &Type::INT2 | &Type::INT4 =>Ok(get_pg_data!(rows[row_idx], col_idx, i32, PairValueEnum, Int)),
-                    &Type::INT8 => Ok(get_pg_data!(rows[row_idx], col_idx, i64, PairValueEnum, BigInt)),
-                    &Type::BYTEA => Ok(get_pg_data!(rows[row_idx], col_idx, Vec<u8>, PairValueEnum, Bin)),
-                    _ => {
-                        CommonError::new(&CommonDefaultErrorKind::ParsingFail,
-                                         format!("PostgresConnection - execute - not support this type({}), return NULL", ty)).to_result()
-                    }
-                }?;
-
-                v.push(d);
+        Ok(PostgresRowCursor { cols, col_types, iter })
+    }
+
+    fn get_statement(&mut self, query : &'_ str) -> Result<Statement, CommonError> {
+        if let Some(stmt) = self.stmt_cache.get(query) {
+            return Ok(stmt.clone());
+        }
+
+        let stmt = self.client.prepare(query).map_err(|e| {
+            CommonError::new(pg_error_kind(&e, &CommonDefaultErrorKind::InvalidApiCall),
+                             format!("PostgresConnection, [query:{:.1024},dbErr:{}]", query, e))
+        })?;
+
+        self.stmt_cache.put(query.to_string(), stmt.clone());
+        Ok(stmt)
+    }
+
+    // schema changes invalidate server side plans, the statement must be prepared again
+    fn invalidate_statement(cache : &mut LruCache<String, Statement>, query : &'_ str, err : &postgres::Error) {
+        match err.code() {
+            Some(&SqlState::FEATURE_NOT_SUPPORTED) | Some(&SqlState::INVALID_SQL_STATEMENT_NAME) |
+            Some(&SqlState::UNDEFINED_TABLE) | Some(&SqlState::UNDEFINED_COLUMN) => {
+                cache.remove(query);
+            },
+            _ => {}
+        }
+    }
+
+    // COPY is a single statement, the rows land all together or not at all
+    fn copy_in_rows(&mut self, target : &'_ PairInsertTarget, stmt : &'_ Statement, params : &'_ [Vec<PgParam<'_>>]) -> Result<Vec<u64>, CommonError> {
+        let copy = pg_copy_query(target);
+        let copy_err = |e : postgres::Error| {
+            CommonError::new(pg_error_kind(&e, &CommonDefaultErrorKind::ExecuteFail),
+                             format!("PostgresConnection, [query:{:.1024},dbErr:{}]", copy, e))
+        };
+
+        let writer = self.client.copy_in(copy.as_str()).map_err(copy_err)?;
+        let mut writer = BinaryCopyInWriter::new(writer, stmt.params());
+        for pg_param in params.iter() {
+            let pg_ref = pg_param.iter().map(|p| p as &(dyn ToSql + Sync)).collect::<Vec<&(dyn ToSql + Sync)>>();
+            writer.write(pg_ref.as_slice()).map_err(copy_err)?;
+        }
+        writer.finish().map_err(copy_err)?;
+
+        Ok(vec![1; params.len()])
+    }
+
+    fn run_execute_query<'a>(client : &'a mut postgres::Client, cache : &'_ mut LruCache<String, Statement>,
+                             stmt : &Statement, query : &'_ str, param : &'_ [PgParam<'_>]) -> Result<RowIter<'a>, CommonError> {
+        match client.query_raw(stmt, param.iter().map(|p| p as &dyn ToSql)) {
+            Ok(ok) => Ok(ok),
+            Err(err) => {
+                Self::invalidate_statement(cache, query, &err);
+                CommonError::new(pg_error_kind(&err, &CommonDefaultErrorKind::InvalidApiCall),
+                                 format!("PostgresConnection, [query:{:.1024},dbErr:{}]", query, err.to_string())).to_result()
+            }
+        }
+    }
+}
+
+struct PostgresRowCursor<'a> {
+    cols : Vec<PairColumnInfo>,
+    col_types : Vec<Type>,
+    iter : RowIter<'a>
+}
+
+fn convert_pg_row(col_types : &'_ [Type], row : &Row) -> Result<Vec<PairValueEnum>, CommonError> {
+    let mut v = Vec::with_capacity(col_types.len());
+
+    for col_idx in 0..col_types.len() {
+        let d = match &col_types[col_idx] {
+            &Type::BOOL => Ok(get_pg_data!(row, col_idx, bool, PairValueEnum, Bool)),
+            &Type::BPCHAR | &Type::VARCHAR | &Type::TEXT | &Type::NAME => Ok(get_pg_data!(row, col_idx, String, PairValueEnum, String)),
+            &Type::CHAR => Ok(get_pg_data!(row, col_idx, i8, PairValueEnum, TinyInt)),
+            &Type::FLOAT4 => Ok(get_pg_data!(row, col_idx, f32, PairValueEnum, Float)),
+            &Type::FLOAT8 => Ok(get_pg_data!(row, col_idx, f64, PairValueEnum, Double)),
+            &Type::NUMERIC => Ok(get_pg_data!(row, col_idx, PgNumeric, PairValueEnum, Decimal, |n : PgNumeric| n.0)),
+            &Type::INT2 => Ok(get_pg_data!(row, col_idx, i16, PairValueEnum, SmallInt)),
+            &Type::INT4 =>Ok(get_pg_data!(row, col_idx, i32, PairValueEnum, Int)),
+            &Type::INT8 => Ok(get_pg_data!(row, col_idx, i64, PairValueEnum, BigInt)),
+            &Type::BYTEA => Ok(get_pg_data!(row, col_idx, Vec<u8>, PairValueEnum, Bin)),
+            &Type::TIMESTAMPTZ => Ok(get_pg_data!(row, col_idx, DateTime<Utc>, PairValueEnum, Timestamp, |t : DateTime<Utc>| t.fixed_offset())),
+            // timestamp without time zone is read as utc
+            &Type::TIMESTAMP => Ok(get_pg_data!(row, col_idx, NaiveDateTime, PairValueEnum, Timestamp, |t : NaiveDateTime| t.and_utc().fixed_offset())),
+            &Type::DATE => Ok(get_pg_data!(row, col_idx, NaiveDate, PairValueEnum, Date)),
+            &Type::TIME => Ok(get_pg_data!(row, col_idx, NaiveTime, PairValueEnum, Time)),
+            &Type::UUID => Ok(get_pg_data!(row, col_idx, uuid::Uuid, PairValueEnum, Uuid)),
+            &Type::JSON | &Type::JSONB => Ok(get_pg_data!(row, col_idx, PgJsonText, PairValueEnum, Json, |j : PgJsonText| j.0)),
+            &Type::INET => Ok(get_pg_data!(row, col_idx, IpAddr, PairValueEnum, Inet)),
+            ty => {
+                CommonError::new(&CommonDefaultErrorKind::ParsingFail,
+                                 format!("PostgresConnection - execute - not support this type({}), return NULL", ty)).to_result()
             }
-            map.insert(col_name.clone(), PairValueEnum::Array(v));
+        }?;
+
+        v.push(d);
+    }
+
+    Ok(v)
+}
+
+impl PairRowCursor for PostgresRowCursor<'_> {
+    fn columns(&self) -> &'_ [PairColumnInfo] {
+        self.cols.as_slice()
+    }
+
+    fn next_row(&mut self) -> Result<Option<Vec<PairValueEnum>>, CommonError> {
+        let row = self.iter.next().map_err(|e| {
+            CommonError::new(pg_error_kind(&e, &CommonDefaultErrorKind::FetchFailed), format!("PostgresRowCursor - next_row - {}", e))
+        })?;
+
+        match row {
+            Some(r) => Ok(Some(convert_pg_row(self.col_types.as_slice(), &r)?)),
+            None => Ok(None)
+        }
+    }
+}
+
+impl PairExecutor for PostgresConnection {
+    fn execute_pair_cursor<'a>(&'a mut self, query: &'_ str, param: &PairValueEnum) -> Result<Box<dyn PairRowCursor + 'a>, CommonError> {
+        Ok(Box::new(self.open_cursor(query, param)?))
+    }
 
+    fn execute_pair_outcome(&mut self, query : &'_ str, param : &PairValueEnum) -> Result<PairExecuteOutcome, CommonError> {
+        let mut cursor = self.open_cursor(query, param)?;
+        let mut result_set = PairResultSet::new(cursor.cols.clone());
+
+        while let Some(row) = cursor.next_row()? {
+            result_set.push_row(row)?;
         }
 
-        Ok(PairValueEnum::Map(map))
+        // command tag count, only known once every row was read. statements returning rows report none
+        let rows_affected = if cursor.cols.is_empty() { cursor.iter.rows_affected() } else { None };
+        Ok(PairExecuteOutcome::new(rows_affected, result_set))
     }
 
     fn get_current_time(&mut self) -> Result<Duration, CommonError> {
         self.get_current_duration()
     }
-}
\ No newline at end of file
+
+    fn ping(&mut self) -> Result<(), CommonError> {
+        self.client.is_valid(PING_TIMEOUT).map_err(|e| {
+            CommonError::new(&CommonDefaultErrorKind::ConnectFail, format!("PostgresConnection - ping - {}", e))
+        })
+    }
+
+    fn is_closed(&mut self) -> bool {
+        self.client.is_closed()
+    }
+
+    // the server aborts the statement with query_canceled, the connection stays usable
+    fn execute_pair_with(&mut self, query : &'_ str, param : &PairValueEnum, options : &PairCallOptions) -> Result<PairValueEnum, CommonError> {
+        if options.is_empty() {
+            return self.execute_pair(query, param);
+        }
+
+        let guard = options.start()?;
+        let (token, tls) = (self.client.cancel_token(), self.tls.clone());
+        guard.set_canceller(move || {
+            let _ = match &tls {
+                Some(connector) => token.cancel_query(connector.clone()),
+                None => token.cancel_query(postgres::NoTls)
+            };
+        })?;
+
+        let ret = self.execute_pair(query, param);
+        guard.finish(ret)
+    }
+
+    // a plain insert is streamed through binary COPY, postgres::Client has no pipelining for anything else
+    fn execute_batch(&mut self, query : &'_ str, rows : &'_ [PairValueEnum]) -> Result<Vec<u64>, CommonError> {
+        let (query, rows) = resolve_named_batch(query, rows, PairPlaceholderStyle::Dollar)?;
+        let query = query.as_ref();
+        if rows.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let stmt = self.get_statement(query)?;
+        let params = rows.iter().map(|row| convert_common_pair_value_to_pg_param(get_array_param(row)?))
+            .collect::<Result<Vec<Vec<PgParam<'_>>>, CommonError>>()?;
+
+        if let Some(target) = PairInsertTarget::parse(query, PairPlaceholderStyle::Dollar) {
+            return self.copy_in_rows(&target, &stmt, params.as_slice());
+        }
+
+        let mut counts = Vec::with_capacity(rows.len());
+        for pg_param in params.iter() {
+            let pg_ref = pg_param.iter().map(|p| p as &(dyn ToSql + Sync)).collect::<Vec<&(dyn ToSql + Sync)>>();
+            let cnt = self.client.execute(&stmt, pg_ref.as_slice()).map_err(|e| {
+                Self::invalidate_statement(&mut self.stmt_cache, query, &e);
+                CommonError::new(pg_error_kind(&e, &CommonDefaultErrorKind::ExecuteFail),
+                                 format!("PostgresConnection, [query:{:.1024},row:{},dbErr:{}]", query, counts.len(), e))
+            })?;
+            counts.push(cnt);
+        }
+
+        Ok(counts)
+    }
+
+    fn statement_cache_stats(&self) -> Option<PairStatementCacheStats> {
+        Some(PairStatementCacheStats {
+            hit : self.stmt_cache.hit_count(),
+            miss : self.stmt_cache.miss_count(),
+            size : self.stmt_cache.len(),
+            capacity : self.stmt_cache.capacity()
+        })
+    }
+
+    fn in_transaction(&self) -> bool {
+        self.in_transaction
+    }
+
+    fn begin(&mut self) -> Result<(), CommonError> {
+        if self.in_transaction {
+            return CommonError::new(&CommonDefaultErrorKind::InvalidApiCall, "PostgresConnection - begin - already in transaction").to_result();
+        }
+
+        self.run_transaction_query("BEGIN TRANSACTION")?;
+        self.in_transaction = true;
+        Ok(())
+    }
+
+    fn commit(&mut self) -> Result<(), CommonError> {
+        if !self.in_transaction {
+            return CommonError::new(&CommonDefaultErrorKind::InvalidApiCall, "PostgresConnection - commit - not in transaction").to_result();
+        }
+
+        self.in_transaction = false;
+        self.run_transaction_query("COMMIT")
+    }
+
+    fn rollback(&mut self) -> Result<(), CommonError> {
+        if !self.in_transaction {
+            return CommonError::new(&CommonDefaultErrorKind::InvalidApiCall, "PostgresConnection - rollback - not in transaction").to_result();
+        }
+
+        self.in_transaction = false;
+        self.run_transaction_query("ROLLBACK")
+    }
+}
@@ -0,0 +1,231 @@
+use std::time::Duration;
+use async_trait::async_trait;
+use futures_util::{pin_mut, TryStreamExt};
+use futures_util::future::join_all;
+use postgres::Statement;
+use postgres::types::ToSql;
+use tokio_postgres::binary_copy::BinaryCopyInWriter;
+use common_core::collection::lru::LruCache;
+use common_err::{CommonError, gen::CommonDefaultErrorKind};
+use common_pair_exec::{resolve_named_batch, resolve_named_param, AsyncPairExecutor, PairColumnInfo, PairExecuteOutcome, PairInsertTarget,
+                       PairPlaceholderStyle, PairResultSet, PairStatementCacheStats, PairTlsOptions, PairValueEnum};
+use super::{convert_common_pair_value_to_pg_param, convert_pg_row, get_array_param, pg_copy_query, pg_error_kind, PgParam, PgTlsConnect, PostgresConnection, PG_IN_RECOVERY_QUERY};
+
+pub struct AsyncPostgresConnection {
+    client : tokio_postgres::Client,
+    in_transaction : bool,
+    stmt_cache : LruCache<String, Statement>
+}
+
+impl AsyncPostgresConnection {
+    pub(crate) async fn new(app_name : &'_ str, user : &'_ str, password : &'_ str, addr : &'_ str, name : &'_ str,
+                            timeout_sec : u32, statement_cache_size : usize, tls : Option<&'_ PairTlsOptions>) -> Result<Self, CommonError> {
+        let tls = tls.map(PgTlsConnect::new).transpose()?;
+        let url = PostgresConnection::create_pg_url(user, password, addr, name, tls.is_some());
+        let connect_err = |e : tokio_postgres::Error| {
+            CommonError::new(&CommonDefaultErrorKind::ConnectFail, format!("AsyncPostgresConnection - new - {}", e))
+        };
+
+        // the connection task ends with the client, a broken socket surfaces on the next call
+        let client = match tls {
+            Some(connector) => {
+                let (client, connection) = tokio_postgres::connect(url.as_str(), connector).await.map_err(connect_err)?;
+                tokio::spawn(async move {
+                    let _ = connection.await;
+                });
+                client
+            },
+            None => {
+                let (client, connection) = tokio_postgres::connect(url.as_str(), tokio_postgres::NoTls).await.map_err(connect_err)?;
+                tokio::spawn(async move {
+                    let _ = connection.await;
+                });
+                client
+            }
+        };
+
+        client.batch_execute(format!("SET statement_timeout='{}s'; set application_name = '{}'", timeout_sec, app_name.replace('\'', "''")).as_str()).await.map_err(|e| {
+            CommonError::new(&CommonDefaultErrorKind::ExecuteFail, e.to_string())
+        })?;
+
+        Ok(AsyncPostgresConnection {
+            client,
+            in_transaction : false,
+            stmt_cache : LruCache::new(statement_cache_size)
+        })
+    }
+
+    pub(crate) async fn check_primary(&mut self) -> Result<(), CommonError> {
+        let row = self.client.query_one(PG_IN_RECOVERY_QUERY, &[]).await.map_err(|e| {
+            CommonError::new(&CommonDefaultErrorKind::ConnectFail, format!("AsyncPostgresConnection - check_primary - {}", e))
+        })?;
+
+        if row.get::<_, bool>(0) {
+            return CommonError::new(&CommonDefaultErrorKind::ConnectFail, "AsyncPostgresConnection - check_primary - server is in recovery").to_result();
+        }
+        Ok(())
+    }
+
+    async fn get_statement(&mut self, query : &'_ str) -> Result<Statement, CommonError> {
+        if let Some(stmt) = self.stmt_cache.get(query) {
+            return Ok(stmt.clone());
+        }
+
+        let stmt = self.client.prepare(query).await.map_err(|e| {
+            CommonError::new(pg_error_kind(&e, &CommonDefaultErrorKind::InvalidApiCall),
+                             format!("AsyncPostgresConnection, [query:{:.1024},dbErr:{}]", query, e))
+        })?;
+
+        self.stmt_cache.put(query.to_string(), stmt.clone());
+        Ok(stmt)
+    }
+
+    async fn run_transaction_query(&mut self, query : &'_ str) -> Result<(), CommonError> {
+        self.client.batch_execute(query).await.map_err(|e| {
+            CommonError::new(pg_error_kind(&e, &CommonDefaultErrorKind::ExecuteFail),
+                             format!("AsyncPostgresConnection, [query:{},dbErr:{}]", query, e))
+        })
+    }
+}
+
+#[async_trait]
+impl AsyncPairExecutor for AsyncPostgresConnection {
+    async fn execute_pair_result_set(&mut self, query : &'_ str, param : &PairValueEnum) -> Result<PairResultSet, CommonError> {
+        Ok(self.execute_pair_outcome(query, param).await?.result_set)
+    }
+
+    async fn execute_pair_outcome(&mut self, query : &'_ str, param : &PairValueEnum) -> Result<PairExecuteOutcome, CommonError> {
+        let (query, param) = resolve_named_param(query, param, PairPlaceholderStyle::Dollar)?;
+        let query = query.as_ref();
+        let pg_param = convert_common_pair_value_to_pg_param(get_array_param(param.as_ref())?)?;
+
+        let stmt = self.get_statement(query).await?;
+        let col_types = stmt.columns().iter().map(|c| c.type_().clone()).collect::<Vec<_>>();
+        let mut result_set = PairResultSet::new(stmt.columns().iter().map(|c| PairColumnInfo::new(c.name(), c.type_().name(), true)).collect());
+
+        let stream = match self.client.query_raw(&stmt, pg_param.iter().map(|p| p as &(dyn ToSql + Sync))).await {
+            Ok(ok) => ok,
+            Err(err) => {
+                PostgresConnection::invalidate_statement(&mut self.stmt_cache, query, &err);
+                return CommonError::new(pg_error_kind(&err, &CommonDefaultErrorKind::InvalidApiCall),
+                                        format!("AsyncPostgresConnection, [query:{:.1024},dbErr:{}]", query, err)).to_result();
+            }
+        };
+        pin_mut!(stream);
+
+        while let Some(row) = stream.try_next().await.map_err(|e| {
+            CommonError::new(pg_error_kind(&e, &CommonDefaultErrorKind::FetchFailed), format!("AsyncPostgresConnection - next_row - {}", e))
+        })? {
+            result_set.push_row(convert_pg_row(col_types.as_slice(), &row)?)?;
+        }
+
+        let rows_affected = if col_types.is_empty() { stream.rows_affected() } else { None };
+        Ok(PairExecuteOutcome::new(rows_affected, result_set))
+    }
+
+    async fn get_current_time(&mut self) -> Result<Duration, CommonError> {
+        let ret = self.execute_pair_result_set("SELECT (EXTRACT(EPOCH FROM clock_timestamp()) * 1000)::bigint  AS unix_timestamp", &PairValueEnum::Null).await.map_err(|e| {
+            CommonError::extend(&CommonDefaultErrorKind::ExecuteFail, "get timestamp failed", e)
+        })?;
+
+        match ret.get_by_name(0, "unix_timestamp") {
+            Some(PairValueEnum::BigInt(unix_data)) => Ok(Duration::from_millis(*unix_data as u64)),
+            Some(_) => CommonError::new(&CommonDefaultErrorKind::NotMatchArgs, "no unix_timestamp value, cols is not int").to_result(),
+            None => CommonError::new(&CommonDefaultErrorKind::NoData, "no unix_timestamp value, unix_timestamp not exists").to_result()
+        }
+    }
+
+    async fn ping(&mut self) -> Result<(), CommonError> {
+        self.client.simple_query("").await.map(|_| ()).map_err(|e| {
+            CommonError::new(&CommonDefaultErrorKind::ConnectFail, format!("AsyncPostgresConnection - ping - {}", e))
+        })
+    }
+
+    // a plain insert is streamed through binary COPY, other statements are pipelined and answered in order
+    async fn execute_batch(&mut self, query : &'_ str, rows : &'_ [PairValueEnum]) -> Result<Vec<u64>, CommonError> {
+        let (query, rows) = resolve_named_batch(query, rows, PairPlaceholderStyle::Dollar)?;
+        let query = query.as_ref();
+        if rows.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let stmt = self.get_statement(query).await?;
+        let params = rows.iter().map(|row| convert_common_pair_value_to_pg_param(get_array_param(row)?))
+            .collect::<Result<Vec<Vec<PgParam<'_>>>, CommonError>>()?;
+        let pg_refs = params.iter().map(|pg_param| {
+            pg_param.iter().map(|p| p as &(dyn ToSql + Sync)).collect::<Vec<&(dyn ToSql + Sync)>>()
+        }).collect::<Vec<Vec<&(dyn ToSql + Sync)>>>();
+
+        if let Some(target) = PairInsertTarget::parse(query, PairPlaceholderStyle::Dollar) {
+            let copy = pg_copy_query(&target);
+            let copy_err = |e : tokio_postgres::Error| {
+                CommonError::new(pg_error_kind(&e, &CommonDefaultErrorKind::ExecuteFail),
+                                 format!("AsyncPostgresConnection, [query:{:.1024},dbErr:{}]", copy, e))
+            };
+
+            let sink = self.client.copy_in(copy.as_str()).await.map_err(copy_err)?;
+            let writer = BinaryCopyInWriter::new(sink, stmt.params());
+            pin_mut!(writer);
+            for pg_ref in pg_refs.iter() {
+                writer.as_mut().write(pg_ref.as_slice()).await.map_err(copy_err)?;
+            }
+            writer.finish().await.map_err(copy_err)?;
+
+            return Ok(vec![1; rows.len()]);
+        }
+
+        // every execute carries its own Sync, outside a transaction a failing row does not stop the ones behind it
+        let rets = join_all(pg_refs.iter().map(|pg_ref| self.client.execute(&stmt, pg_ref.as_slice()))).await;
+
+        let mut counts = Vec::with_capacity(rows.len());
+        for ret in rets {
+            match ret {
+                Ok(cnt) => counts.push(cnt),
+                Err(err) => {
+                    PostgresConnection::invalidate_statement(&mut self.stmt_cache, query, &err);
+                    return CommonError::new(pg_error_kind(&err, &CommonDefaultErrorKind::ExecuteFail),
+                                            format!("AsyncPostgresConnection, [query:{:.1024},row:{},dbErr:{}]", query, counts.len(), err)).to_result();
+                }
+            }
+        }
+
+        Ok(counts)
+    }
+
+    fn statement_cache_stats(&self) -> Option<PairStatementCacheStats> {
+        Some(PairStatementCacheStats {
+            hit : self.stmt_cache.hit_count(),
+            miss : self.stmt_cache.miss_count(),
+            size : self.stmt_cache.len(),
+            capacity : self.stmt_cache.capacity()
+        })
+    }
+
+    async fn begin(&mut self) -> Result<(), CommonError> {
+        if self.in_transaction {
+            return CommonError::new(&CommonDefaultErrorKind::InvalidApiCall, "AsyncPostgresConnection - begin - already in transaction").to_result();
+        }
+
+        self.run_transaction_query("BEGIN TRANSACTION").await?;
+        self.in_transaction = true;
+        Ok(())
+    }
+
+    async fn commit(&mut self) -> Result<(), CommonError> {
+        if !self.in_transaction {
+            return CommonError::new(&CommonDefaultErrorKind::InvalidApiCall, "AsyncPostgresConnection - commit - not in transaction").to_result();
+        }
+
+        self.in_transaction = false;
+        self.run_transaction_query("COMMIT").await
+    }
+
+    async fn rollback(&mut self) -> Result<(), CommonError> {
+        if !self.in_transaction {
+            return CommonError::new(&CommonDefaultErrorKind::InvalidApiCall, "AsyncPostgresConnection - rollback - not in transaction").to_result();
+        }
+
+        self.in_transaction = false;
+        self.run_transaction_query("ROLLBACK").await
+    }
+}
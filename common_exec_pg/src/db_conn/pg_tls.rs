@@ -0,0 +1,30 @@
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio_postgres::tls::MakeTlsConnect;
+use tokio_postgres_rustls::MakeRustlsConnect;
+use common_err::CommonError;
+use common_pair_exec::PairTlsOptions;
+
+// sends server_name as SNI in place of the host the url points to
+#[derive(Clone)]
+pub(crate) struct PgTlsConnect {
+    inner : MakeRustlsConnect,
+    server_name : Option<String>
+}
+
+impl PgTlsConnect {
+    pub(crate) fn new(options : &'_ PairTlsOptions) -> Result<Self, CommonError> {
+        Ok(PgTlsConnect { inner : MakeRustlsConnect::new(options.client_config()?), server_name : options.server_name.clone() })
+    }
+}
+
+impl<S> MakeTlsConnect<S> for PgTlsConnect
+where S : AsyncRead + AsyncWrite + Unpin + Send + 'static {
+    type Stream = <MakeRustlsConnect as MakeTlsConnect<S>>::Stream;
+    type TlsConnect = <MakeRustlsConnect as MakeTlsConnect<S>>::TlsConnect;
+    type Error = <MakeRustlsConnect as MakeTlsConnect<S>>::Error;
+
+    fn make_tls_connect(&mut self, hostname : &'_ str) -> Result<Self::TlsConnect, Self::Error> {
+        let hostname = self.server_name.as_deref().unwrap_or(hostname);
+        <MakeRustlsConnect as MakeTlsConnect<S>>::make_tls_connect(&mut self.inner, hostname)
+    }
+}
@@ -0,0 +1,209 @@
+use std::error::Error;
+use bytes::{Buf, BufMut, BytesMut};
+use chrono::Utc;
+use postgres::types::{to_sql_checked, FromSql, IsNull, ToSql, Type};
+use common_pair_exec::{PairDecimal, PairValueEnum};
+
+type PgTypeResult<T> = Result<T, Box<dyn Error + Sync + Send>>;
+
+const NUMERIC_POS : u16 = 0x0000;
+const NUMERIC_NEG : u16 = 0x4000;
+const JSONB_VERSION : u8 = 1;
+
+// binary NUMERIC, base 10000 digits
+#[derive(Debug)]
+pub(crate) struct PgNumeric(pub(crate) PairDecimal);
+
+impl<'a> FromSql<'a> for PgNumeric {
+    fn from_sql(_ : &Type, mut raw : &'a [u8]) -> PgTypeResult<Self> {
+        if raw.len() < 8 {
+            return Err("PgNumeric.from_sql - invalid header".into());
+        }
+
+        let ndigits = raw.get_i16() as i32;
+        let weight = raw.get_i16() as i32;
+        let sign = raw.get_u16();
+        let dscale = raw.get_u16() as i32;
+
+        if sign != NUMERIC_POS && sign != NUMERIC_NEG {
+            return Err("PgNumeric.from_sql - NaN or Infinity is not supported".into());
+        }
+        if ndigits < 0 || raw.len() != ndigits as usize * 2 {
+            return Err("PgNumeric.from_sql - invalid digits".into());
+        }
+
+        let mut value : i128 = 0;
+        for idx in 0..ndigits {
+            let digit = raw.get_i16() as i128;
+            let exp = 4 * (weight - idx) + dscale;
+
+            // digits past dscale are always zero padding
+            let term = if exp >= 0 {
+                10i128.checked_pow(exp as u32).and_then(|p| digit.checked_mul(p))
+            } else {
+                Some(10i128.checked_pow(-exp as u32).map(|p| digit / p).unwrap_or(0))
+            };
+
+            value = term.and_then(|t| value.checked_add(t)).ok_or("PgNumeric.from_sql - overflow i128")?;
+        }
+
+        Ok(PgNumeric(PairDecimal::new(if sign == NUMERIC_NEG { -value } else { value }, dscale as u32)))
+    }
+
+    fn accepts(ty : &Type) -> bool {
+        *ty == Type::NUMERIC
+    }
+}
+
+impl ToSql for PgNumeric {
+    fn to_sql(&self, _ : &Type, out : &mut BytesMut) -> PgTypeResult<IsNull> {
+        let scale = self.0.scale() as usize;
+        let digits = self.0.value().unsigned_abs().to_string();
+
+        let (int, frac) = if digits.len() > scale {
+            let (i, f) = digits.split_at(digits.len() - scale);
+            (i.to_string(), f.to_string())
+        } else {
+            (String::new(), format!("{:0>width$}", digits, width = scale))
+        };
+
+        // align the decimal point on a group boundary
+        let int_pad = (4 - int.len() % 4) % 4;
+        let frac_pad = (4 - frac.len() % 4) % 4;
+        let aligned = format!("{}{}{}{}", "0".repeat(int_pad), int, frac, "0".repeat(frac_pad));
+
+        let groups = aligned.as_bytes().chunks(4).map(|c| {
+            c.iter().fold(0i16, |acc, d| acc * 10 + (d - b'0') as i16)
+        }).collect::<Vec<i16>>();
+
+        let mut weight = ((int_pad + int.len()) / 4) as i32 - 1;
+        let start = groups.iter().position(|g| *g != 0).unwrap_or(groups.len());
+        let end = groups.iter().rposition(|g| *g != 0).map(|p| p + 1).unwrap_or(start);
+        weight -= start as i32;
+
+        let groups = &groups[start..end];
+        if groups.is_empty() {
+            weight = 0;
+        }
+
+        out.put_i16(groups.len() as i16);
+        out.put_i16(weight as i16);
+        out.put_u16(if self.0.value() < 0 { NUMERIC_NEG } else { NUMERIC_POS });
+        out.put_u16(scale as u16);
+        for g in groups {
+            out.put_i16(*g);
+        }
+
+        Ok(IsNull::No)
+    }
+
+    fn accepts(ty : &Type) -> bool {
+        *ty == Type::NUMERIC
+    }
+
+    to_sql_checked!();
+}
+
+// json and jsonb kept as text
+#[derive(Debug)]
+pub(crate) struct PgJson<'a>(pub(crate) &'a str);
+
+impl ToSql for PgJson<'_> {
+    fn to_sql(&self, ty : &Type, out : &mut BytesMut) -> PgTypeResult<IsNull> {
+        if *ty == Type::JSONB {
+            out.put_u8(JSONB_VERSION);
+        }
+        out.put_slice(self.0.as_bytes());
+        Ok(IsNull::No)
+    }
+
+    fn accepts(ty : &Type) -> bool {
+        *ty == Type::JSON || *ty == Type::JSONB
+    }
+
+    to_sql_checked!();
+}
+
+#[derive(Debug)]
+pub(crate) struct PgJsonText(pub(crate) String);
+
+impl<'a> FromSql<'a> for PgJsonText {
+    fn from_sql(ty : &Type, mut raw : &'a [u8]) -> PgTypeResult<Self> {
+        if *ty == Type::JSONB {
+            if raw.is_empty() || raw[0] != JSONB_VERSION {
+                return Err("PgJsonText.from_sql - unsupported jsonb version".into());
+            }
+            raw = &raw[1..];
+        }
+        Ok(PgJsonText(String::from_utf8(raw.to_vec())?))
+    }
+
+    fn accepts(ty : &Type) -> bool {
+        *ty == Type::JSON || *ty == Type::JSONB
+    }
+}
+
+// binds a pair value to the type the server inferred for the placeholder
+#[derive(Debug)]
+pub(crate) struct PgParam<'a>(pub(crate) &'a PairValueEnum);
+
+fn int_to_sql(v : i64, ty : &Type, out : &mut BytesMut) -> PgTypeResult<IsNull> {
+    match *ty {
+        Type::CHAR => i8::try_from(v)?.to_sql_checked(ty, out),
+        Type::INT2 => i16::try_from(v)?.to_sql_checked(ty, out),
+        Type::INT4 => i32::try_from(v)?.to_sql_checked(ty, out),
+        Type::FLOAT4 => (v as f32).to_sql_checked(ty, out),
+        Type::FLOAT8 => (v as f64).to_sql_checked(ty, out),
+        Type::NUMERIC => PgNumeric(PairDecimal::new(v as i128, 0)).to_sql_checked(ty, out),
+        _ => v.to_sql_checked(ty, out)
+    }
+}
+
+impl ToSql for PgParam<'_> {
+    fn to_sql(&self, ty : &Type, out : &mut BytesMut) -> PgTypeResult<IsNull> {
+        match self.0 {
+            PairValueEnum::Null => Ok(IsNull::Yes),
+            PairValueEnum::Bool(b) => b.to_sql_checked(ty, out),
+            PairValueEnum::TinyInt(i) => int_to_sql(*i as i64, ty, out),
+            PairValueEnum::SmallInt(i) => int_to_sql(*i as i64, ty, out),
+            PairValueEnum::Int(i) => int_to_sql(*i as i64, ty, out),
+            PairValueEnum::BigInt(i) => int_to_sql(*i, ty, out),
+            PairValueEnum::Double(f) => match *ty {
+                Type::FLOAT4 => (*f as f32).to_sql_checked(ty, out),
+                _ => f.to_sql_checked(ty, out)
+            },
+            PairValueEnum::Float(f) => match *ty {
+                Type::FLOAT8 => (*f as f64).to_sql_checked(ty, out),
+                _ => f.to_sql_checked(ty, out)
+            },
+            PairValueEnum::String(s) => match *ty {
+                Type::JSON | Type::JSONB => PgJson(s).to_sql_checked(ty, out),
+                Type::NUMERIC => {
+                    let d = s.parse::<PairDecimal>().map_err(|e| e.get_cause())?;
+                    PgNumeric(d).to_sql_checked(ty, out)
+                },
+                _ => s.to_sql_checked(ty, out)
+            },
+            PairValueEnum::Bin(v) => v.to_sql_checked(ty, out),
+            PairValueEnum::Timestamp(t) => match *ty {
+                Type::TIMESTAMP => t.naive_utc().to_sql_checked(ty, out),
+                _ => t.with_timezone(&Utc).to_sql_checked(ty, out)
+            },
+            PairValueEnum::Date(d) => d.to_sql_checked(ty, out),
+            PairValueEnum::Time(t) => t.to_sql_checked(ty, out),
+            PairValueEnum::Uuid(u) => u.to_sql_checked(ty, out),
+            PairValueEnum::Decimal(d) => PgNumeric(*d).to_sql_checked(ty, out),
+            PairValueEnum::Json(j) => PgJson(j).to_sql_checked(ty, out),
+            PairValueEnum::Inet(i) => i.to_sql_checked(ty, out),
+            PairValueEnum::Array(_) | PairValueEnum::Map(_) => {
+                Err(format!("PgParam.to_sql - not support type({})", self.0.type_name()).into())
+            }
+        }
+    }
+
+    fn accepts(_ : &Type) -> bool {
+        true
+    }
+
+    to_sql_checked!();
+}
@@ -1,22 +1,39 @@
 mod db_conn;
 
+use std::sync::Arc;
 use common_err::CommonError;
 
-use common_core::collection::pool::get_thread_safe_pool;
-use common_pair_exec::{PairExecutor, PairExecutorInfo, PairExecutorPool};
-use db_conn::PostgresConnection;
+use common_core::collection::pool::{get_async_thread_safe_pool, get_thread_safe_pool_with_policy};
+use common_pair_exec::{AsyncPairExecutor, AsyncPairExecutorPool, PairAddrSelector, PairBackendOptions, PairExecutor, PairExecutorInfo, PairExecutorPool, PairPgOptions};
+use db_conn::{AsyncPostgresConnection, PostgresConnection};
+
+fn pg_application_name(name : &'_ str, info : &'_ PairExecutorInfo) -> String {
+    match &info.backend {
+        Some(PairBackendOptions::Postgres(PairPgOptions { application_name : Some(app_name) })) => app_name.clone(),
+        _ => name.to_string()
+    }
+}
 
 pub fn create_pg_pair_conn_pool(name : String, info : PairExecutorInfo, alloc_size : usize) -> PairExecutorPool {
-    let app_name = name.clone();
-    
-    let gen_fn : Box<dyn Fn(()) -> Result<Box<dyn PairExecutor>, CommonError>> = (|info : PairExecutorInfo| {
+    let policy = info.pool.to_pool_policy();
+    let app_name = pg_application_name(&name, &info);
+    let selector = PairAddrSelector::new(info.addr.clone(), info.failover);
+
+    let gen_fn : Box<dyn Fn(()) -> Result<Box<dyn PairExecutor + Send>, CommonError> + Send + Sync> = (|info : PairExecutorInfo| {
         let real_fn  = move |_ : ()| {
             let conn_info = info.clone();
-            let conn = PostgresConnection::new(app_name.as_str(), conn_info.user.as_str(),
-                                               conn_info.password.as_str(),conn_info.addr[0].as_str(), conn_info.name.as_str(), conn_info.timeout_sec);
+            let conn = selector.connect(|addr| {
+                let mut conn = PostgresConnection::new(app_name.as_str(), conn_info.user.as_str(),
+                                                       conn_info.password.as_str(), addr, conn_info.name.as_str(), conn_info.timeout_sec,
+                                                       conn_info.statement_cache_size, conn_info.tls.as_ref())?;
+                if conn_info.failover.require_primary {
+                    conn.check_primary()?;
+                }
+                Ok(conn)
+            });
 
             match conn {
-                Ok(ok) => Ok(Box::new(ok) as Box<dyn PairExecutor>),
+                Ok(ok) => Ok(Box::new(ok) as Box<dyn PairExecutor + Send>),
                 Err(err) => {Err(err)}
             }
         };
@@ -24,5 +41,29 @@ pub fn create_pg_pair_conn_pool(name : String, info : PairExecutorInfo, alloc_si
         Box::new(real_fn)
     })(info);
 
-    get_thread_safe_pool(name, gen_fn, alloc_size)
-}
\ No newline at end of file
+    get_thread_safe_pool_with_policy(name, gen_fn, alloc_size, policy)
+}
+
+pub fn create_pg_async_pair_conn_pool(name : String, info : PairExecutorInfo, alloc_size : usize) -> AsyncPairExecutorPool {
+    let app_name = pg_application_name(&name, &info);
+    let selector = Arc::new(PairAddrSelector::new(info.addr.clone(), info.failover));
+
+    get_async_thread_safe_pool(name, Box::new(move |_ : ()| {
+        let (app_name, conn_info, selector) = (app_name.clone(), info.clone(), selector.clone());
+        Box::pin(async move {
+            let conn = selector.connect_async(|addr| {
+                let (app_name, conn_info) = (&app_name, &conn_info);
+                async move {
+                    let mut conn = AsyncPostgresConnection::new(app_name.as_str(), conn_info.user.as_str(),
+                                                                conn_info.password.as_str(), addr.as_str(), conn_info.name.as_str(), conn_info.timeout_sec,
+                                                                conn_info.statement_cache_size, conn_info.tls.as_ref()).await?;
+                    if conn_info.failover.require_primary {
+                        conn.check_primary().await?;
+                    }
+                    Ok(conn)
+                }
+            }).await?;
+            Ok(Box::new(conn) as Box<dyn AsyncPairExecutor>)
+        })
+    }), alloc_size)
+}
@@ -1,23 +1,109 @@
+mod async_conn;
+
 use std::collections::HashMap;
 use std::fmt::format;
 use std::time::Duration;
-use redis::{Commands, ConnectionLike, ToRedisArgs, TypedCommands, Cmd, Value};
+use redis::{Commands, ConnectionLike, ToRedisArgs, TypedCommands, Cmd, Value, ClientTlsConfig, IntoConnectionInfo, TlsCertificates};
 use common_err::CommonError;
 use common_err::gen::CommonDefaultErrorKind;
-use common_pair_exec::{PairExecutor, PairValueEnum};
+use common_pair_exec::{encode_url_component, install_pair_crypto_provider, PairCallOptions, PairColumnInfo, PairExecuteOutcome, PairExecutor, PairResultSet, PairRowCursor, PairTlsOptions, PairTlsVerify, PairValueEnum};
+
+pub(crate) use async_conn::AsyncRedisConnection;
 
 pub struct RedisConnection {
     redis_client : redis::Client,
+    // MULTI state lives on a single connection, Client opens a new one per command
+    tx_conn : Option<redis::Connection>
 }
 
 impl RedisConnection {
-    pub fn new(addr : &'_ str, user : &'_ str, password : &'_ str, db_name : &'_ str) -> Result<Self, CommonError> {
-        let url = format!("redis://{}:{}@{}/{}", user, password, addr, db_name);
-        let client = redis::Client::open(url.as_str()).map_err(|e| {
+    fn create_redis_client(addr : &'_ str, user : &'_ str, password : &'_ str, db_name : &'_ str, tls : Option<&'_ PairTlsOptions>) -> Result<redis::Client, CommonError> {
+        let (user, password, db_name) = (encode_url_component(user), encode_url_component(password), encode_url_component(db_name));
+        let client_err = |e : redis::RedisError| {
             CommonError::new(&CommonDefaultErrorKind::ThirdLibCallFail, format!("redis connect failed : {:.1024}", e.to_string()))
+        };
+
+        let Some(tls) = tls else {
+            let url = format!("redis://{}:{}@{}/{}", user, password, addr, db_name);
+            return redis::Client::open(url.as_str()).map_err(client_err);
+        };
+
+        // the driver sends and verifies the address host and has no way to override it
+        if let Some(name) = tls.server_name.as_deref() {
+            let host = addr.rsplit_once(':').map_or(addr, |(host, _)| host).trim_start_matches('[').trim_end_matches(']');
+            if !name.eq_ignore_ascii_case(host) {
+                return CommonError::new(&CommonDefaultErrorKind::NoSupport, format!("RedisConnection - tls - server_name {} differs from the address host {}, use CaOnly", name, host)).to_result();
+            }
+        }
+
+        let client_tls = match tls.client_cert_files()? {
+            Some((cert, key)) => Some(ClientTlsConfig { client_cert : PairTlsOptions::read_file(cert)?, client_key : PairTlsOptions::read_file(key)? }),
+            None => None
+        };
+        let root_cert = tls.ca_file.as_deref().map(PairTlsOptions::read_file).transpose()?;
+
+        let fragment = if tls.verify == PairTlsVerify::None { "#insecure" } else { "" };
+        let mut conn_info = format!("rediss://{}:{}@{}/{}{}", user, password, addr, db_name, fragment).into_connection_info().map_err(client_err)?;
+        if tls.verify == PairTlsVerify::CaOnly {
+            let mut tls_addr = conn_info.addr().clone();
+            tls_addr.set_danger_accept_invalid_hostnames(true);
+            conn_info = conn_info.set_addr(tls_addr);
+        }
+
+        // redis builds its rustls config with the process provider
+        install_pair_crypto_provider();
+        redis::Client::build_with_tls(conn_info, TlsCertificates { client_tls, root_cert }).map_err(client_err)
+    }
+
+    pub fn new(addr : &'_ str, user : &'_ str, password : &'_ str, db_name : &'_ str, tls : Option<&'_ PairTlsOptions>) -> Result<Self, CommonError> {
+        let client = Self::create_redis_client(addr, user, password, db_name, tls)?;
+
+        Ok(RedisConnection { redis_client : client, tx_conn : None })
+    }
+
+    fn error_kind(e : &'_ redis::RedisError) -> &'static CommonDefaultErrorKind {
+        if e.is_io_error() || e.is_connection_dropped() {
+            &CommonDefaultErrorKind::ConnectFail
+        } else if e.is_timeout() || matches!(e.code(), Some("LOADING" | "TRYAGAIN" | "MASTERDOWN" | "CLUSTERDOWN")) {
+            &CommonDefaultErrorKind::Transient
+        } else {
+            &CommonDefaultErrorKind::ExecuteFail
+        }
+    }
+
+    // first element of the ROLE reply is master, slave or sentinel
+    fn is_master_role(role : &'_ Value) -> bool {
+        match role {
+            Value::Array(a) => match a.first() {
+                Some(Value::BulkString(b)) => b.as_slice() == b"master",
+                Some(Value::SimpleString(s)) => s == "master",
+                _ => false
+            },
+            _ => false
+        }
+    }
+
+    pub(crate) fn check_primary(&mut self) -> Result<(), CommonError> {
+        let role : Value = redis::cmd("ROLE").query(&mut self.redis_client).map_err(|e| {
+            CommonError::new(Self::error_kind(&e), format!("RedisConnection - check_primary - {}", e))
         })?;
 
-        Ok(RedisConnection { redis_client : client })
+        if !Self::is_master_role(&role) {
+            return CommonError::new(&CommonDefaultErrorKind::ConnectFail, "RedisConnection - check_primary - not a master").to_result();
+        }
+        Ok(())
+    }
+
+    fn end_transaction(&mut self, command : &'_ str) -> Result<Value, CommonError> {
+        let mut conn = match self.tx_conn.take() {
+            Some(conn) => conn,
+            None => return CommonError::new(&CommonDefaultErrorKind::InvalidApiCall,
+                                            format!("RedisConnection - {} - not in transaction", command)).to_result()
+        };
+
+        redis::cmd(command).query::<Value>(&mut conn).map_err(|e| {
+            CommonError::new(Self::error_kind(&e), format!("RedisConnection - {} - {}", command, e))
+        })
     }
 
     fn set_pair_to_redis_args(mut cmd :Cmd, param : &PairValueEnum) -> Result<Cmd, CommonError> {
@@ -34,6 +120,12 @@ impl RedisConnection {
                         PairValueEnum::String(s) => cmd.arg(s),
                         PairValueEnum::Bin(bin) => cmd.arg(bin),
                         PairValueEnum::Float(f) => cmd.arg(f),
+                        PairValueEnum::SmallInt(i) => cmd.arg(i),
+                        PairValueEnum::TinyInt(i) => cmd.arg(i),
+                        // redis has no typed values, the rest is sent in its text form
+                        PairValueEnum::Timestamp(_) | PairValueEnum::Date(_) | PairValueEnum::Time(_) |
+                        PairValueEnum::Uuid(_) | PairValueEnum::Decimal(_) | PairValueEnum::Json(_) |
+                        PairValueEnum::Inet(_) => cmd.arg(x.to_string()),
                         _ => {
                             return CommonError::new(&CommonDefaultErrorKind::NoSupport,
                                                     format!("not support type : {:?}", x)).to_result();
@@ -131,29 +223,7 @@ impl RedisConnection {
         Ok(ret)
     }
 
-}
-
-impl PairExecutor for RedisConnection {
-    fn execute_pair(&mut self, query: &'_ str, param: &PairValueEnum) -> Result<PairValueEnum, CommonError> {
-        let cmd = Self::set_pair_to_redis_args(redis::cmd(query), param).map_err(|e| {
-            CommonError::extend(&CommonDefaultErrorKind::ThirdLibCallFail, "set args failed", e)
-        })?;
-
-        let result : Value = cmd.query(&mut self.redis_client).map_err(|e| {
-            CommonError::new(&CommonDefaultErrorKind::ExecuteFail, format!("execute: {}", e.to_string()))
-        })?;
-
-        let ret = Self::convert_redis_value_to_pair_root_map(result).map_err(|e| {
-            CommonError::extend(&CommonDefaultErrorKind::ExecuteFail, "convert data failed", e)
-        })?;
-
-        Ok(ret)
-    }
-
-    fn get_current_time(&mut self) -> Result<Duration, CommonError> {
-        let execute_result = self.execute_pair("time", &PairValueEnum::Null).map_err(|e| {
-            CommonError::extend(&CommonDefaultErrorKind::ExecuteFail, "get current time failed", e)
-        })?;
+    fn convert_time_to_duration(execute_result : PairValueEnum) -> Result<Duration, CommonError> {
         if let PairValueEnum::Map(data) = execute_result {
             if let Some(PairValueEnum::Array(a)) = data.get("0") {
                 if a.len() != 2 {
@@ -187,4 +257,190 @@ impl PairExecutor for RedisConnection {
             CommonError::new(&CommonDefaultErrorKind::ExecuteFail, format!("not map type : {:?}", execute_result)).to_result()
         }
     }
+
+    fn convert_batch_counts(result : Vec<Value>) -> Vec<u64> {
+        result.into_iter().map(|v| match v {
+            Value::Int(i) => i.max(0) as u64,
+            Value::Nil => 0,
+            _ => 1
+        }).collect()
+    }
+}
+
+struct RedisRowCursor {
+    cols : Vec<PairColumnInfo>,
+    row : Option<Vec<PairValueEnum>>
+}
+
+impl RedisRowCursor {
+    fn new(value : PairValueEnum) -> Self {
+        match value {
+            PairValueEnum::Map(m) => {
+                let mut items : Vec<(String, PairValueEnum)> = m.into_iter().collect();
+                items.sort_by(|a, b| a.0.cmp(&b.0));
+
+                let (cols, row) = items.into_iter().map(|(k, v)| {
+                    (PairColumnInfo::new(k, v.type_name(), true), v)
+                }).unzip();
+                RedisRowCursor { cols, row : Some(row) }
+            },
+            _ => RedisRowCursor { cols : Vec::new(), row : None }
+        }
+    }
+}
+
+impl PairRowCursor for RedisRowCursor {
+    fn columns(&self) -> &'_ [PairColumnInfo] {
+        self.cols.as_slice()
+    }
+
+    fn next_row(&mut self) -> Result<Option<Vec<PairValueEnum>>, CommonError> {
+        Ok(self.row.take())
+    }
+}
+
+impl RedisConnection {
+    // a timed out reply may still arrive, so the connection it was read from is never reused
+    fn query_timeout(&mut self, cmd : Cmd, timeout : Duration) -> redis::RedisResult<Value> {
+        if let Some(conn) = self.tx_conn.as_mut() {
+            conn.set_read_timeout(Some(timeout))?;
+            let ret = cmd.query(conn);
+            match &ret {
+                // the server discards the MULTI once the connection is closed
+                Err(e) if e.is_timeout() => self.tx_conn = None,
+                _ => conn.set_read_timeout(None)?
+            }
+            return ret;
+        }
+
+        let mut conn = self.redis_client.get_connection_with_timeout(timeout)?;
+        conn.set_read_timeout(Some(timeout))?;
+        conn.set_write_timeout(Some(timeout))?;
+        cmd.query(&mut conn)
+    }
+
+    fn run_pair(&mut self, query: &'_ str, param: &PairValueEnum, timeout : Option<Duration>) -> Result<PairValueEnum, CommonError> {
+        let cmd = Self::set_pair_to_redis_args(redis::cmd(query), param).map_err(|e| {
+            CommonError::extend(&CommonDefaultErrorKind::ThirdLibCallFail, "set args failed", e)
+        })?;
+
+        let result : Value = match (timeout, self.tx_conn.as_mut()) {
+            // a zero timeout is rejected by the socket
+            (Some(t), _) => self.query_timeout(cmd, t.max(Duration::from_millis(1))),
+            (None, Some(conn)) => cmd.query(conn),
+            (None, None) => cmd.query(&mut self.redis_client)
+        }.map_err(|e| {
+            CommonError::new(Self::error_kind(&e), format!("execute: {}", e.to_string()))
+        })?;
+
+        let ret = Self::convert_redis_value_to_pair_root_map(result).map_err(|e| {
+            CommonError::extend(&CommonDefaultErrorKind::ExecuteFail, "convert data failed", e)
+        })?;
+
+        Ok(ret)
+    }
+}
+
+impl PairExecutor for RedisConnection {
+    fn execute_pair(&mut self, query: &'_ str, param: &PairValueEnum) -> Result<PairValueEnum, CommonError> {
+        self.run_pair(query, param, None)
+    }
+
+    // a command cannot be aborted once sent, only the deadline bounds it, a cancel stops the calls not yet started
+    fn execute_pair_with(&mut self, query : &'_ str, param : &PairValueEnum, options : &PairCallOptions) -> Result<PairValueEnum, CommonError> {
+        if options.is_empty() {
+            return self.execute_pair(query, param);
+        }
+
+        let guard = options.start()?;
+        let ret = self.run_pair(query, param, options.remaining());
+        guard.finish(ret)
+    }
+
+    fn execute_pair_outcome(&mut self, query : &'_ str, param : &PairValueEnum) -> Result<PairExecuteOutcome, CommonError> {
+        let ret = self.execute_pair(query, param)?;
+
+        let rows_affected = match &ret {
+            PairValueEnum::Map(m) if m.len() == 1 => match m.get("0") {
+                Some(PairValueEnum::BigInt(i)) => Some((*i).max(0) as u64),
+                _ => None
+            },
+            _ => None
+        };
+
+        let result_set = PairResultSet::from_cursor(Box::new(RedisRowCursor::new(ret)))?;
+        Ok(PairExecuteOutcome::new(rows_affected, result_set))
+    }
+
+    fn execute_pair_cursor<'a>(&'a mut self, query: &'_ str, param: &PairValueEnum) -> Result<Box<dyn PairRowCursor + 'a>, CommonError> {
+        let ret = self.execute_pair(query, param)?;
+        Ok(Box::new(RedisRowCursor::new(ret)))
+    }
+
+    fn execute_batch(&mut self, query : &'_ str, rows : &'_ [PairValueEnum]) -> Result<Vec<u64>, CommonError> {
+        if rows.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let mut pipe = redis::pipe();
+        for row in rows {
+            let cmd = Self::set_pair_to_redis_args(redis::cmd(query), row).map_err(|e| {
+                CommonError::extend(&CommonDefaultErrorKind::ThirdLibCallFail, "set args failed", e)
+            })?;
+            pipe.add_command(cmd);
+        }
+
+        let result : Vec<Value> = match self.tx_conn.as_mut() {
+            Some(conn) => pipe.query(conn),
+            None => pipe.query(&mut self.redis_client)
+        }.map_err(|e| {
+            CommonError::new(Self::error_kind(&e), format!("execute_batch: {}", e))
+        })?;
+
+        Ok(Self::convert_batch_counts(result))
+    }
+
+    fn in_transaction(&self) -> bool {
+        self.tx_conn.is_some()
+    }
+
+    fn begin(&mut self) -> Result<(), CommonError> {
+        if self.tx_conn.is_some() {
+            return CommonError::new(&CommonDefaultErrorKind::InvalidApiCall, "RedisConnection - begin - already in transaction").to_result();
+        }
+
+        let mut conn = self.redis_client.get_connection().map_err(|e| {
+            CommonError::new(&CommonDefaultErrorKind::ConnectFail, format!("RedisConnection - begin - {}", e))
+        })?;
+
+        redis::cmd("MULTI").query::<Value>(&mut conn).map_err(|e| {
+            CommonError::new(&CommonDefaultErrorKind::ExecuteFail, format!("RedisConnection - MULTI - {}", e))
+        })?;
+
+        self.tx_conn = Some(conn);
+        Ok(())
+    }
+
+    fn commit(&mut self) -> Result<(), CommonError> {
+        match self.end_transaction("EXEC")? {
+            Value::Nil => CommonError::new(&CommonDefaultErrorKind::ExecuteFail, "RedisConnection - EXEC - transaction aborted").to_result(),
+            _ => Ok(())
+        }
+    }
+
+    fn rollback(&mut self) -> Result<(), CommonError> {
+        self.end_transaction("DISCARD")?;
+        Ok(())
+    }
+
+    fn get_current_time(&mut self) -> Result<Duration, CommonError> {
+        let execute_result = self.execute_pair("time", &PairValueEnum::Null).map_err(|e| {
+            CommonError::extend(&CommonDefaultErrorKind::ExecuteFail, "get current time failed", e)
+        })?;
+        Self::convert_time_to_duration(execute_result)
+    }
+
+    fn ping(&mut self) -> Result<(), CommonError> {
+        self.execute_pair("ping", &PairValueEnum::Null).map(|_| ())
+    }
 }
\ No newline at end of file
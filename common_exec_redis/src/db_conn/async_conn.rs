@@ -0,0 +1,141 @@
+use std::time::Duration;
+use async_trait::async_trait;
+use redis::aio::MultiplexedConnection;
+use redis::Value;
+use common_err::CommonError;
+use common_err::gen::CommonDefaultErrorKind;
+use common_pair_exec::{AsyncPairExecutor, PairExecuteOutcome, PairResultSet, PairTlsOptions, PairValueEnum};
+use super::{RedisConnection, RedisRowCursor};
+
+pub struct AsyncRedisConnection {
+    // not shared with other executors, so MULTI state stays on this connection
+    conn : MultiplexedConnection,
+    in_transaction : bool
+}
+
+impl AsyncRedisConnection {
+    pub(crate) async fn new(addr : &'_ str, user : &'_ str, password : &'_ str, db_name : &'_ str, tls : Option<&'_ PairTlsOptions>) -> Result<Self, CommonError> {
+        let client = RedisConnection::create_redis_client(addr, user, password, db_name, tls)?;
+        let conn = client.get_multiplexed_async_connection().await.map_err(|e| {
+            CommonError::new(&CommonDefaultErrorKind::ConnectFail, format!("AsyncRedisConnection - new - {:.1024}", e))
+        })?;
+
+        Ok(AsyncRedisConnection { conn, in_transaction : false })
+    }
+
+    async fn run_transaction_command(&mut self, command : &'_ str) -> Result<Value, CommonError> {
+        redis::cmd(command).query_async::<Value>(&mut self.conn).await.map_err(|e| {
+            CommonError::new(RedisConnection::error_kind(&e), format!("AsyncRedisConnection - {} - {}", command, e))
+        })
+    }
+
+    pub(crate) async fn check_primary(&mut self) -> Result<(), CommonError> {
+        let role = self.run_transaction_command("ROLE").await?;
+
+        if !RedisConnection::is_master_role(&role) {
+            return CommonError::new(&CommonDefaultErrorKind::ConnectFail, "AsyncRedisConnection - check_primary - not a master").to_result();
+        }
+        Ok(())
+    }
+
+    fn end_transaction(&mut self, command : &'_ str) -> Result<(), CommonError> {
+        if !self.in_transaction {
+            return CommonError::new(&CommonDefaultErrorKind::InvalidApiCall,
+                                    format!("AsyncRedisConnection - {} - not in transaction", command)).to_result();
+        }
+        self.in_transaction = false;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl AsyncPairExecutor for AsyncRedisConnection {
+    async fn execute_pair(&mut self, query : &'_ str, param : &PairValueEnum) -> Result<PairValueEnum, CommonError> {
+        let cmd = RedisConnection::set_pair_to_redis_args(redis::cmd(query), param).map_err(|e| {
+            CommonError::extend(&CommonDefaultErrorKind::ThirdLibCallFail, "set args failed", e)
+        })?;
+
+        let result : Value = cmd.query_async(&mut self.conn).await.map_err(|e| {
+            CommonError::new(RedisConnection::error_kind(&e), format!("execute: {}", e))
+        })?;
+
+        RedisConnection::convert_redis_value_to_pair_root_map(result).map_err(|e| {
+            CommonError::extend(&CommonDefaultErrorKind::ExecuteFail, "convert data failed", e)
+        })
+    }
+
+    async fn execute_pair_result_set(&mut self, query : &'_ str, param : &PairValueEnum) -> Result<PairResultSet, CommonError> {
+        let ret = self.execute_pair(query, param).await?;
+        PairResultSet::from_cursor(Box::new(RedisRowCursor::new(ret)))
+    }
+
+    async fn execute_pair_outcome(&mut self, query : &'_ str, param : &PairValueEnum) -> Result<PairExecuteOutcome, CommonError> {
+        let ret = self.execute_pair(query, param).await?;
+
+        let rows_affected = match &ret {
+            PairValueEnum::Map(m) if m.len() == 1 => match m.get("0") {
+                Some(PairValueEnum::BigInt(i)) => Some((*i).max(0) as u64),
+                _ => None
+            },
+            _ => None
+        };
+
+        let result_set = PairResultSet::from_cursor(Box::new(RedisRowCursor::new(ret)))?;
+        Ok(PairExecuteOutcome::new(rows_affected, result_set))
+    }
+
+    async fn get_current_time(&mut self) -> Result<Duration, CommonError> {
+        let execute_result = self.execute_pair("time", &PairValueEnum::Null).await.map_err(|e| {
+            CommonError::extend(&CommonDefaultErrorKind::ExecuteFail, "get current time failed", e)
+        })?;
+        RedisConnection::convert_time_to_duration(execute_result)
+    }
+
+    async fn ping(&mut self) -> Result<(), CommonError> {
+        self.execute_pair("ping", &PairValueEnum::Null).await.map(|_| ())
+    }
+
+    async fn execute_batch(&mut self, query : &'_ str, rows : &'_ [PairValueEnum]) -> Result<Vec<u64>, CommonError> {
+        if rows.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let mut pipe = redis::pipe();
+        for row in rows {
+            let cmd = RedisConnection::set_pair_to_redis_args(redis::cmd(query), row).map_err(|e| {
+                CommonError::extend(&CommonDefaultErrorKind::ThirdLibCallFail, "set args failed", e)
+            })?;
+            pipe.add_command(cmd);
+        }
+
+        let result : Vec<Value> = pipe.query_async(&mut self.conn).await.map_err(|e| {
+            CommonError::new(RedisConnection::error_kind(&e), format!("execute_batch: {}", e))
+        })?;
+
+        Ok(RedisConnection::convert_batch_counts(result))
+    }
+
+    async fn begin(&mut self) -> Result<(), CommonError> {
+        if self.in_transaction {
+            return CommonError::new(&CommonDefaultErrorKind::InvalidApiCall, "AsyncRedisConnection - begin - already in transaction").to_result();
+        }
+
+        self.run_transaction_command("MULTI").await?;
+        self.in_transaction = true;
+        Ok(())
+    }
+
+    async fn commit(&mut self) -> Result<(), CommonError> {
+        self.end_transaction("EXEC")?;
+        match self.run_transaction_command("EXEC").await? {
+            Value::Nil => CommonError::new(&CommonDefaultErrorKind::ExecuteFail, "AsyncRedisConnection - EXEC - transaction aborted").to_result(),
+            _ => Ok(())
+        }
+    }
+
+    async fn rollback(&mut self) -> Result<(), CommonError> {
+        self.end_transaction("DISCARD")?;
+        self.run_transaction_command("DISCARD").await?;
+        Ok(())
+    }
+}
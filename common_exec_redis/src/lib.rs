@@ -1,25 +1,40 @@
 mod db_conn;
 
+use std::sync::Arc;
 use common_err::CommonError;
 
-use common_core::collection::pool::get_thread_safe_pool;
-use common_pair_exec::{PairExecutor, PairExecutorInfo, PairExecutorPool};
-use db_conn::RedisConnection;
+use common_core::collection::pool::{get_async_thread_safe_pool, get_thread_safe_pool_with_policy};
+use common_pair_exec::{AsyncPairExecutor, AsyncPairExecutorPool, PairAddrSelector, PairExecutor, PairExecutorInfo, PairExecutorPool};
+use db_conn::{AsyncRedisConnection, RedisConnection};
 
 
 pub fn create_redis_pair_conn_pool(name : String, info : PairExecutorInfo, alloc_size : usize) -> PairExecutorPool {
-    let gen_fn : Box<dyn Fn(()) -> Result<Box<dyn PairExecutor>, CommonError>> = (|info : PairExecutorInfo| {
+    let policy = info.pool.to_pool_policy();
+    let selector = PairAddrSelector::new(info.addr.clone(), info.failover);
+
+    let gen_fn : Box<dyn Fn(()) -> Result<Box<dyn PairExecutor + Send>, CommonError> + Send + Sync> = (|info : PairExecutorInfo| {
 
         let real_fn  = move |_ : ()| {
             let conn_info = info.clone();
-            let conn = RedisConnection::new(
-                conn_info.addr[0].as_str(), 
-                conn_info.user.as_str(), 
-                conn_info.password.as_str(), 
-                conn_info.name.as_str());
+            let conn = selector.connect(|addr| {
+                let mut conn = RedisConnection::new(
+                    addr,
+                    conn_info.user.as_str(),
+                    conn_info.password.as_str(),
+                    conn_info.name.as_str(),
+                    conn_info.tls.as_ref())?;
+
+                // the client connects lazily, a round trip tells whether the node is alive
+                if conn_info.failover.require_primary {
+                    conn.check_primary()?;
+                } else if conn_info.addr.len() > 1 {
+                    conn.ping()?;
+                }
+                Ok(conn)
+            });
 
             match conn {
-                Ok(ok) => Ok(Box::new(ok) as Box<dyn PairExecutor>),
+                Ok(ok) => Ok(Box::new(ok) as Box<dyn PairExecutor + Send>),
                 Err(err) => {Err(err)}
             }
         };
@@ -27,5 +42,31 @@ pub fn create_redis_pair_conn_pool(name : String, info : PairExecutorInfo, alloc
         Box::new(real_fn)
     })(info);
 
-    get_thread_safe_pool(name, gen_fn, alloc_size)
-}
\ No newline at end of file
+    get_thread_safe_pool_with_policy(name, gen_fn, alloc_size, policy)
+}
+
+pub fn create_redis_async_pair_conn_pool(name : String, info : PairExecutorInfo, alloc_size : usize) -> AsyncPairExecutorPool {
+    let selector = Arc::new(PairAddrSelector::new(info.addr.clone(), info.failover));
+
+    get_async_thread_safe_pool(name, Box::new(move |_ : ()| {
+        let (conn_info, selector) = (info.clone(), selector.clone());
+        Box::pin(async move {
+            let conn = selector.connect_async(|addr| {
+                let conn_info = &conn_info;
+                async move {
+                    let mut conn = AsyncRedisConnection::new(
+                        addr.as_str(),
+                        conn_info.user.as_str(),
+                        conn_info.password.as_str(),
+                        conn_info.name.as_str(),
+                        conn_info.tls.as_ref()).await?;
+                    if conn_info.failover.require_primary {
+                        conn.check_primary().await?;
+                    }
+                    Ok(conn)
+                }
+            }).await?;
+            Ok(Box::new(conn) as Box<dyn AsyncPairExecutor>)
+        })
+    }), alloc_size)
+}
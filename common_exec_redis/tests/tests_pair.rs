@@ -1,12 +1,12 @@
 use std::collections::HashMap;
 use toml;
 use common_core::utils::func::generate_random_string;
-use common_err::CommonError;
+use common_err::{CommonError, CommonErrorKind};
 use common_err::gen::CommonDefaultErrorKind;
-use common_exec_redis::create_redis_pair_conn_pool;
-use common_pair_exec::{PairExecutor, PairExecutorInfo, PairValueEnum};
+use common_exec_redis::{create_redis_async_pair_conn_pool, create_redis_pair_conn_pool};
+use common_pair_exec::{PairExecutor, PairExecutorInfo, PairTlsOptions, PairTlsVerify, PairValueEnum};
 
-fn connect_redis_db() -> Result<common_pair_exec::PairExecutorPool, CommonError> {
+fn read_redis_info() -> Result<PairExecutorInfo, CommonError> {
     let read_toml : HashMap<String, String> = toml::from_str(include_str!("./tests.asset.toml")).map_err(|e| {
         CommonError::new(&CommonDefaultErrorKind::Etc, e.to_string())
     })?;
@@ -16,10 +16,17 @@ fn connect_redis_db() -> Result<common_pair_exec::PairExecutorPool, CommonError>
         user: read_toml["user"].clone(),
         password: read_toml["password"].clone(),
         timeout_sec: 3600,
-        extend: None
+        statement_cache_size: 64,
+        pool: Default::default(),
+        failover: Default::default(),
+        tls: None,
+        backend: None
     };
+    Ok(info)
+}
 
-    let p = create_redis_pair_conn_pool("test".to_string(), info, 5);
+fn connect_redis_db() -> Result<common_pair_exec::PairExecutorPool, CommonError> {
+    let p = create_redis_pair_conn_pool("test".to_string(), read_redis_info()?, 5);
     Ok(p)
 }
 #[test]
@@ -149,4 +156,53 @@ fn test_connect_select_list() -> Result<(), CommonError> {
     ]))?;
 
     Ok(())
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_connect_tls_options() -> Result<(), CommonError> {
+    let cert_dir = format!("{}/../common_pair_exec/tests/tls", env!("CARGO_MANIFEST_DIR"));
+    let has_kind = |err : CommonError, kind : &CommonDefaultErrorKind| err.func_ref().iter().any(|pos| pos.3.name() == kind.name());
+
+    // rejected before dialing, the driver cannot send another SNI
+    let mut info = read_redis_info()?;
+    info.tls = Some(PairTlsOptions { server_name : Some("localhost".to_string()), verify : PairTlsVerify::CaOnly, ..Default::default() });
+    let p = create_redis_pair_conn_pool("test".to_string(), info, 1);
+    assert!(has_kind(p.get_owned(()).err().unwrap(), &CommonDefaultErrorKind::NoSupport));
+
+    // the address host itself is what the driver sends anyway
+    let mut info = read_redis_info()?;
+    let host = info.addr[0].rsplit_once(':').map(|(host, _)| host.to_string()).unwrap_or_default();
+    info.tls = Some(PairTlsOptions { server_name : Some(host), verify : PairTlsVerify::CaOnly, ..Default::default() });
+    let p = create_redis_pair_conn_pool("test".to_string(), info, 1);
+    assert!(p.get_owned(()).is_ok());
+
+    let mut info = read_redis_info()?;
+    info.tls = Some(PairTlsOptions { cert_file : Some(format!("{}/client.crt", cert_dir)), ..Default::default() });
+    let p = create_redis_pair_conn_pool("test".to_string(), info, 1);
+    assert!(has_kind(p.get_owned(()).err().unwrap(), &CommonDefaultErrorKind::InitFailed));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_connect_async() -> Result<(), CommonError> {
+    let p = create_redis_async_pair_conn_pool("test".to_string(), read_redis_info()?, 2);
+    let mut item = p.get_owned(()).await?;
+    let conn = item.get_value();
+
+    let test_key = PairValueEnum::String("async_testing".to_string());
+    conn.execute_pair("del", &PairValueEnum::Array(vec![test_key.clone()])).await?;
+
+    conn.begin().await?;
+    conn.execute_pair("set", &PairValueEnum::Array(vec![test_key.clone(), PairValueEnum::String("hello".to_string())])).await?;
+    conn.commit().await?;
+
+    let ret = conn.execute_pair_result_set("get", &PairValueEnum::Array(vec![test_key.clone()])).await?;
+    let deleted = conn.execute_pair_outcome("del", &PairValueEnum::Array(vec![test_key])).await?;
+
+    assert_eq!(ret.get(0, 0), Some(&PairValueEnum::String("hello".to_string())));
+    assert_eq!(deleted.rows_affected, Some(1));
+    assert!(conn.get_current_time().await?.as_secs() > 0);
+
+    Ok(())
+}
@@ -1,21 +1,109 @@
+mod async_conn;
 mod util;
 
-use std::collections::HashMap;
+use std::future::Future;
+use std::sync::Arc;
 use std::time::Duration;
+use futures::future::{select, Either};
+use futures::StreamExt;
 use scylla::client::execution_profile::ExecutionProfile;
-use scylla::serialize::value::SerializeValue;
+use scylla::client::pager::{QueryPager, TypedRowStream};
+use scylla::errors::{DbError, ExecutionError, NextPageError, PagerExecutionError, RequestAttemptError, RequestError};
+use scylla::value::{CqlTimestamp, CqlValue};
 use tokio::runtime::{Builder, Runtime};
+use tokio::sync::Notify;
 use scylla::client::session::Session;
 use scylla::client::session_builder::SessionBuilder;
-use scylla::response::query_result::{QueryResult, QueryRowsResult};
+use scylla::statement::batch::{Batch, BatchType};
 use scylla::statement::prepared::PreparedStatement;
+use common_core::collection::lru::LruCache;
 use common_err::{CommonError, gen::CommonDefaultErrorKind};
-use common_pair_exec::{PairExecutor, PairExecutorInfo, PairValueEnum};
-use crate::db_conn::util::ScyllaPairFetcherRow;
+use common_pair_exec::{resolve_named_batch, resolve_named_param, PairCallOptions, PairColumnInfo, PairExecuteOutcome, PairExecutor, PairPlaceholderStyle, PairRowCursor, PairStatementCacheStats, PairTlsOptions, PairValueEnum};
+pub(crate) use async_conn::{create_scylla_session, AsyncScyllaConnection};
+use crate::db_conn::util::{convert_pair_date_to_cql, convert_pair_decimal_to_cql, convert_pair_time_to_cql, ScyllaPairFetcherRow};
+
+const CURSOR_PAGE_SIZE : i32 = 5000;
+// rows per unlogged batch, keeps a bulk load under batch_size_fail_threshold_in_kb and the statement limit
+const BATCH_CHUNK_ROWS : usize = 100;
+
+// one unlogged batch of the prepared statement per chunk of rows
+fn chunk_batches(prepare : &'_ PreparedStatement, values : Vec<Vec<Option<CqlValue>>>) -> Vec<(Batch, Vec<Vec<Option<CqlValue>>>)> {
+    let mut chunks = Vec::with_capacity(values.len().div_ceil(BATCH_CHUNK_ROWS));
+    let mut values = values.into_iter().peekable();
+
+    while values.peek().is_some() {
+        let chunk : Vec<_> = values.by_ref().take(BATCH_CHUNK_ROWS).collect();
+        let mut batch = Batch::new(BatchType::Unlogged);
+        for _ in 0..chunk.len() {
+            batch.append_statement(prepare.clone());
+        }
+        chunks.push((batch, chunk));
+    }
+    chunks
+}
+
+fn convert_pair_to_cql_param(param : &'_ PairValueEnum) -> Result<Vec<Option<CqlValue>>, CommonError> {
+    if let PairValueEnum::Array(a) = param {
+        let mut p_vec = Vec::new();
+        for param_data in a {
+            let p_ele : Option<CqlValue> = match param_data {
+                PairValueEnum::Int(i) => Some(CqlValue::Int(*i)),
+                PairValueEnum::Bin(bs) => Some(CqlValue::Blob(bs.clone())),
+                PairValueEnum::Double(f) => Some(CqlValue::Double(*f)),
+                PairValueEnum::String(s) => Some(CqlValue::Text(s.clone())),
+                PairValueEnum::Bool(b) => Some(CqlValue::Boolean(*b)),
+                PairValueEnum::Null => None,
+                PairValueEnum::BigInt(bi) => Some(CqlValue::BigInt(*bi)),
+                PairValueEnum::Float(f) => Some(CqlValue::Float(*f)),
+                PairValueEnum::SmallInt(i) => Some(CqlValue::SmallInt(*i)),
+                PairValueEnum::TinyInt(i) => Some(CqlValue::TinyInt(*i)),
+                PairValueEnum::Timestamp(t) => Some(CqlValue::Timestamp(CqlTimestamp(t.timestamp_millis()))),
+                PairValueEnum::Date(d) => Some(CqlValue::Date(convert_pair_date_to_cql(d))),
+                PairValueEnum::Time(t) => Some(CqlValue::Time(convert_pair_time_to_cql(t))),
+                PairValueEnum::Uuid(u) => Some(CqlValue::Uuid(*u)),
+                PairValueEnum::Decimal(d) => Some(CqlValue::Decimal(convert_pair_decimal_to_cql(d)?)),
+                PairValueEnum::Json(j) => Some(CqlValue::Text(j.clone())),
+                PairValueEnum::Inet(i) => Some(CqlValue::Inet(*i)),
+                _ => {
+                    return CommonError::new(&CommonDefaultErrorKind::InvalidApiCall, "not support type")
+                        .to_result();
+                }
+            };
+            p_vec.push(p_ele);
+        }
+        Ok(p_vec)
+    }
+    else if param == &PairValueEnum::Null {
+        Ok(Vec::new())
+    }
+    else {
+        CommonError::new(&CommonDefaultErrorKind::InvalidApiCall, "not array type").to_result()
+    }
+}
 
 pub struct ScyllaConnection {
     session : Session,
-    rt : Runtime
+    rt : Runtime,
+    tx_batch : Option<(Batch, Vec<Vec<Option<CqlValue>>>)>,
+    stmt_cache : LruCache<String, PreparedStatement>,
+    // set for the duration of execute_pair_with
+    request_timeout : Option<Duration>,
+    cancel : Option<Arc<Notify>>
+}
+
+// the driver future is dropped when the running call is cancelled
+fn block_on_cancellable<F : Future>(rt : &'_ Runtime, cancel : Option<&'_ Notify>, f : F) -> Result<F::Output, CommonError> {
+    let cancel = match cancel {
+        Some(cancel) => cancel,
+        None => return Ok(rt.block_on(f))
+    };
+
+    rt.block_on(async {
+        match select(Box::pin(f), Box::pin(cancel.notified())).await {
+            Either::Left((out, _)) => Ok(out),
+            Either::Right(_) => CommonError::new(&CommonDefaultErrorKind::ExecuteFail, "ScyllaConnection - request abandoned").to_result()
+        }
+    })
 }
 
 #[derive(Debug,Clone, Default)]
@@ -24,11 +112,13 @@ pub struct ScyllaConnInfo {
     pub name : String,
     pub user : String,
     pub password : String,
-    pub timeout_sec : u32
+    pub timeout_sec : u32,
+    pub statement_cache_size : usize,
+    pub tls : Option<PairTlsOptions>
 }
 
 impl ScyllaConnection {
-    pub(crate) fn new(infos : ScyllaConnInfo) -> Result<Self, CommonError> {
+    fn create_session_builder(infos : &'_ ScyllaConnInfo) -> Result<SessionBuilder, CommonError> {
         let mut builder = SessionBuilder::new();
         let mut profile_builder = ExecutionProfile::builder();
 
@@ -43,6 +133,16 @@ impl ScyllaConnection {
             .use_keyspace(infos.name.clone(), false)
             .default_execution_profile_handle(profile_builder.build().into_handle());
 
+        // the driver hands rustls the node ip, so server_name is verified but never sent as SNI
+        if let Some(tls) = &infos.tls {
+            builder = builder.tls_context(Some(Arc::new(tls.client_config()?)));
+        }
+        Ok(builder)
+    }
+
+    pub(crate) fn new(infos : ScyllaConnInfo) -> Result<Self, CommonError> {
+        let builder = Self::create_session_builder(&infos)?;
+
         let rt = Builder::new_current_thread()
             .enable_all()
             .build()
@@ -52,36 +152,98 @@ impl ScyllaConnection {
         let block = rt.block_on(feature);
 
         match block {
-            Ok(ok) => Ok(ScyllaConnection {session : ok, rt}),
+            Ok(ok) => Ok(ScyllaConnection {session : ok, rt, tx_batch : None, stmt_cache : LruCache::new(infos.statement_cache_size),
+                                           request_timeout : None, cancel : None}),
             Err(err) => CommonError::new(&CommonDefaultErrorKind::ConnectFail,
                                          format!("ScyllaConnection.new - {}", err)).to_result()
         }
     }
     
     pub fn get_prepare(&mut self, query : &'_ str) -> Result<PreparedStatement, CommonError> {
+        if let Some(prepare) = self.stmt_cache.get(query) {
+            return Ok(prepare.clone());
+        }
+
         let feature = self.session.prepare(query);
 
-        match self.rt.block_on(feature) {
+        let prepare = match block_on_cancellable(&self.rt, self.cancel.as_deref(), feature)? {
             Ok(ok) => Ok(ok),
             Err(err) => CommonError::new(&CommonDefaultErrorKind::InvalidApiCall,
                                          format!("ScyllaConnection.get_prepare - {}", err)).to_result()
+        }?;
+
+        self.stmt_cache.put(query.to_string(), prepare.clone());
+        Ok(prepare)
+    }
+
+    // the driver re-prepares by itself, but a schema change leaves stale metadata in the cache
+    fn is_stale_prepare(err : &RequestAttemptError) -> bool {
+        matches!(err, RequestAttemptError::DbError(DbError::Unprepared { .. } | DbError::Invalid, _))
+    }
+
+    // timeouts and missing replicas clear up on their own, a broken connection needs a new one
+    fn attempt_error_kind(err : &'_ RequestAttemptError, kind : &'static CommonDefaultErrorKind) -> &'static CommonDefaultErrorKind {
+        match err {
+            RequestAttemptError::BrokenConnectionError(_) | RequestAttemptError::UnableToAllocStreamId => &CommonDefaultErrorKind::ConnectFail,
+            RequestAttemptError::DbError(DbError::Unavailable { .. } | DbError::ReadTimeout { .. } | DbError::WriteTimeout { .. } |
+                                         DbError::Overloaded | DbError::IsBootstrapping | DbError::RateLimitReached { .. }, _) => &CommonDefaultErrorKind::Transient,
+            _ => kind
         }
     }
-    
-    pub fn execute_query(&mut self, prepare : &PreparedStatement, p : &[Option<&dyn SerializeValue>]) -> Result<QueryResult, CommonError> {
-        let feature = self.session.execute_unpaged(prepare, p);
-        match self.rt.block_on(feature) {
-            Ok(ok) => Ok(ok),
-            Err(err) => CommonError::new(&CommonDefaultErrorKind::ExecuteFail,
-                                         format!("ScyllaConnection.execute_query - execute_query - {}", err)).to_result()
+
+    fn execution_error_kind(err : &'_ ExecutionError, kind : &'static CommonDefaultErrorKind) -> &'static CommonDefaultErrorKind {
+        match err {
+            ExecutionError::ConnectionPoolError(_) => &CommonDefaultErrorKind::ConnectFail,
+            ExecutionError::RequestTimeout(_) => &CommonDefaultErrorKind::Transient,
+            ExecutionError::LastAttemptError(e) => Self::attempt_error_kind(e, kind),
+            _ => kind
         }
     }
-    
-    pub fn get_query_row_result(&self, qr : QueryResult) -> Result<QueryRowsResult, CommonError> {
-        match qr.into_rows_result() {
+
+    fn pager_error_kind(err : &'_ PagerExecutionError, kind : &'static CommonDefaultErrorKind) -> &'static CommonDefaultErrorKind {
+        match err {
+            PagerExecutionError::NextPageError(NextPageError::RequestFailure(e)) => match e {
+                RequestError::ConnectionPoolError(_) => &CommonDefaultErrorKind::ConnectFail,
+                RequestError::RequestTimeout(_) => &CommonDefaultErrorKind::Transient,
+                RequestError::LastAttemptError(e) => Self::attempt_error_kind(e, kind),
+                _ => kind
+            },
+            _ => kind
+        }
+    }
+
+    pub fn execute_query_iter(&mut self, query : &'_ str, prepare : PreparedStatement, p : Vec<Option<CqlValue>>) -> Result<QueryPager, CommonError> {
+        let feature = self.session.execute_iter(prepare, p);
+        match block_on_cancellable(&self.rt, self.cancel.as_deref(), feature)? {
             Ok(ok) => Ok(ok),
-            Err(err) => CommonError::new(&CommonDefaultErrorKind::InvalidApiCall,
-                                         format!("ScyllaConnection.get_query_row_result - query_result - {}", err)).to_result()
+            Err(err) => {
+                let stale = match &err {
+                    PagerExecutionError::NextPageError(NextPageError::RequestFailure(RequestError::LastAttemptError(e))) => Self::is_stale_prepare(e),
+                    PagerExecutionError::NextPageError(NextPageError::TypeCheckError(_)) => true,
+                    _ => false
+                };
+                if stale {
+                    self.stmt_cache.remove(query);
+                }
+                CommonError::new(Self::pager_error_kind(&err, &CommonDefaultErrorKind::ExecuteFail),
+                                 format!("ScyllaConnection.execute_query_iter - execute_iter - {}", err)).to_result()
+            }
+        }
+    }
+
+    fn run_batch(&mut self, query : Option<&'_ str>, batch : Batch, values : Vec<Vec<Option<CqlValue>>>) -> Result<(), CommonError> {
+        let feature = self.session.batch(&batch, values);
+        match block_on_cancellable(&self.rt, self.cancel.as_deref(), feature)? {
+            Ok(_) => Ok(()),
+            Err(err) => {
+                if let (Some(q), ExecutionError::LastAttemptError(e)) = (query, &err) {
+                    if Self::is_stale_prepare(e) {
+                        self.stmt_cache.remove(q);
+                    }
+                }
+                CommonError::new(Self::execution_error_kind(&err, &CommonDefaultErrorKind::ExecuteFail),
+                                 format!("ScyllaConnection.run_batch - batch - {}", err)).to_result()
+            }
         }
     }
 
@@ -112,104 +274,193 @@ impl ScyllaConnection {
     }
 }
 
-impl PairExecutor for ScyllaConnection {
-    fn execute_pair(&mut self, query: &'_ str, param: &PairValueEnum) -> Result<PairValueEnum, CommonError> {
-        let execute_param = if let PairValueEnum::Array(a) = &param {
-            let mut p_vec = Vec::new();
-            for param_data in a {
-                let p_ele : Option<&dyn SerializeValue> = match param_data {
-                    PairValueEnum::Int(i) => Some(i),
-                    PairValueEnum::Bin(bs) => Some(bs),
-                    PairValueEnum::Double(f) => Some(f),
-                    PairValueEnum::String(s) => Some(s),
-                    PairValueEnum::Bool(b) => Some(b),
-                    PairValueEnum::Null => None,
-                    PairValueEnum::BigInt(bi) => Some(bi),
-                    PairValueEnum::Float(f) => Some(f),
-                    _ => {
-                        return CommonError::new(&CommonDefaultErrorKind::InvalidApiCall, "not support type")
-                            .to_result();
-                    }
-                };
-                p_vec.push(p_ele);
-            }
-            Ok(p_vec)
+fn convert_fetcher_row<E : std::fmt::Display>(r : Result<ScyllaPairFetcherRow, E>) -> Result<Vec<PairValueEnum>, CommonError> {
+    let mut convert_row = match r {
+        Ok(ok) => Ok(ok),
+        Err(err) => CommonError::new(&CommonDefaultErrorKind::InvalidApiCall,
+                                     format!("ScyllaRowCursor.next_row - convert_row - {}", err)).to_result()
+    }?;
+
+    let chk_err = convert_row.get_error();
+
+    if chk_err.is_err() {
+        return CommonError::extend(&CommonDefaultErrorKind::InvalidApiCall, "row read fail", chk_err.err().unwrap()).to_result()
+    }
+
+    Ok(convert_row.into_col())
+}
+
+pub(crate) struct ScyllaRowCursor<'a> {
+    cols : Vec<PairColumnInfo>,
+    stream : TypedRowStream<ScyllaPairFetcherRow>,
+    rt : &'a Runtime,
+    cancel : Option<&'a Notify>
+}
+
+impl PairRowCursor for ScyllaRowCursor<'_> {
+    fn columns(&self) -> &'_ [PairColumnInfo] {
+        self.cols.as_slice()
+    }
+
+    fn next_row(&mut self) -> Result<Option<Vec<PairValueEnum>>, CommonError> {
+        match block_on_cancellable(self.rt, self.cancel, self.stream.next())? {
+            Some(r) => Ok(Some(convert_fetcher_row(r)?)),
+            None => Ok(None)
         }
-        else if param == &PairValueEnum::Null {
-            Ok(Vec::new())
-        }  
-        else {
-            CommonError::new(&CommonDefaultErrorKind::InvalidApiCall, "not array type").to_result()
-        }?;
-        
-        let prepare = self.get_prepare(query)?;
+    }
+}
 
-        let query_result = self.execute_query(&prepare, execute_param.as_slice())?;
-        
-        let cols_g = prepare.get_result_set_col_specs();
+struct ScyllaQueuedCursor;
+
+impl PairRowCursor for ScyllaQueuedCursor {
+    fn columns(&self) -> &'_ [PairColumnInfo] {
+        &[]
+    }
+
+    fn next_row(&mut self) -> Result<Option<Vec<PairValueEnum>>, CommonError> {
+        Ok(None)
+    }
+}
+
+impl PairExecutor for ScyllaConnection {
+    fn execute_pair_cursor<'a>(&'a mut self, query: &'_ str, param: &PairValueEnum) -> Result<Box<dyn PairRowCursor + 'a>, CommonError> {
+        let (query, param) = resolve_named_param(query, param, PairPlaceholderStyle::Question)?;
+        let query = query.as_ref();
+        let execute_param = convert_pair_to_cql_param(param.as_ref())?;
 
-        if cols_g.len() <= 0 {
-            return Ok(PairValueEnum::Null);
+        let mut prepare = self.get_prepare(query)?;
+        prepare.set_page_size(CURSOR_PAGE_SIZE);
+        if self.request_timeout.is_some() {
+            prepare.set_request_timeout(self.request_timeout);
         }
-        
-        let mut cache = HashMap::new();
-        let mut cache_idx_m = HashMap::new();
-        
-        {
-            let slice = cols_g.as_slice();
-            for cols in 0..cols_g.len() {
-                cache_idx_m.insert(cols, slice[cols].name());
-                cache.entry(slice[cols].name()).or_insert_with(|| {Vec::<PairValueEnum>::new()});
-            }    
+
+        let cols : Vec<PairColumnInfo> = prepare.get_current_result_set_col_specs().get().iter().map(|c| {
+            PairColumnInfo::new(c.name(), format!("{:?}", c.typ()), true)
+        }).collect();
+
+        // inside a transaction, statements without result set are queued into the logged batch
+        if cols.is_empty() {
+            if let Some((batch, values)) = self.tx_batch.as_mut() {
+                batch.append_statement(prepare);
+                values.push(execute_param);
+                return Ok(Box::new(ScyllaQueuedCursor));
+            }
         }
-        
-        let rows = self.get_query_row_result(query_result)?;
 
-        let mut row_iter = match rows.rows::<ScyllaPairFetcherRow>() {
+        let pager = self.execute_query_iter(query, prepare, execute_param)?;
+
+        let stream = match pager.rows_stream::<ScyllaPairFetcherRow>() {
             Ok(ok) => Ok(ok),
             Err(err) => CommonError::new(&CommonDefaultErrorKind::InvalidApiCall,
-                                         format!("ScyllaConnection.execute_pair - row_iter - {}", err)).to_result()
+                                         format!("ScyllaConnection.execute_pair_cursor - row_iter - {}", err)).to_result()
         }?;
 
-        while let Some(r) = row_iter.next() {
-            let mut convert_row = match r {
-                Ok(ok) => Ok(ok),
-                Err(err) => CommonError::new(&CommonDefaultErrorKind::InvalidApiCall,
-                                             format!("ScyllaConnection.execute_pair - convert_row - {}", err)).to_result()
-            }?;
-
-            let chk_err = convert_row.get_error();
-            
-            if chk_err.is_err() {
-                return CommonError::extend(&CommonDefaultErrorKind::InvalidApiCall, "row read fail", chk_err.err().unwrap()).to_result()
-            }
-            let col_data = convert_row.as_slice();
-            
-            for data_idx in 0..col_data.len() {
-                let key = cache_idx_m.get(&data_idx).ok_or_else(|| {
-                    CommonError::new(&CommonDefaultErrorKind::NoData, format!("not exists idx : {}", data_idx)) 
-                })?;
-                
-                let v = cache.get_mut(key).ok_or_else(|| {
-                    CommonError::new(&CommonDefaultErrorKind::NoData, format!("not exists col idx : {}", data_idx))
-                })?;
-                
-                v.push(col_data[data_idx].clone());
-            }
+        Ok(Box::new(ScyllaRowCursor { cols, stream, rt : &self.rt, cancel : self.cancel.as_deref() }))
+    }
+
+    fn execute_pair_outcome(&mut self, query : &'_ str, param : &PairValueEnum) -> Result<PairExecuteOutcome, CommonError> {
+        let result_set = self.execute_pair_result_set(query, param)?;
+
+        // cql reports no write counts, only conditional (LWT) statements tell through [applied]
+        let rows_affected = match result_set.get_by_name(0, "[applied]") {
+            Some(PairValueEnum::Bool(applied)) => Some(if *applied { 1 } else { 0 }),
+            _ => None
+        };
+
+        Ok(PairExecuteOutcome::new(rows_affected, result_set))
+    }
+
+    fn get_current_time(&mut self) -> Result<std::time::Duration, CommonError> {
+        self.get_current_duration()
+    }
+
+    // the deadline becomes the per statement request timeout, paging past it is cut by the watchdog
+    fn execute_pair_with(&mut self, query : &'_ str, param : &PairValueEnum, options : &PairCallOptions) -> Result<PairValueEnum, CommonError> {
+        if options.is_empty() {
+            return self.execute_pair(query, param);
+        }
+
+        let guard = options.start()?;
+        let cancel = Arc::new(Notify::new());
+        let waker = cancel.clone();
+        guard.set_canceller(move || waker.notify_one())?;
+
+        self.request_timeout = options.remaining();
+        self.cancel = Some(cancel);
+        let ret = self.execute_pair(query, param);
+        self.request_timeout = None;
+        self.cancel = None;
+
+        guard.finish(ret)
+    }
+
+    fn execute_batch(&mut self, query : &'_ str, rows : &'_ [PairValueEnum]) -> Result<Vec<u64>, CommonError> {
+        if rows.is_empty() {
+            return Ok(Vec::new());
         }
 
-        let mut ret = PairValueEnum::Null;
-        let mut convert_m = HashMap::new();
-        if cache.len() > 0 {
-            for item in cache {
-                convert_m.insert(item.0.to_string(), PairValueEnum::Array(item.1));
+        let (query, rows) = resolve_named_batch(query, rows, PairPlaceholderStyle::Question)?;
+        let query = query.as_ref();
+        let prepare = self.get_prepare(query)?;
+
+        let mut values = Vec::with_capacity(rows.len());
+        for row in rows.iter() {
+            values.push(convert_pair_to_cql_param(row)?);
+        }
+
+        // cql does not report affected rows, every applied statement counts as one
+        let counts = vec![1; values.len()];
+
+        if let Some((batch, tx_values)) = self.tx_batch.as_mut() {
+            for v in values {
+                batch.append_statement(prepare.clone());
+                tx_values.push(v);
             }
-            ret = PairValueEnum::Map(convert_m);
+            return Ok(counts);
         }
-        Ok(ret)
+
+        // unlogged batches are not atomic anyway, chunks already sent stay applied when a later one fails
+        for (batch, chunk) in chunk_batches(&prepare, values) {
+            self.run_batch(Some(query), batch, chunk)?;
+        }
+        Ok(counts)
     }
 
-    fn get_current_time(&mut self) -> Result<std::time::Duration, CommonError> {
-        self.get_current_duration()
+    fn statement_cache_stats(&self) -> Option<PairStatementCacheStats> {
+        Some(PairStatementCacheStats {
+            hit : self.stmt_cache.hit_count(),
+            miss : self.stmt_cache.miss_count(),
+            size : self.stmt_cache.len(),
+            capacity : self.stmt_cache.capacity()
+        })
+    }
+
+    fn begin(&mut self) -> Result<(), CommonError> {
+        if self.tx_batch.is_some() {
+            return CommonError::new(&CommonDefaultErrorKind::InvalidApiCall, "ScyllaConnection.begin - already in transaction").to_result();
+        }
+
+        self.tx_batch = Some((Batch::new(BatchType::Logged), Vec::new()));
+        Ok(())
+    }
+
+    fn commit(&mut self) -> Result<(), CommonError> {
+        let (batch, values) = match self.tx_batch.take() {
+            Some(tx) => tx,
+            None => return CommonError::new(&CommonDefaultErrorKind::InvalidApiCall, "ScyllaConnection.commit - not in transaction").to_result()
+        };
+
+        if values.is_empty() {
+            return Ok(());
+        }
+
+        self.run_batch(None, batch, values)
     }
-}
\ No newline at end of file
+
+    fn rollback(&mut self) -> Result<(), CommonError> {
+        match self.tx_batch.take() {
+            Some(_) => Ok(()),
+            None => CommonError::new(&CommonDefaultErrorKind::InvalidApiCall, "ScyllaConnection.rollback - not in transaction").to_result()
+        }
+    }
+}
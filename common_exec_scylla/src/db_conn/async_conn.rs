@@ -0,0 +1,207 @@
+use std::sync::Arc;
+use std::time::Duration;
+use async_trait::async_trait;
+use futures::StreamExt;
+use scylla::client::session::Session;
+use scylla::errors::{ExecutionError, NextPageError, PagerExecutionError, RequestError};
+use scylla::statement::batch::{Batch, BatchType};
+use scylla::statement::prepared::PreparedStatement;
+use scylla::value::CqlValue;
+use common_core::collection::lru::LruCache;
+use common_err::{CommonError, gen::CommonDefaultErrorKind};
+use common_pair_exec::{resolve_named_batch, resolve_named_param, AsyncPairExecutor, PairColumnInfo, PairExecuteOutcome,
+                       PairPlaceholderStyle, PairResultSet, PairStatementCacheStats, PairValueEnum};
+use crate::db_conn::util::ScyllaPairFetcherRow;
+use super::{chunk_batches, convert_fetcher_row, convert_pair_to_cql_param, ScyllaConnInfo, ScyllaConnection};
+
+// Session is already a multiplexed pool, every pooled executor shares one
+pub(crate) async fn create_scylla_session(infos : &'_ ScyllaConnInfo) -> Result<Arc<Session>, CommonError> {
+    ScyllaConnection::create_session_builder(infos)?.build().await.map(Arc::new).map_err(|e| {
+        CommonError::new(&CommonDefaultErrorKind::ConnectFail, format!("AsyncScyllaConnection.new - {}", e))
+    })
+}
+
+pub struct AsyncScyllaConnection {
+    session : Arc<Session>,
+    tx_batch : Option<(Batch, Vec<Vec<Option<CqlValue>>>)>,
+    stmt_cache : LruCache<String, PreparedStatement>
+}
+
+impl AsyncScyllaConnection {
+    pub(crate) fn new(session : Arc<Session>, statement_cache_size : usize) -> Self {
+        AsyncScyllaConnection { session, tx_batch : None, stmt_cache : LruCache::new(statement_cache_size) }
+    }
+
+    async fn get_prepare(&mut self, query : &'_ str) -> Result<PreparedStatement, CommonError> {
+        if let Some(prepare) = self.stmt_cache.get(query) {
+            return Ok(prepare.clone());
+        }
+
+        let prepare = self.session.prepare(query).await.map_err(|e| {
+            CommonError::new(&CommonDefaultErrorKind::InvalidApiCall, format!("AsyncScyllaConnection.get_prepare - {}", e))
+        })?;
+
+        self.stmt_cache.put(query.to_string(), prepare.clone());
+        Ok(prepare)
+    }
+
+    async fn run_batch(&mut self, query : Option<&'_ str>, batch : Batch, values : Vec<Vec<Option<CqlValue>>>) -> Result<(), CommonError> {
+        match self.session.batch(&batch, values).await {
+            Ok(_) => Ok(()),
+            Err(err) => {
+                if let (Some(q), ExecutionError::LastAttemptError(e)) = (query, &err) {
+                    if ScyllaConnection::is_stale_prepare(e) {
+                        self.stmt_cache.remove(q);
+                    }
+                }
+                CommonError::new(ScyllaConnection::execution_error_kind(&err, &CommonDefaultErrorKind::ExecuteFail),
+                                 format!("AsyncScyllaConnection.run_batch - batch - {}", err)).to_result()
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl AsyncPairExecutor for AsyncScyllaConnection {
+    async fn execute_pair_result_set(&mut self, query : &'_ str, param : &PairValueEnum) -> Result<PairResultSet, CommonError> {
+        let (query, param) = resolve_named_param(query, param, PairPlaceholderStyle::Question)?;
+        let query = query.as_ref();
+        let execute_param = convert_pair_to_cql_param(param.as_ref())?;
+
+        let prepare = self.get_prepare(query).await?;
+
+        let cols : Vec<PairColumnInfo> = prepare.get_current_result_set_col_specs().get().iter().map(|c| {
+            PairColumnInfo::new(c.name(), format!("{:?}", c.typ()), true)
+        }).collect();
+
+        // inside a transaction, statements without result set are queued into the logged batch
+        if cols.is_empty() {
+            if let Some((batch, values)) = self.tx_batch.as_mut() {
+                batch.append_statement(prepare);
+                values.push(execute_param);
+                return Ok(PairResultSet::default());
+            }
+        }
+
+        let pager = match self.session.execute_iter(prepare, execute_param).await {
+            Ok(ok) => ok,
+            Err(err) => {
+                let stale = match &err {
+                    PagerExecutionError::NextPageError(NextPageError::RequestFailure(RequestError::LastAttemptError(e))) => ScyllaConnection::is_stale_prepare(e),
+                    PagerExecutionError::NextPageError(NextPageError::TypeCheckError(_)) => true,
+                    _ => false
+                };
+                if stale {
+                    self.stmt_cache.remove(query);
+                }
+                return CommonError::new(ScyllaConnection::pager_error_kind(&err, &CommonDefaultErrorKind::ExecuteFail),
+                                        format!("AsyncScyllaConnection.execute_pair_result_set - execute_iter - {}", err)).to_result();
+            }
+        };
+
+        let mut stream = pager.rows_stream::<ScyllaPairFetcherRow>().map_err(|e| {
+            CommonError::new(&CommonDefaultErrorKind::InvalidApiCall,
+                             format!("AsyncScyllaConnection.execute_pair_result_set - row_iter - {}", e))
+        })?;
+
+        let mut result_set = PairResultSet::new(cols);
+        while let Some(r) = stream.next().await {
+            result_set.push_row(convert_fetcher_row(r)?)?;
+        }
+
+        Ok(result_set)
+    }
+
+    async fn execute_pair_outcome(&mut self, query : &'_ str, param : &PairValueEnum) -> Result<PairExecuteOutcome, CommonError> {
+        let result_set = self.execute_pair_result_set(query, param).await?;
+
+        let rows_affected = match result_set.get_by_name(0, "[applied]") {
+            Some(PairValueEnum::Bool(applied)) => Some(if *applied { 1 } else { 0 }),
+            _ if !result_set.columns().is_empty() => Some(result_set.row_count() as u64),
+            _ => None
+        };
+
+        Ok(PairExecuteOutcome::new(rows_affected, result_set))
+    }
+
+    async fn get_current_time(&mut self) -> Result<Duration, CommonError> {
+        let ret = self.execute_pair_result_set("SELECT CAST(toUnixTimestamp(now()) AS BIGINT) AS unix_timestamp  FROM system.local", &PairValueEnum::Null).await.map_err(|e| {
+            CommonError::extend(&CommonDefaultErrorKind::ExecuteFail, "get timestamp failed", e)
+        })?;
+
+        match ret.get_by_name(0, "unix_timestamp") {
+            Some(PairValueEnum::BigInt(unix_data)) => Ok(Duration::from_millis(*unix_data as u64)),
+            Some(_) => CommonError::new(&CommonDefaultErrorKind::NotMatchArgs, "no unix_timestamp value, cols is not int").to_result(),
+            None => CommonError::new(&CommonDefaultErrorKind::NoData, "no unix_timestamp value, unix_timestamp not exists").to_result()
+        }
+    }
+
+    async fn execute_batch(&mut self, query : &'_ str, rows : &'_ [PairValueEnum]) -> Result<Vec<u64>, CommonError> {
+        if rows.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let (query, rows) = resolve_named_batch(query, rows, PairPlaceholderStyle::Question)?;
+        let query = query.as_ref();
+        let prepare = self.get_prepare(query).await?;
+
+        let mut values = Vec::with_capacity(rows.len());
+        for row in rows.iter() {
+            values.push(convert_pair_to_cql_param(row)?);
+        }
+
+        // cql does not report affected rows, every applied statement counts as one
+        let counts = vec![1; values.len()];
+
+        if let Some((batch, tx_values)) = self.tx_batch.as_mut() {
+            for v in values {
+                batch.append_statement(prepare.clone());
+                tx_values.push(v);
+            }
+            return Ok(counts);
+        }
+
+        for (batch, chunk) in chunk_batches(&prepare, values) {
+            self.run_batch(Some(query), batch, chunk).await?;
+        }
+        Ok(counts)
+    }
+
+    fn statement_cache_stats(&self) -> Option<PairStatementCacheStats> {
+        Some(PairStatementCacheStats {
+            hit : self.stmt_cache.hit_count(),
+            miss : self.stmt_cache.miss_count(),
+            size : self.stmt_cache.len(),
+            capacity : self.stmt_cache.capacity()
+        })
+    }
+
+    async fn begin(&mut self) -> Result<(), CommonError> {
+        if self.tx_batch.is_some() {
+            return CommonError::new(&CommonDefaultErrorKind::InvalidApiCall, "AsyncScyllaConnection.begin - already in transaction").to_result();
+        }
+
+        self.tx_batch = Some((Batch::new(BatchType::Logged), Vec::new()));
+        Ok(())
+    }
+
+    async fn commit(&mut self) -> Result<(), CommonError> {
+        let (batch, values) = match self.tx_batch.take() {
+            Some(tx) => tx,
+            None => return CommonError::new(&CommonDefaultErrorKind::InvalidApiCall, "AsyncScyllaConnection.commit - not in transaction").to_result()
+        };
+
+        if values.is_empty() {
+            return Ok(());
+        }
+
+        self.run_batch(None, batch, values).await
+    }
+
+    async fn rollback(&mut self) -> Result<(), CommonError> {
+        match self.tx_batch.take() {
+            Some(_) => Ok(()),
+            None => CommonError::new(&CommonDefaultErrorKind::InvalidApiCall, "AsyncScyllaConnection.rollback - not in transaction").to_result()
+        }
+    }
+}
@@ -1,4 +1,4 @@
 
 mod pair;
 
-pub(crate) use pair::ScyllaPairFetcherRow;
+pub(crate) use pair::{convert_pair_date_to_cql, convert_pair_decimal_to_cql, convert_pair_time_to_cql, ScyllaPairFetcherRow};
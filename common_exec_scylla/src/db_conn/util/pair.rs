@@ -2,15 +2,48 @@
 
 use scylla::deserialize::row::DeserializeRow;
 use scylla::deserialize::value::DeserializeValue;
-use scylla::value::CqlValue;
+use chrono::{DateTime, NaiveDate, NaiveTime, TimeDelta};
+use scylla::value::{CqlDate, CqlDecimal, CqlTime, CqlValue};
 use scylla::frame::response::result::NativeType;
 use scylla::frame::response::result::ColumnType;
 use common_err::{CommonError, gen::CommonDefaultErrorKind};
-use common_pair_exec::PairValueEnum;
+use common_pair_exec::{PairDecimal, PairValueEnum};
+
+const CQL_DATE_EPOCH : u32 = 1 << 31;
+const NANOS_PER_SEC : i64 = 1_000_000_000;
+
+pub(crate) fn convert_pair_decimal_to_cql(d : &'_ PairDecimal) -> Result<CqlDecimal, CommonError> {
+    let scale = i32::try_from(d.scale()).map_err(|_| {
+        CommonError::new(&CommonDefaultErrorKind::ParsingFail, format!("convert_pair_decimal_to_cql - scale overflow {}", d.scale()))
+    })?;
+
+    // varint is the shortest two's complement form
+    let bytes = d.value().to_be_bytes();
+    let mut start = 0;
+    while start < bytes.len() - 1 {
+        let redundant = (bytes[start] == 0x00 && bytes[start + 1] & 0x80 == 0) ||
+            (bytes[start] == 0xff && bytes[start + 1] & 0x80 != 0);
+        if !redundant {
+            break;
+        }
+        start += 1;
+    }
+
+    Ok(CqlDecimal::from_signed_be_bytes_slice_and_exponent(&bytes[start..], scale))
+}
+
+pub(crate) fn convert_pair_date_to_cql(d : &'_ NaiveDate) -> CqlDate {
+    let days = d.signed_duration_since(NaiveDate::default()).num_days();
+    CqlDate((CQL_DATE_EPOCH as i64 + days) as u32)
+}
+
+pub(crate) fn convert_pair_time_to_cql(t : &'_ NaiveTime) -> CqlTime {
+    CqlTime((*t - NaiveTime::MIN).num_nanoseconds().unwrap_or(0))
+}
 
 pub(crate) struct ScyllaPairFetcherRow {
     col : Vec<PairValueEnum>,
-    catch_err : Option<Result<(), Box<dyn std::error::Error>>>
+    catch_err : Option<Result<(), Box<dyn std::error::Error + Send + Sync>>>
 }
 
 impl ScyllaPairFetcherRow {
@@ -29,7 +62,7 @@ impl ScyllaPairFetcherRow {
     pub fn clone_col(&self) -> Vec<PairValueEnum> {
         self.col.clone()
     }
-    pub fn as_slice(&self) -> &[PairValueEnum] {self.col.as_slice()}
+    pub fn into_col(self) -> Vec<PairValueEnum> {self.col}
 
     #[inline]
     fn cast_cql_val_to_comm_int_value(t : &'_ NativeType, cql_value : &'_ CqlValue) -> PairValueEnum {
@@ -42,12 +75,20 @@ impl ScyllaPairFetcherRow {
                     PairValueEnum::Int(opt.unwrap())
                 }
             },
+            NativeType::SmallInt => {
+                let opt = cql_value.as_smallint();
+                if opt.is_none() {
+                    PairValueEnum::Null
+                }else {
+                    PairValueEnum::SmallInt(opt.unwrap())
+                }
+            },
             NativeType::TinyInt => {
                 let opt = cql_value.as_tinyint();
                 if opt.is_none() {
                     PairValueEnum::Null
                 }else {
-                    PairValueEnum::Int(opt.unwrap() as i32)
+                    PairValueEnum::TinyInt(opt.unwrap())
                 }
             },
             _ => PairValueEnum::Null
@@ -114,15 +155,100 @@ impl ScyllaPairFetcherRow {
         }
     }
 
+    #[inline]
+    fn cast_cql_val_to_comm_uuid_value(cql_value : &'_ CqlValue) -> PairValueEnum {
+        let opt = cql_value.as_uuid().or_else(|| cql_value.as_timeuuid().map(|u| u.into()));
+        if opt.is_none() {
+            PairValueEnum::Null
+        }else {
+            PairValueEnum::Uuid(opt.unwrap())
+        }
+    }
+
+    #[inline]
+    fn cast_cql_val_to_comm_inet_value(cql_value : &'_ CqlValue) -> PairValueEnum {
+        let opt = cql_value.as_inet();
+        if opt.is_none() {
+            PairValueEnum::Null
+        }else {
+            PairValueEnum::Inet(opt.unwrap())
+        }
+    }
+
+    fn cast_cql_val_to_comm_timestamp_value(cql_value : &'_ CqlValue) -> Result<PairValueEnum, CommonError> {
+        let Some(ts) = cql_value.as_cql_timestamp() else {
+            return Ok(PairValueEnum::Null);
+        };
+
+        let t = DateTime::from_timestamp_millis(ts.0).ok_or_else(|| {
+            CommonError::new(&CommonDefaultErrorKind::ParsingFail, format!("cast_cql_val_to_comm_timestamp_value - out of range {}", ts.0))
+        })?;
+        Ok(PairValueEnum::Timestamp(t.fixed_offset()))
+    }
+
+    fn cast_cql_val_to_comm_date_value(cql_value : &'_ CqlValue) -> Result<PairValueEnum, CommonError> {
+        let Some(date) = cql_value.as_cql_date() else {
+            return Ok(PairValueEnum::Null);
+        };
+
+        let days = date.0 as i64 - CQL_DATE_EPOCH as i64;
+        let d = NaiveDate::default().checked_add_signed(TimeDelta::days(days)).ok_or_else(|| {
+            CommonError::new(&CommonDefaultErrorKind::ParsingFail, format!("cast_cql_val_to_comm_date_value - out of range {}", date.0))
+        })?;
+        Ok(PairValueEnum::Date(d))
+    }
+
+    fn cast_cql_val_to_comm_time_value(cql_value : &'_ CqlValue) -> Result<PairValueEnum, CommonError> {
+        let Some(time) = cql_value.as_cql_time() else {
+            return Ok(PairValueEnum::Null);
+        };
+
+        let t = NaiveTime::from_num_seconds_from_midnight_opt((time.0 / NANOS_PER_SEC) as u32, (time.0 % NANOS_PER_SEC) as u32).ok_or_else(|| {
+            CommonError::new(&CommonDefaultErrorKind::ParsingFail, format!("cast_cql_val_to_comm_time_value - out of range {}", time.0))
+        })?;
+        Ok(PairValueEnum::Time(t))
+    }
+
+    fn cast_cql_val_to_comm_decimal_value(cql_value : &'_ CqlValue) -> Result<PairValueEnum, CommonError> {
+        let CqlValue::Decimal(d) = cql_value else {
+            return Ok(PairValueEnum::Null);
+        };
+
+        let (bytes, exp) = d.as_signed_be_bytes_slice_and_exponent();
+        let overflow = || CommonError::new(&CommonDefaultErrorKind::ParsingFail, "cast_cql_val_to_comm_decimal_value - overflow i128");
+        if bytes.len() > 16 {
+            return Err(overflow());
+        }
+
+        // sign extend the varint into 16 bytes
+        let fill = if bytes.first().map(|b| b & 0x80 != 0).unwrap_or(false) { 0xff } else { 0x00 };
+        let mut buf = [fill; 16];
+        buf[16 - bytes.len()..].copy_from_slice(bytes);
+        let value = i128::from_be_bytes(buf);
+
+        if exp >= 0 {
+            Ok(PairValueEnum::Decimal(PairDecimal::new(value, exp as u32)))
+        } else {
+            let value = 10i128.checked_pow(exp.unsigned_abs()).and_then(|p| value.checked_mul(p)).ok_or_else(overflow)?;
+            Ok(PairValueEnum::Decimal(PairDecimal::new(value, 0)))
+        }
+    }
+
     fn cast_data(t : &'_ NativeType, cql_value : &'_ CqlValue) -> Result<PairValueEnum, CommonError> {
         let d = match t {
-            NativeType::Int | NativeType::TinyInt => Self::cast_cql_val_to_comm_int_value(t,cql_value),
+            NativeType::Int | NativeType::SmallInt | NativeType::TinyInt => Self::cast_cql_val_to_comm_int_value(t,cql_value),
             NativeType::BigInt => Self::cast_cql_val_to_comm_bigint_value(cql_value),
             NativeType::Boolean => Self::cast_cql_val_to_comm_bool_value(cql_value),
             NativeType::Blob => Self::cast_cql_val_to_comm_blob_value(cql_value),
-            NativeType::Text => Self::cast_cql_val_to_comm_text_value(cql_value),
+            NativeType::Text | NativeType::Ascii => Self::cast_cql_val_to_comm_text_value(cql_value),
             NativeType::Float => Self::cast_cql_val_to_comm_float_value(cql_value),
             NativeType::Double => Self::cast_cql_val_to_comm_double_value(cql_value),
+            NativeType::Uuid | NativeType::Timeuuid => Self::cast_cql_val_to_comm_uuid_value(cql_value),
+            NativeType::Inet => Self::cast_cql_val_to_comm_inet_value(cql_value),
+            NativeType::Timestamp => Self::cast_cql_val_to_comm_timestamp_value(cql_value)?,
+            NativeType::Date => Self::cast_cql_val_to_comm_date_value(cql_value)?,
+            NativeType::Time => Self::cast_cql_val_to_comm_time_value(cql_value)?,
+            NativeType::Decimal => Self::cast_cql_val_to_comm_decimal_value(cql_value)?,
 
             _ => return CommonError::new(&CommonDefaultErrorKind::ParsingFail,
                                          format!("copy_response_data - can't cast data type:{:?}", t)).to_result()
@@ -139,7 +265,7 @@ impl DeserializeRow<'_,'_> for ScyllaPairFetcherRow {
     fn deserialize(row: scylla::deserialize::row::ColumnIterator<'_, '_>) -> Result<Self, scylla::deserialize::DeserializationError> {
         let mut iter = row.into_iter();
         let mut datas = Vec::with_capacity(10);
-        let mut catch_err : Result<(), Box<dyn std::error::Error>> = Ok(());
+        let mut catch_err : Result<(), Box<dyn std::error::Error + Send + Sync>> = Ok(());
 
         while let Some(rc) = iter.next() {
             let raw_c = rc?;
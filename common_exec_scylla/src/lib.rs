@@ -2,13 +2,17 @@ mod db_conn;
 
 use common_err::CommonError;
 
-use common_core::collection::pool::get_thread_safe_pool;
-use common_pair_exec::{PairExecutor, PairExecutorInfo, PairExecutorPool};
-use db_conn::ScyllaConnection;
+use std::sync::Arc;
+use scylla::client::session::Session;
+use tokio::sync::OnceCell;
+use common_core::collection::pool::{get_async_thread_safe_pool, get_thread_safe_pool_with_policy};
+use common_pair_exec::{AsyncPairExecutor, AsyncPairExecutorPool, PairExecutor, PairExecutorInfo, PairExecutorPool};
+use db_conn::{create_scylla_session, AsyncScyllaConnection, ScyllaConnection};
 use crate::db_conn::ScyllaConnInfo;
 
 pub fn create_scylla_pair_conn_pool(name : String, info : PairExecutorInfo, alloc_size : usize) -> PairExecutorPool {
-    let gen_fn : Box<dyn Fn(()) -> Result<Box<dyn PairExecutor>, CommonError>> = (|info : PairExecutorInfo| {
+    let policy = info.pool.to_pool_policy();
+    let gen_fn : Box<dyn Fn(()) -> Result<Box<dyn PairExecutor + Send>, CommonError> + Send + Sync> = (|info : PairExecutorInfo| {
         let real_fn  = move |_ : ()| {
             let conn_info = ScyllaConnInfo {
                 addr: info.addr.clone(),
@@ -16,11 +20,13 @@ pub fn create_scylla_pair_conn_pool(name : String, info : PairExecutorInfo, allo
                 user: info.user.clone(),
                 password: info.password.clone(),
                 timeout_sec: info.timeout_sec,
+                statement_cache_size: info.statement_cache_size,
+                tls: info.tls.clone(),
             };
             let conn = ScyllaConnection::new(conn_info);
 
             match conn {
-                Ok(ok) => Ok(Box::new(ok) as Box<dyn PairExecutor>),
+                Ok(ok) => Ok(Box::new(ok) as Box<dyn PairExecutor + Send>),
                 Err(err) => {Err(err)}
             }
         };
@@ -28,5 +34,27 @@ pub fn create_scylla_pair_conn_pool(name : String, info : PairExecutorInfo, allo
         Box::new(real_fn)
     })(info);
 
-    get_thread_safe_pool(name, gen_fn, alloc_size)
-}
\ No newline at end of file
+    get_thread_safe_pool_with_policy(name, gen_fn, alloc_size, policy)
+}
+
+pub fn create_scylla_async_pair_conn_pool(name : String, info : PairExecutorInfo, alloc_size : usize) -> AsyncPairExecutorPool {
+    let conn_info = Arc::new(ScyllaConnInfo {
+        addr: info.addr.clone(),
+        name: info.name.clone(),
+        user: info.user.clone(),
+        password: info.password.clone(),
+        timeout_sec: info.timeout_sec,
+        statement_cache_size: info.statement_cache_size,
+        tls: info.tls.clone(),
+    });
+    // connected by the first borrower, a failed connect is retried by the next one
+    let session : Arc<OnceCell<Arc<Session>>> = Arc::new(OnceCell::new());
+
+    get_async_thread_safe_pool(name, Box::new(move |_ : ()| {
+        let (conn_info, session) = (conn_info.clone(), session.clone());
+        Box::pin(async move {
+            let session = session.get_or_try_init(|| create_scylla_session(conn_info.as_ref())).await?;
+            Ok(Box::new(AsyncScyllaConnection::new(session.clone(), conn_info.statement_cache_size)) as Box<dyn AsyncPairExecutor>)
+        })
+    }), alloc_size)
+}
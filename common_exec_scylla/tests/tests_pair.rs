@@ -3,10 +3,10 @@ use toml;
 use common_core::utils::func::generate_random_string;
 use common_err::CommonError;
 use common_err::gen::CommonDefaultErrorKind;
-use common_exec_scylla::create_scylla_pair_conn_pool;
+use common_exec_scylla::{create_scylla_async_pair_conn_pool, create_scylla_pair_conn_pool};
 use common_pair_exec::{PairExecutor, PairExecutorInfo, PairValueEnum};
 
-fn connect_scylla_db() -> Result<common_pair_exec::PairExecutorPool, CommonError> {
+fn read_scylla_info() -> Result<PairExecutorInfo, CommonError> {
     let read_toml : HashMap<String, String> = toml::from_str(include_str!("./tests.asset.toml")).map_err(|e| {
         CommonError::new(&CommonDefaultErrorKind::Etc, e.to_string())
     })?;
@@ -16,14 +16,21 @@ fn connect_scylla_db() -> Result<common_pair_exec::PairExecutorPool, CommonError
         user: read_toml["user"].clone(),
         password: read_toml["password"].clone(),
         timeout_sec: 3600,
-        extend: None
+        statement_cache_size: 64,
+        pool: Default::default(),
+        failover: Default::default(),
+        tls: None,
+        backend: None
     };
+    Ok(info)
+}
 
-    let p = create_scylla_pair_conn_pool("test".to_string(), info, 5);
+fn connect_scylla_db() -> Result<common_pair_exec::PairExecutorPool, CommonError> {
+    let p = create_scylla_pair_conn_pool("test".to_string(), read_scylla_info()?, 5);
     Ok(p)
 }
 
-fn create_large_table(conn : &mut Box<dyn PairExecutor>) -> Result<(), CommonError> {
+fn create_large_table(conn : &mut Box<dyn PairExecutor + Send>) -> Result<(), CommonError> {
     const KEYSPACE : &'static str = "create  keyspace if not exists large_test    WITH REPLICATION = {
       'class' : 'SimpleStrategy'
     }";
@@ -39,7 +46,7 @@ fn create_large_table(conn : &mut Box<dyn PairExecutor>) -> Result<(), CommonErr
     Ok(())
 }
 
-fn insert_large_data(conn : &mut Box<dyn PairExecutor>, count : usize) -> Result<(), CommonError> {
+fn insert_large_data(conn : &mut Box<dyn PairExecutor + Send>, count : usize) -> Result<(), CommonError> {
     for i in 0..count {
         conn.execute_pair("insert into large_test.large(id, name, hash, data) values(?,?,?,?)",
         &PairValueEnum::Array(vec![
@@ -51,7 +58,7 @@ fn insert_large_data(conn : &mut Box<dyn PairExecutor>, count : usize) -> Result
     Ok(())
 }
 
-fn drop_large_table(conn : &mut Box<dyn PairExecutor>) -> Result<(), CommonError> {
+fn drop_large_table(conn : &mut Box<dyn PairExecutor + Send>) -> Result<(), CommonError> {
     const KEYSPACE : &'static str = "drop keyspace if exists large_test";
 
     const TABLE : &'static str = "drop table if exists large_test.large";
@@ -135,6 +142,31 @@ fn test_connect_insert() -> Result<(), CommonError> {
     Ok(())
 }
 
+#[test]
+fn test_connect_insert_batch() -> Result<(), CommonError> {
+    let p = connect_scylla_db()?;
+    let mut item = p.get_owned(())?;
+    let conn = item.get_value();
+
+    create_large_table(conn)?;
+
+    // more rows than one unlogged batch chunk holds
+    let rows : Vec<PairValueEnum> = (0..1050).map(|i| PairValueEnum::Array(vec![
+        PairValueEnum::BigInt(i), PairValueEnum::String("hello".to_string()),
+        PairValueEnum::String(generate_random_string(32)),
+        PairValueEnum::String(generate_random_string(100))
+    ])).collect();
+    let counts = conn.execute_batch("insert into large_test.large(id, name, hash, data) values(?,?,?,?)", rows.as_slice());
+    let loaded = conn.execute_pair_result_set("select count(*) as cnt from large_test.large", &PairValueEnum::Null);
+
+    drop_large_table(conn)?;
+
+    assert_eq!(counts?.len(), 1050);
+    assert_eq!(loaded?.get_by_name(0, "cnt"), Some(&PairValueEnum::BigInt(1050)));
+
+    Ok(())
+}
+
 #[test]
 fn test_connect_select_large() -> Result<(), CommonError> {
     let p = connect_scylla_db()?;
@@ -182,4 +214,20 @@ fn test_connect_select_large() -> Result<(), CommonError> {
     drop_large_table(conn)?;
 
     Ok(())
-}
\ No newline at end of file
+}
+
+#[tokio::test]
+async fn test_connect_async_shared_session() -> Result<(), CommonError> {
+    let p = create_scylla_async_pair_conn_pool("test".to_string(), read_scylla_info()?, 2);
+    let mut first = p.get_owned(()).await?;
+    let mut second = p.get_owned(()).await?;
+
+    let now = first.get_value().get_current_time().await?;
+    let ret = second.get_value().execute_pair_result_set("select release_version from system.local", &PairValueEnum::Null).await?;
+
+    assert!(now.as_secs() > 0);
+    assert_eq!(ret.row_count(), 1);
+    assert_eq!(p.alloc_size(), 2);
+
+    Ok(())
+}
@@ -1,4 +1,5 @@
 use std::sync::Arc;
+use common_core::collection::pool::PoolStats;
 use common_err::CommonError;
 use crate::console_logger::ConsoleLogger;
 use crate::file_logger::FileLogger;
@@ -41,4 +42,28 @@ pub fn new_logger(config: LoggerConfig) -> Result<Arc<dyn Logger>, CommonError>
             Ok(Arc::new(ConsoleLogger::new()?))
         }
     }
-}
\ No newline at end of file
+}
+
+// one trace line per gauge and counter, keys are prefixed with the pool name
+pub fn trace_pool_stats(logger : &'_ dyn Logger, name : &'_ str, pool_name : &'_ str, stats : &PoolStats) {
+    let values = [
+        ("idle", stats.idle as f64),
+        ("in_use", stats.in_use as f64),
+        ("waiting", stats.waiting as f64),
+        ("max_size", stats.max_size as f64),
+        ("created", stats.created as f64),
+        ("create_failed", stats.create_failed as f64),
+        ("checkouts", stats.checkouts as f64),
+        ("returns", stats.returns as f64),
+        ("disposed", stats.disposed as f64),
+        ("wait_timeouts", stats.wait_timeouts as f64),
+        ("wait_mean_ms", stats.wait.mean().as_secs_f64() * 1000.0),
+        ("wait_max_ms", stats.wait.max.as_secs_f64() * 1000.0),
+        ("hold_mean_ms", stats.hold.mean().as_secs_f64() * 1000.0),
+        ("hold_max_ms", stats.hold.max.as_secs_f64() * 1000.0)
+    ];
+
+    for (key, value) in values {
+        logger.trace(name, format!("{}.{}", pool_name, key).as_str(), value);
+    }
+}
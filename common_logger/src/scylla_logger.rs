@@ -3,7 +3,7 @@ use std::sync::Mutex;
 use common_core::core_err_log;
 use common_err::CommonError;
 use common_err::gen::CommonDefaultErrorKind;
-use common_pair_exec::{PairExecutorInfo, PairExecutorPool, PairValueEnum};
+use common_pair_exec::{PairExecutor, PairExecutorInfo, PairExecutorPool, PairValueEnum, PooledPairExecutor};
 use crate::LogLevel;
 
 const LOG_TABLE : &'static str = "create table if not exists comm_log (
@@ -41,7 +41,11 @@ impl ScyllaLogger {
             user,
             password: passwd,
             timeout_sec: 1,
-            extend: None,
+            statement_cache_size: 16,
+            pool: Default::default(),
+            failover: Default::default(),
+            tls: None,
+            backend: None,
         }, 10);
 
         let mut get_ret = p.get_owned(()).map_err(|e| {
@@ -80,26 +84,23 @@ impl crate::Logger for ScyllaLogger {
             return;
         }
 
-        let conn_item_ret = self.pool.get_owned(()).map_err(|e| {
+        let conn_ret = PooledPairExecutor::from_pool(&self.pool).map_err(|e| {
             CommonError::extend(&CommonDefaultErrorKind::ConnectFail, "", e)
         });
 
-        if conn_item_ret.is_err() {
-            core_err_log!("{}", conn_item_ret.err().unwrap().to_string());
+        if conn_ret.is_err() {
+            core_err_log!("{}", conn_ret.err().unwrap().to_string());
             return;
         }
 
-        let mut conn_item = conn_item_ret.unwrap();
-        let conn = conn_item.get_value();
+        // a broken connection is disposed when conn is dropped
+        let mut conn = conn_ret.unwrap();
 
         let ret = conn.execute_pair(self.insert_query.as_str(),
                                     &self.create_log_param(name.to_string(), func.to_string(), file.to_string(), "DEBUG", message.to_string()));
 
         if ret.is_err() {
             core_err_log!("{}", ret.err().unwrap().to_string());
-            conn_item.dispose();
-        } else {
-            conn_item.restoration();
         }
     }
 
@@ -108,26 +109,22 @@ impl crate::Logger for ScyllaLogger {
             return;
         }
 
-        let conn_item_ret = self.pool.get_owned(()).map_err(|e| {
+        let conn_ret = PooledPairExecutor::from_pool(&self.pool).map_err(|e| {
             CommonError::extend(&CommonDefaultErrorKind::ConnectFail, "", e)
         });
 
-        if conn_item_ret.is_err() {
-            core_err_log!("{}", conn_item_ret.err().unwrap().to_string());
+        if conn_ret.is_err() {
+            core_err_log!("{}", conn_ret.err().unwrap().to_string());
             return;
         }
 
-        let mut conn_item = conn_item_ret.unwrap();
-        let conn = conn_item.get_value();
+        let mut conn = conn_ret.unwrap();
 
         let ret = conn.execute_pair(self.insert_query.as_str(),
                                     &self.create_log_param(name.to_string(), func.to_string(), file.to_string(), "INFO", message.to_string()));
 
         if ret.is_err() {
             core_err_log!("{}", ret.err().unwrap().to_string());
-            conn_item.dispose();
-        } else {
-            conn_item.restoration();
         }
     }
 
@@ -136,26 +133,22 @@ impl crate::Logger for ScyllaLogger {
             return;
         }
 
-        let conn_item_ret = self.pool.get_owned(()).map_err(|e| {
+        let conn_ret = PooledPairExecutor::from_pool(&self.pool).map_err(|e| {
             CommonError::extend(&CommonDefaultErrorKind::ConnectFail, "", e)
         });
 
-        if conn_item_ret.is_err() {
-            core_err_log!("{}", conn_item_ret.err().unwrap().to_string());
+        if conn_ret.is_err() {
+            core_err_log!("{}", conn_ret.err().unwrap().to_string());
             return;
         }
 
-        let mut conn_item = conn_item_ret.unwrap();
-        let conn = conn_item.get_value();
+        let mut conn = conn_ret.unwrap();
 
         let ret = conn.execute_pair(self.insert_query.as_str(),
                                     &self.create_log_param(name.to_string(), func.to_string(), file.to_string(), "ERROR", message.to_string()));
 
         if ret.is_err() {
             core_err_log!("{}", ret.err().unwrap().to_string());
-            conn_item.dispose();
-        } else {
-            conn_item.restoration();
         }
     }
 
@@ -164,17 +157,16 @@ impl crate::Logger for ScyllaLogger {
             return;
         }
 
-        let conn_item_ret = self.pool.get_owned(()).map_err(|e| {
+        let conn_ret = PooledPairExecutor::from_pool(&self.pool).map_err(|e| {
             CommonError::extend(&CommonDefaultErrorKind::ConnectFail, "", e)
         });
 
-        if conn_item_ret.is_err() {
-            core_err_log!("{}", conn_item_ret.err().unwrap().to_string());
+        if conn_ret.is_err() {
+            core_err_log!("{}", conn_ret.err().unwrap().to_string());
             return;
         }
 
-        let mut conn_item = conn_item_ret.unwrap();
-        let conn = conn_item.get_value();
+        let mut conn = conn_ret.unwrap();
 
         let ret = conn.execute_pair(self.insert_trace_query.as_str(), &PairValueEnum::Array(
             vec![
@@ -187,9 +179,6 @@ impl crate::Logger for ScyllaLogger {
 
         if ret.is_err() {
             core_err_log!("{}", ret.err().unwrap().to_string());
-            conn_item.dispose();
-        } else {
-            conn_item.restoration();
         }
     }
 }
\ No newline at end of file
@@ -0,0 +1,197 @@
+// generated code refers to ::common_pair_exec and ::common_err, the deriving crate depends on both
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{parse_macro_input, Attribute, Data, DeriveInput, Fields, Ident, LitStr};
+
+#[derive(Default)]
+struct PairAttr {
+    rename : Option<String>,
+    skip : bool
+}
+
+fn parse_pair_attr(attrs : &'_ [Attribute]) -> syn::Result<PairAttr> {
+    let mut ret = PairAttr::default();
+
+    for attr in attrs.iter().filter(|a| a.path().is_ident("pair")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("rename") {
+                let lit : LitStr = meta.value()?.parse()?;
+                ret.rename = Some(lit.value());
+                Ok(())
+            } else if meta.path.is_ident("skip") {
+                ret.skip = true;
+                Ok(())
+            } else {
+                Err(meta.error("unknown pair attribute, expected `rename` or `skip`"))
+            }
+        })?;
+    }
+
+    Ok(ret)
+}
+
+struct PairField {
+    ident : Ident,
+    name : String,
+    skip : bool
+}
+
+fn named_fields(input : &'_ DeriveInput, derive : &'_ str) -> syn::Result<Vec<PairField>> {
+    let Data::Struct(data) = &input.data else {
+        return Err(syn::Error::new_spanned(&input.ident, format!("{} supports structs only", derive)));
+    };
+    let Fields::Named(fields) = &data.fields else {
+        return Err(syn::Error::new_spanned(&input.ident, format!("{} supports named fields only", derive)));
+    };
+
+    fields.named.iter().map(|f| {
+        let attr = parse_pair_attr(f.attrs.as_slice())?;
+        let ident = f.ident.clone().unwrap();
+        let name = attr.rename.unwrap_or_else(|| ident.to_string());
+        Ok(PairField { ident, name, skip : attr.skip })
+    }).collect()
+}
+
+fn unit_variants(input : &'_ DeriveInput, derive : &'_ str) -> syn::Result<Vec<(Ident, String)>> {
+    let Data::Enum(data) = &input.data else {
+        return Err(syn::Error::new_spanned(&input.ident, format!("{} supports fieldless enums only", derive)));
+    };
+
+    data.variants.iter().map(|v| {
+        if !matches!(v.fields, Fields::Unit) {
+            return Err(syn::Error::new_spanned(&v.ident, format!("{} supports fieldless enums only", derive)));
+        }
+        let attr = parse_pair_attr(v.attrs.as_slice())?;
+        Ok((v.ident.clone(), attr.rename.unwrap_or_else(|| v.ident.to_string())))
+    }).collect()
+}
+
+fn expand_from_pair_row(input : &'_ DeriveInput) -> syn::Result<TokenStream2> {
+    let fields = named_fields(input, "FromPairRow")?;
+    let ident = &input.ident;
+    let type_name = ident.to_string();
+    let (impl_g, ty_g, where_g) = input.generics.split_for_impl();
+
+    let inits = fields.iter().map(|f| {
+        let field = &f.ident;
+        let name = f.name.as_str();
+        if f.skip {
+            quote! { #field : ::std::default::Default::default() }
+        } else {
+            quote! { #field : ::common_pair_exec::get_pair_row_column(#type_name, columns, row, #name)? }
+        }
+    });
+
+    Ok(quote! {
+        impl #impl_g ::common_pair_exec::FromPairRow for #ident #ty_g #where_g {
+            fn from_pair_row(columns : &[::common_pair_exec::PairColumnInfo], row : &[::common_pair_exec::PairValueEnum])
+                -> ::std::result::Result<Self, ::common_err::CommonError> {
+                ::std::result::Result::Ok(#ident { #(#inits),* })
+            }
+        }
+    })
+}
+
+fn expand_to_pair_params(input : &'_ DeriveInput) -> syn::Result<TokenStream2> {
+    let fields = named_fields(input, "ToPairParams")?;
+    let ident = &input.ident;
+    let (impl_g, ty_g, where_g) = input.generics.split_for_impl();
+
+    let values = fields.iter().filter(|f| !f.skip).map(|f| {
+        let field = &f.ident;
+        quote! { ::common_pair_exec::ToPairValue::to_pair_value(&self.#field) }
+    });
+    let named = fields.iter().filter(|f| !f.skip).map(|f| {
+        let field = &f.ident;
+        let name = f.name.as_str();
+        quote! {
+            map.insert(::std::string::ToString::to_string(#name), ::common_pair_exec::ToPairValue::to_pair_value(&self.#field));
+        }
+    });
+
+    Ok(quote! {
+        impl #impl_g ::common_pair_exec::ToPairParams for #ident #ty_g #where_g {
+            fn to_pair_params(&self) -> ::common_pair_exec::PairValueEnum {
+                ::common_pair_exec::PairValueEnum::Array(::std::vec![#(#values),*])
+            }
+
+            fn to_pair_named_params(&self) -> ::common_pair_exec::PairValueEnum {
+                #[allow(unused_mut)]
+                let mut map = ::std::collections::HashMap::new();
+                #(#named)*
+                ::common_pair_exec::PairValueEnum::Map(map)
+            }
+        }
+    })
+}
+
+fn expand_from_pair_value(input : &'_ DeriveInput) -> syn::Result<TokenStream2> {
+    let variants = unit_variants(input, "FromPairValue")?;
+    let ident = &input.ident;
+    let type_name = ident.to_string();
+
+    let arms = variants.iter().map(|(v, name)| {
+        quote! { #name => ::std::result::Result::Ok(#ident::#v) }
+    });
+
+    Ok(quote! {
+        impl ::common_pair_exec::FromPairValue for #ident {
+            fn from_pair_value(value : &::common_pair_exec::PairValueEnum) -> ::std::result::Result<Self, ::common_err::CommonError> {
+                let text = <::std::string::String as ::common_pair_exec::FromPairValue>::from_pair_value(value)?;
+                match text.as_str() {
+                    #(#arms,)*
+                    _ => ::common_pair_exec::unknown_pair_variant(#type_name, text.as_str())
+                }
+            }
+        }
+    })
+}
+
+fn expand_to_pair_value(input : &'_ DeriveInput) -> syn::Result<TokenStream2> {
+    let variants = unit_variants(input, "ToPairValue")?;
+    let ident = &input.ident;
+
+    let arms = variants.iter().map(|(v, name)| {
+        quote! { #ident::#v => #name }
+    });
+
+    Ok(quote! {
+        impl ::common_pair_exec::ToPairValue for #ident {
+            fn to_pair_value(&self) -> ::common_pair_exec::PairValueEnum {
+                let text = match self {
+                    #(#arms),*
+                };
+                ::common_pair_exec::PairValueEnum::String(::std::string::ToString::to_string(text))
+            }
+        }
+    })
+}
+
+fn into_stream(ret : syn::Result<TokenStream2>) -> TokenStream {
+    ret.unwrap_or_else(|e| e.to_compile_error()).into()
+}
+
+#[proc_macro_derive(FromPairRow, attributes(pair))]
+pub fn derive_from_pair_row(input : TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    into_stream(expand_from_pair_row(&input))
+}
+
+#[proc_macro_derive(ToPairParams, attributes(pair))]
+pub fn derive_to_pair_params(input : TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    into_stream(expand_to_pair_params(&input))
+}
+
+#[proc_macro_derive(FromPairValue, attributes(pair))]
+pub fn derive_from_pair_value(input : TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    into_stream(expand_from_pair_value(&input))
+}
+
+#[proc_macro_derive(ToPairValue, attributes(pair))]
+pub fn derive_to_pair_value(input : TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    into_stream(expand_to_pair_value(&input))
+}
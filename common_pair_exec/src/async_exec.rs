@@ -0,0 +1,140 @@
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use async_trait::async_trait;
+use common_core::collection::pool::{get_async_thread_safe_pool, AsyncThreadSafePool, PoolItem};
+use common_err::{CommonError, gen::CommonDefaultErrorKind};
+use crate::{PairExecuteOutcome, PairExecutor, PairResultSet, PairStatementCacheStats, PairValueEnum};
+
+#[async_trait]
+pub trait AsyncPairExecutor : Send {
+    async fn execute_pair(&mut self, query : &'_ str, param : &PairValueEnum) -> Result<PairValueEnum, CommonError> {
+        Ok(self.execute_pair_result_set(query, param).await?.into_pair_map())
+    }
+    async fn execute_pair_result_set(&mut self, query : &'_ str, param : &PairValueEnum) -> Result<PairResultSet, CommonError>;
+    async fn execute_pair_outcome(&mut self, query : &'_ str, param : &PairValueEnum) -> Result<PairExecuteOutcome, CommonError> {
+        let result_set = self.execute_pair_result_set(query, param).await?;
+        Ok(PairExecuteOutcome::new(None, result_set))
+    }
+    async fn get_current_time(&mut self) -> Result<Duration, CommonError>;
+
+    async fn ping(&mut self) -> Result<(), CommonError> {
+        self.get_current_time().await.map(|_| ())
+    }
+
+    async fn execute_batch(&mut self, _query : &'_ str, _rows : &'_ [PairValueEnum]) -> Result<Vec<u64>, CommonError> {
+        CommonError::new(&CommonDefaultErrorKind::NoSupport, "execute batch").to_result()
+    }
+
+    fn statement_cache_stats(&self) -> Option<PairStatementCacheStats> {
+        None
+    }
+
+    async fn begin(&mut self) -> Result<(), CommonError> {
+        CommonError::new(&CommonDefaultErrorKind::NoSupport, "begin transaction").to_result()
+    }
+    async fn commit(&mut self) -> Result<(), CommonError> {
+        CommonError::new(&CommonDefaultErrorKind::NoSupport, "commit transaction").to_result()
+    }
+    async fn rollback(&mut self) -> Result<(), CommonError> {
+        CommonError::new(&CommonDefaultErrorKind::NoSupport, "rollback transaction").to_result()
+    }
+}
+
+pub type AsyncPairExecutorBox = Box<dyn PoolItem<Box<dyn AsyncPairExecutor>> + Send>;
+pub type AsyncPairExecutorPool = Arc<dyn AsyncThreadSafePool<Box<dyn AsyncPairExecutor>, ()>>;
+
+// runs a sync executor (duckdb, odbc) on the tokio blocking thread pool
+pub struct BlockingPairExecutor {
+    // shared with the blocking task, a call whose future is dropped leaves the executor for the next one
+    inner : Arc<Mutex<Box<dyn PairExecutor + Send>>>,
+    cache_stats : Option<PairStatementCacheStats>
+}
+
+impl BlockingPairExecutor {
+    pub fn new(executor : Box<dyn PairExecutor + Send>) -> Self {
+        let cache_stats = executor.statement_cache_stats();
+        BlockingPairExecutor { inner : Arc::new(Mutex::new(executor)), cache_stats }
+    }
+
+    async fn run<R, F>(&mut self, f : F) -> Result<R, CommonError>
+    where R : Send + 'static, F : FnOnce(&mut dyn PairExecutor) -> Result<R, CommonError> + Send + 'static {
+        let inner = self.inner.clone();
+
+        let (cache_stats, ret) = tokio::task::spawn_blocking(move || {
+            let mut g = inner.lock().map_err(|_| {
+                CommonError::new(&CommonDefaultErrorKind::InvalidApiCall, "BlockingPairExecutor - executor lost by a panicked call")
+            })?;
+            let ret = f(g.as_mut());
+            Ok::<_, CommonError>((g.statement_cache_stats(), ret))
+        }).await.map_err(|e| {
+            CommonError::new(&CommonDefaultErrorKind::ExecuteFail, format!("BlockingPairExecutor - spawn_blocking - {}", e))
+        })??;
+
+        self.cache_stats = cache_stats;
+        ret
+    }
+}
+
+#[async_trait]
+impl AsyncPairExecutor for BlockingPairExecutor {
+    async fn execute_pair(&mut self, query : &'_ str, param : &PairValueEnum) -> Result<PairValueEnum, CommonError> {
+        let (query, param) = (query.to_string(), param.clone());
+        self.run(move |e| e.execute_pair(query.as_str(), &param)).await
+    }
+
+    async fn execute_pair_result_set(&mut self, query : &'_ str, param : &PairValueEnum) -> Result<PairResultSet, CommonError> {
+        let (query, param) = (query.to_string(), param.clone());
+        self.run(move |e| e.execute_pair_result_set(query.as_str(), &param)).await
+    }
+
+    async fn execute_pair_outcome(&mut self, query : &'_ str, param : &PairValueEnum) -> Result<PairExecuteOutcome, CommonError> {
+        let (query, param) = (query.to_string(), param.clone());
+        self.run(move |e| e.execute_pair_outcome(query.as_str(), &param)).await
+    }
+
+    async fn get_current_time(&mut self) -> Result<Duration, CommonError> {
+        self.run(|e| e.get_current_time()).await
+    }
+
+    async fn ping(&mut self) -> Result<(), CommonError> {
+        self.run(|e| e.ping()).await
+    }
+
+    async fn execute_batch(&mut self, query : &'_ str, rows : &'_ [PairValueEnum]) -> Result<Vec<u64>, CommonError> {
+        let (query, rows) = (query.to_string(), rows.to_vec());
+        self.run(move |e| e.execute_batch(query.as_str(), rows.as_slice())).await
+    }
+
+    fn statement_cache_stats(&self) -> Option<PairStatementCacheStats> {
+        self.cache_stats
+    }
+
+    async fn begin(&mut self) -> Result<(), CommonError> {
+        self.run(|e| e.begin()).await
+    }
+
+    async fn commit(&mut self) -> Result<(), CommonError> {
+        self.run(|e| e.commit()).await
+    }
+
+    async fn rollback(&mut self) -> Result<(), CommonError> {
+        self.run(|e| e.rollback()).await
+    }
+}
+
+// async pool over a sync generator, connecting also happens on the blocking thread pool
+pub fn create_blocking_async_pair_pool(name : String, gen : Box<dyn Fn(()) -> Result<Box<dyn PairExecutor + Send>, CommonError> + Send + Sync>,
+                                       alloc_size : usize) -> AsyncPairExecutorPool {
+    let gen = Arc::new(gen);
+
+    get_async_thread_safe_pool(name, Box::new(move |_ : ()| {
+        let gen = gen.clone();
+        Box::pin(async move {
+            let conn = tokio::task::spawn_blocking(move || gen(())).await.map_err(|e| {
+                CommonError::new(&CommonDefaultErrorKind::ConnectFail, format!("create_blocking_async_pair_pool - spawn_blocking - {}", e))
+            })??;
+
+            Ok(Box::new(BlockingPairExecutor::new(conn)) as Box<dyn AsyncPairExecutor>)
+        })
+    }), alloc_size)
+}
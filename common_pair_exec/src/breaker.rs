@@ -0,0 +1,166 @@
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use common_core::collection::observer::Subject;
+use common_err::CommonError;
+use common_err::gen::CommonDefaultErrorKind;
+use crate::{is_connection_error, PairExecutor, PairExecutorPool, PooledPairExecutor};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PairCircuitState {
+    Closed,
+    Open,
+    HalfOpen
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PairCircuitOptions {
+    // consecutive connection failures that open the circuit
+    pub failure_threshold : u32,
+    // calls fail fast for this long before a probe is let through
+    pub cool_down : Duration,
+    // concurrent probes while half open, the rest still fail fast
+    pub half_open_probes : u32
+}
+
+impl Default for PairCircuitOptions {
+    fn default() -> Self {
+        PairCircuitOptions { failure_threshold : 5, cool_down : Duration::from_secs(30), half_open_probes : 1 }
+    }
+}
+
+struct CircuitInner {
+    state : PairCircuitState,
+    failures : u32,
+    opened_at : Instant,
+    probes : u32
+}
+
+// gives the half open slot back on drop, also when the call panics
+struct ProbeSlot<'a> {
+    inner : &'a Mutex<CircuitInner>,
+    probe : bool
+}
+
+impl Drop for ProbeSlot<'_> {
+    fn drop(&mut self) {
+        if self.probe {
+            if let Ok(mut inner) = self.inner.lock() {
+                inner.probes = inner.probes.saturating_sub(1);
+            }
+        }
+    }
+}
+
+// only connection failures count, an error the server answered with proves it is up
+pub struct PairCircuitBreaker {
+    name : String,
+    pool : PairExecutorPool,
+    options : PairCircuitOptions,
+    inner : Mutex<CircuitInner>,
+    subject : Arc<Subject<PairCircuitState>>
+}
+
+impl PairCircuitBreaker {
+    pub fn new(name : String, pool : PairExecutorPool, options : PairCircuitOptions) -> Self {
+        let inner = CircuitInner { state : PairCircuitState::Closed, failures : 0, opened_at : Instant::now(), probes : 0 };
+        PairCircuitBreaker { name, pool, options, inner : Mutex::new(inner), subject : Subject::new_arc() }
+    }
+
+    pub fn state(&self) -> PairCircuitState {
+        self.inner.lock().unwrap().state
+    }
+
+    pub fn options(&self) -> PairCircuitOptions {
+        self.options
+    }
+
+    // subscribe with ThreadSafeObserver, every state change is published
+    pub fn subject(&self) -> Arc<Subject<PairCircuitState>> {
+        self.subject.clone()
+    }
+
+    fn transition(&self, inner : &'_ mut CircuitInner, state : PairCircuitState) {
+        if inner.state == state {
+            return;
+        }
+        inner.state = state;
+        match state {
+            PairCircuitState::Open => inner.opened_at = Instant::now(),
+            PairCircuitState::Closed => inner.failures = 0,
+            PairCircuitState::HalfOpen => {}
+        }
+        self.subject.notify(state);
+    }
+
+    // the slot is a half open probe when its flag is set
+    fn acquire(&self) -> Result<ProbeSlot<'_>, CommonError> {
+        let mut inner = self.inner.lock().unwrap();
+
+        if inner.state == PairCircuitState::Open {
+            let elapsed = inner.opened_at.elapsed();
+            if elapsed < self.options.cool_down {
+                return CommonError::new(&CommonDefaultErrorKind::CircuitOpen,
+                                        format!("breaker:{} - open, retry in {:?}", self.name, self.options.cool_down - elapsed)).to_result();
+            }
+            self.transition(&mut inner, PairCircuitState::HalfOpen);
+        }
+
+        if inner.state == PairCircuitState::HalfOpen {
+            if inner.probes >= self.options.half_open_probes.max(1) {
+                return CommonError::new(&CommonDefaultErrorKind::CircuitOpen,
+                                        format!("breaker:{} - half open, probe in flight", self.name)).to_result();
+            }
+            inner.probes += 1;
+            return Ok(ProbeSlot { inner : &self.inner, probe : true });
+        }
+        Ok(ProbeSlot { inner : &self.inner, probe : false })
+    }
+
+    fn record<T>(&self, probe : bool, ret : &'_ Result<T, CommonError>) {
+        let mut inner = self.inner.lock().unwrap();
+
+        match ret {
+            Ok(_) => {
+                inner.failures = 0;
+                if probe && inner.state == PairCircuitState::HalfOpen {
+                    self.transition(&mut inner, PairCircuitState::Closed);
+                }
+            },
+            Err(e) if is_connection_error(e) => {
+                inner.failures += 1;
+                let open = match inner.state {
+                    PairCircuitState::Closed => inner.failures >= self.options.failure_threshold,
+                    PairCircuitState::HalfOpen => probe,
+                    // a call started before the circuit opened
+                    PairCircuitState::Open => false
+                };
+                if open {
+                    self.transition(&mut inner, PairCircuitState::Open);
+                }
+            },
+            Err(_) => {
+                // the server answered the probe, so it is up
+                if probe && inner.state == PairCircuitState::HalfOpen {
+                    self.transition(&mut inner, PairCircuitState::Closed);
+                }
+            }
+        }
+    }
+
+    // counts only the checkout, the backend connect happens inside the pool generator
+    pub fn get_executor(&self) -> Result<PooledPairExecutor, CommonError> {
+        let slot = self.acquire()?;
+        let ret = PooledPairExecutor::from_pool(&self.pool);
+        self.record(slot.probe, &ret);
+        ret
+    }
+
+    // counts the checkout and the call
+    pub fn call<T, F>(&self, f : F) -> Result<T, CommonError>
+    where F : FnOnce(&mut dyn PairExecutor) -> Result<T, CommonError> {
+        let slot = self.acquire()?;
+        let ret = PooledPairExecutor::from_pool(&self.pool).and_then(|mut conn| f(&mut conn));
+        self.record(slot.probe, &ret);
+        ret
+    }
+}
@@ -0,0 +1,195 @@
+use std::collections::BTreeMap;
+use std::sync::{Arc, Condvar, Mutex, OnceLock};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+use common_err::CommonError;
+use common_err::gen::CommonDefaultErrorKind;
+
+type Canceller = Box<dyn Fn() + Send>;
+
+// one running call, fired by the handle or the watchdog
+#[derive(Default)]
+struct CallState {
+    cancelled : AtomicBool,
+    timed_out : AtomicBool,
+    canceller : Mutex<Option<Canceller>>
+}
+
+impl CallState {
+    // runs under the lock, so the canceller never outlives the guard that registered it
+    fn fire(&self, flag : &'_ AtomicBool) {
+        flag.store(true, Ordering::SeqCst);
+        if let Some(canceller) = self.canceller.lock().unwrap().as_ref() {
+            canceller();
+        }
+    }
+}
+
+#[derive(Default)]
+struct CancelState {
+    cancelled : AtomicBool,
+    calls : Mutex<Vec<Arc<CallState>>>
+}
+
+// shared with the thread that may cancel, one handle can cover several calls
+#[derive(Clone, Default)]
+pub struct PairCancelHandle {
+    state : Arc<CancelState>
+}
+
+impl PairCancelHandle {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // aborts the running calls, calls started afterwards fail at once
+    pub fn cancel(&self) {
+        self.state.cancelled.store(true, Ordering::SeqCst);
+        for call in self.state.calls.lock().unwrap().iter() {
+            call.fire(&call.cancelled);
+        }
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.state.cancelled.load(Ordering::SeqCst)
+    }
+}
+
+struct Watchdog {
+    deadlines : Mutex<BTreeMap<(Instant, u64), Arc<CallState>>>,
+    cond : Condvar,
+    seq : AtomicU64
+}
+
+// a single thread for every deadline, cancellers run on their own thread as pg cancels over the network
+fn watchdog() -> &'static Watchdog {
+    static WATCHDOG : OnceLock<Watchdog> = OnceLock::new();
+
+    WATCHDOG.get_or_init(|| {
+        std::thread::Builder::new().name("pair-deadline".to_string()).spawn(|| {
+            let w = watchdog();
+            let mut deadlines = w.deadlines.lock().unwrap();
+            loop {
+                let now = Instant::now();
+                deadlines = match deadlines.first_key_value().map(|(k, _)| k.0) {
+                    None => w.cond.wait(deadlines).unwrap(),
+                    Some(at) if at > now => w.cond.wait_timeout(deadlines, at - now).unwrap().0,
+                    Some(_) => {
+                        let (_, call) = deadlines.pop_first().unwrap();
+                        std::thread::spawn(move || call.fire(&call.timed_out));
+                        deadlines
+                    }
+                };
+            }
+        }).unwrap();
+
+        Watchdog { deadlines : Mutex::new(BTreeMap::new()), cond : Condvar::new(), seq : AtomicU64::new(0) }
+    })
+}
+
+#[derive(Clone, Default)]
+pub struct PairCallOptions {
+    pub deadline : Option<Instant>,
+    pub cancel : Option<PairCancelHandle>
+}
+
+impl PairCallOptions {
+    pub fn with_timeout(timeout : Duration) -> Self {
+        PairCallOptions { deadline : Some(Instant::now() + timeout), cancel : None }
+    }
+
+    pub fn with_cancel(mut self, cancel : PairCancelHandle) -> Self {
+        self.cancel = Some(cancel);
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.deadline.is_none() && self.cancel.is_none()
+    }
+
+    pub fn remaining(&self) -> Option<Duration> {
+        self.deadline.map(|d| d.saturating_duration_since(Instant::now()))
+    }
+
+    // arms the deadline, the backend then registers how to abort its call
+    pub fn start(&self) -> Result<PairCallGuard, CommonError> {
+        if self.cancel.as_ref().is_some_and(|c| c.is_cancelled()) {
+            return CommonError::new(&CommonDefaultErrorKind::Cancelled, "PairCallOptions - start - cancelled before start").to_result();
+        }
+        if self.remaining() == Some(Duration::ZERO) {
+            return CommonError::new(&CommonDefaultErrorKind::Timeout, "PairCallOptions - start - deadline passed before start").to_result();
+        }
+
+        let call = Arc::new(CallState::default());
+
+        if let Some(cancel) = &self.cancel {
+            cancel.state.calls.lock().unwrap().push(call.clone());
+        }
+
+        let key = self.deadline.map(|deadline| {
+            let w = watchdog();
+            let key = (deadline, w.seq.fetch_add(1, Ordering::Relaxed));
+            let mut deadlines = w.deadlines.lock().unwrap();
+            deadlines.insert(key, call.clone());
+            if deadlines.first_key_value().is_some_and(|(k, _)| *k == key) {
+                w.cond.notify_one();
+            }
+            key
+        });
+
+        Ok(PairCallGuard { call, cancel : self.cancel.clone(), key, deadline : self.deadline })
+    }
+}
+
+pub struct PairCallGuard {
+    call : Arc<CallState>,
+    cancel : Option<PairCancelHandle>,
+    key : Option<(Instant, u64)>,
+    deadline : Option<Instant>
+}
+
+impl PairCallGuard {
+    // a driver side timeout may answer before the watchdog fires
+    fn aborted_kind(&self) -> Option<&'static CommonDefaultErrorKind> {
+        if self.call.timed_out.load(Ordering::SeqCst) || self.deadline.is_some_and(|d| Instant::now() >= d) {
+            Some(&CommonDefaultErrorKind::Timeout)
+        } else if self.call.cancelled.load(Ordering::SeqCst) {
+            Some(&CommonDefaultErrorKind::Cancelled)
+        } else {
+            None
+        }
+    }
+
+    // fails when the call was aborted before the canceller was in place
+    pub fn set_canceller<F>(&self, canceller : F) -> Result<(), CommonError>
+    where F : Fn() + Send + 'static {
+        *self.call.canceller.lock().unwrap() = Some(Box::new(canceller));
+
+        match self.aborted_kind() {
+            Some(kind) => CommonError::new(kind, "PairCallGuard - set_canceller - aborted before start").to_result(),
+            None => Ok(())
+        }
+    }
+
+    // unregisters the canceller, must run before the handle it captured is freed
+    pub fn finish<T>(self, ret : Result<T, CommonError>) -> Result<T, CommonError> {
+        *self.call.canceller.lock().unwrap() = None;
+        ret.map_err(|e| match self.aborted_kind() {
+            Some(kind) => CommonError::extend(kind, "PairCallGuard - finish - aborted", e),
+            None => e
+        })
+    }
+}
+
+impl Drop for PairCallGuard {
+    fn drop(&mut self) {
+        *self.call.canceller.lock().unwrap() = None;
+
+        if let Some(cancel) = &self.cancel {
+            cancel.state.calls.lock().unwrap().retain(|c| !Arc::ptr_eq(c, &self.call));
+        }
+        if let Some(key) = self.key {
+            watchdog().deadlines.lock().unwrap().remove(&key);
+        }
+    }
+}
@@ -0,0 +1,7 @@
+use common_err::CommonError;
+use crate::{PairColumnInfo, PairValueEnum};
+
+pub trait PairRowCursor {
+    fn columns(&self) -> &'_ [PairColumnInfo];
+    fn next_row(&mut self) -> Result<Option<Vec<PairValueEnum>>, CommonError>;
+}
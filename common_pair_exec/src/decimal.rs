@@ -0,0 +1,72 @@
+use std::fmt::Display;
+use std::str::FromStr;
+use common_err::{CommonError, gen::CommonDefaultErrorKind};
+
+// exact decimal, value * 10^-scale
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct PairDecimal {
+    value : i128,
+    scale : u32
+}
+
+impl PairDecimal {
+    pub fn new(value : i128, scale : u32) -> Self {
+        PairDecimal { value, scale }
+    }
+
+    pub fn value(&self) -> i128 { self.value }
+    pub fn scale(&self) -> u32 { self.scale }
+
+    pub fn to_f64(&self) -> f64 {
+        self.to_string().parse::<f64>().unwrap_or(f64::NAN)
+    }
+}
+
+impl Display for PairDecimal {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let digits = self.value.unsigned_abs().to_string();
+        let sign = if self.value < 0 { "-" } else { "" };
+        let scale = self.scale as usize;
+
+        if scale == 0 {
+            return write!(f, "{}{}", sign, digits);
+        }
+
+        let digits = if digits.len() <= scale {
+            format!("{}{}", "0".repeat(scale - digits.len() + 1), digits)
+        } else {
+            digits
+        };
+        let (int, frac) = digits.split_at(digits.len() - scale);
+        write!(f, "{}{}.{}", sign, int, frac)
+    }
+}
+
+impl FromStr for PairDecimal {
+    type Err = CommonError;
+
+    fn from_str(s : &str) -> Result<Self, Self::Err> {
+        let err = || CommonError::new(&CommonDefaultErrorKind::ParsingFail, format!("PairDecimal.from_str - {}", s));
+        let trim = s.trim();
+        let (neg, body) = match trim.as_bytes().first() {
+            Some(b'-') => (true, &trim[1..]),
+            Some(b'+') => (false, &trim[1..]),
+            _ => (false, trim)
+        };
+        let (int, frac) = body.split_once('.').unwrap_or((body, ""));
+
+        if int.is_empty() && frac.is_empty() {
+            return Err(err());
+        }
+        if !int.bytes().chain(frac.bytes()).all(|b| b.is_ascii_digit()) {
+            return Err(err());
+        }
+
+        let mut value : i128 = 0;
+        for b in int.bytes().chain(frac.bytes()) {
+            value = value.checked_mul(10).and_then(|v| v.checked_add((b - b'0') as i128)).ok_or_else(err)?;
+        }
+
+        Ok(PairDecimal { value : if neg { -value } else { value }, scale : frac.len() as u32 })
+    }
+}
@@ -0,0 +1,304 @@
+use std::collections::HashMap;
+use common_err::CommonError;
+use common_err::gen::CommonDefaultErrorKind;
+use crate::{PairExecutorInfo, PairTlsOptions, PairTlsVerify};
+
+const DEFAULT_TIMEOUT_SEC : u32 = 30;
+const DEFAULT_STATEMENT_CACHE_SIZE : usize = 64;
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PairPgOptions {
+    // the pool name when unset
+    pub application_name : Option<String>
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PairOdbcOptions {
+    pub connection_string : String,
+    // answers get_current_time, the driver has no portable clock query
+    pub time_query : String,
+    pub time_column : String
+}
+
+// what only one backend understands, the shared fields stay on PairExecutorInfo
+#[derive(Debug, Clone, PartialEq)]
+pub enum PairBackendOptions {
+    Postgres(PairPgOptions),
+    Redis,
+    Scylla,
+    DuckDb,
+    Odbc(PairOdbcOptions)
+}
+
+fn dsn_error<S : Into<String>>(url : &'_ str, msg : S) -> CommonError {
+    CommonError::new(&CommonDefaultErrorKind::ParsingFail, format!("PairDsn - {} - {}", mask_password(url), msg.into()))
+}
+
+// keeps the password out of error messages
+fn mask_password(url : &'_ str) -> String {
+    let Some((scheme, rest)) = url.split_once("://") else {
+        return url.to_string();
+    };
+    let authority_end = rest.find(['/', '?']).unwrap_or(rest.len());
+    match rest[..authority_end].rfind('@').and_then(|at| rest[..at].find(':').map(|colon| (colon, at))) {
+        Some((colon, at)) => format!("{}://{}:***{}", scheme, &rest[..colon], &rest[at..]),
+        None => url.to_string()
+    }
+}
+
+// everything but the unreserved characters of RFC 3986
+pub fn encode_url_component(s : &'_ str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for b in s.bytes() {
+        if b.is_ascii_alphanumeric() || matches!(b, b'-' | b'.' | b'_' | b'~') {
+            out.push(b as char);
+        } else {
+            out.push_str(format!("%{:02X}", b).as_str());
+        }
+    }
+    out
+}
+
+pub fn decode_url_component(s : &'_ str) -> Result<String, CommonError> {
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut idx = 0;
+    while idx < bytes.len() {
+        if bytes[idx] == b'%' {
+            let hex = bytes.get(idx + 1..idx + 3).and_then(|h| std::str::from_utf8(h).ok()).and_then(|h| u8::from_str_radix(h, 16).ok());
+            match hex {
+                Some(b) => out.push(b),
+                None => return CommonError::new(&CommonDefaultErrorKind::ParsingFail, format!("PairDsn - bad percent escape at {}", idx)).to_result()
+            }
+            idx += 3;
+        } else {
+            out.push(bytes[idx]);
+            idx += 1;
+        }
+    }
+    String::from_utf8(out).map_err(|e| CommonError::new(&CommonDefaultErrorKind::ParsingFail, format!("PairDsn - not utf-8 after decoding - {}", e)))
+}
+
+fn parse_query(url : &'_ str, query : Option<&'_ str>) -> Result<HashMap<String, String>, CommonError> {
+    let mut params = HashMap::new();
+    for pair in query.unwrap_or("").split('&').filter(|p| !p.is_empty()) {
+        let (key, value) = pair.split_once('=').ok_or_else(|| dsn_error(url, format!("option {} has no value", pair)))?;
+        let key = decode_url_component(key)?;
+        if params.insert(key.clone(), decode_url_component(value)?).is_some() {
+            return Err(dsn_error(url, format!("option {} given twice", key)));
+        }
+    }
+    Ok(params)
+}
+
+fn parse_number<T : std::str::FromStr>(url : &'_ str, key : &'_ str, value : &'_ str) -> Result<T, CommonError> {
+    value.parse::<T>().map_err(|_| dsn_error(url, format!("option {} is not a number : {}", key, value)))
+}
+
+// host, host:port, [v6] or [v6]:port, the port is always filled in
+fn parse_host(url : &'_ str, host : &'_ str, default_port : u16) -> Result<String, CommonError> {
+    let (name, port) = match host.strip_prefix('[') {
+        Some(v6) => {
+            let (name, rest) = v6.split_once(']').ok_or_else(|| dsn_error(url, format!("unclosed bracket in host {}", host)))?;
+            match rest {
+                "" => (format!("[{}]", name), None),
+                _ => (format!("[{}]", name), Some(rest.strip_prefix(':').ok_or_else(|| dsn_error(url, format!("bad host {}", host)))?))
+            }
+        },
+        None => match host.split_once(':') {
+            Some((name, port)) => (name.to_string(), Some(port)),
+            None => (host.to_string(), None)
+        }
+    };
+
+    if name.is_empty() || name == "[]" {
+        return Err(dsn_error(url, "empty host"));
+    }
+    let port = match port {
+        Some(port) => parse_number::<u16>(url, "port", port)?,
+        None => default_port
+    };
+    Ok(format!("{}:{}", name, port))
+}
+
+fn parse_tls(url : &'_ str, params : &'_ mut HashMap<String, String>, default_verify : Option<PairTlsVerify>) -> Result<Option<PairTlsOptions>, CommonError> {
+    // libpq names, require encrypts without checking the certificate
+    let verify = match params.remove("sslmode").as_deref() {
+        None => default_verify,
+        Some("disable") => None,
+        Some("require") => Some(PairTlsVerify::None),
+        Some("verify-ca") => Some(PairTlsVerify::CaOnly),
+        Some("verify-full") => Some(PairTlsVerify::Full),
+        Some(other) => return Err(dsn_error(url, format!("sslmode {} is not one of disable, require, verify-ca, verify-full", other)))
+    };
+
+    let tls = PairTlsOptions {
+        ca_file : params.remove("sslrootcert"),
+        cert_file : params.remove("sslcert"),
+        key_file : params.remove("sslkey"),
+        verify : verify.unwrap_or_default(),
+        server_name : params.remove("server_name")
+    };
+
+    match verify {
+        Some(_) => {
+            if tls.cert_file.is_some() != tls.key_file.is_some() {
+                return Err(dsn_error(url, "sslcert and sslkey must be given together"));
+            }
+            Ok(Some(tls))
+        },
+        None if tls == PairTlsOptions::default() => Ok(None),
+        None => Err(dsn_error(url, "ssl options given while tls is off"))
+    }
+}
+
+fn reject_unknown(url : &'_ str, params : HashMap<String, String>) -> Result<(), CommonError> {
+    let mut keys = params.into_keys().collect::<Vec<_>>();
+    if keys.is_empty() {
+        return Ok(());
+    }
+    keys.sort();
+    Err(dsn_error(url, format!("unknown option {}", keys.join(", "))))
+}
+
+// scheme://[user[:password]@]host[:port][,host[:port]...][/name][?key=value&...]
+fn parse_network(url : &'_ str, rest : &'_ str, default_port : u16) -> Result<(PairExecutorInfo, HashMap<String, String>), CommonError> {
+    if rest.contains('#') {
+        return Err(dsn_error(url, "fragments are not supported, use sslmode"));
+    }
+    let (rest, query) = match rest.split_once('?') {
+        Some((rest, query)) => (rest, Some(query)),
+        None => (rest, None)
+    };
+    let (authority, name) = match rest.split_once('/') {
+        Some((authority, name)) => (authority, name),
+        None => (rest, "")
+    };
+    if name.contains('/') {
+        return Err(dsn_error(url, "database name has a '/', escape it as %2F"));
+    }
+
+    // the last '@' so an unescaped one in the password still parses
+    let (user_info, hosts) = match authority.rfind('@') {
+        Some(at) => (Some(&authority[..at]), &authority[at + 1..]),
+        None => (None, authority)
+    };
+    let (user, password) = match user_info.map(|u| u.split_once(':').unwrap_or((u, ""))) {
+        Some((user, password)) => (decode_url_component(user)?, decode_url_component(password)?),
+        None => (String::new(), String::new())
+    };
+
+    let addr = hosts.split(',').map(|h| parse_host(url, h, default_port)).collect::<Result<Vec<_>, _>>()?;
+    let mut params = parse_query(url, query)?;
+
+    let timeout_sec = match params.remove("timeout") {
+        Some(v) => parse_number(url, "timeout", v.as_str())?,
+        None => DEFAULT_TIMEOUT_SEC
+    };
+    let statement_cache_size = match params.remove("statement_cache_size") {
+        Some(v) => parse_number(url, "statement_cache_size", v.as_str())?,
+        None => DEFAULT_STATEMENT_CACHE_SIZE
+    };
+
+    let info = PairExecutorInfo {
+        addr,
+        name : decode_url_component(name)?,
+        user,
+        password,
+        timeout_sec,
+        statement_cache_size,
+        ..Default::default()
+    };
+    Ok((info, params))
+}
+
+// odbc:<connection string>[?time_query=...&time_column=...], a '?' in the string is escaped as %3F
+fn parse_odbc(url : &'_ str, rest : &'_ str) -> Result<PairExecutorInfo, CommonError> {
+    let (conn_str, query) = match rest.split_once('?') {
+        Some((conn_str, query)) => (conn_str, Some(query)),
+        None => (rest, None)
+    };
+    let connection_string = decode_url_component(conn_str)?;
+    if connection_string.trim().is_empty() {
+        return Err(dsn_error(url, "empty connection string"));
+    }
+
+    let mut params = parse_query(url, query)?;
+    let (Some(time_query), Some(time_column)) = (params.remove("time_query"), params.remove("time_column")) else {
+        return Err(dsn_error(url, "time_query and time_column are required"));
+    };
+    reject_unknown(url, params)?;
+
+    Ok(PairExecutorInfo {
+        backend : Some(PairBackendOptions::Odbc(PairOdbcOptions { connection_string, time_query, time_column })),
+        ..Default::default()
+    })
+}
+
+// duckdb:///abs/path, duckdb://relative/path or duckdb:// for an in-memory database
+fn parse_duckdb(url : &'_ str, rest : &'_ str) -> Result<PairExecutorInfo, CommonError> {
+    let (path, query) = match rest.split_once('?') {
+        Some((path, query)) => (path, Some(query)),
+        None => (rest, None)
+    };
+
+    let mut params = parse_query(url, query)?;
+    let statement_cache_size = match params.remove("statement_cache_size") {
+        Some(v) => parse_number(url, "statement_cache_size", v.as_str())?,
+        None => DEFAULT_STATEMENT_CACHE_SIZE
+    };
+    reject_unknown(url, params)?;
+
+    Ok(PairExecutorInfo {
+        addr : vec![decode_url_component(path)?],
+        statement_cache_size,
+        backend : Some(PairBackendOptions::DuckDb),
+        ..Default::default()
+    })
+}
+
+impl PairExecutorInfo {
+    // user, password, names and option values are percent-decoded, unknown options are an error
+    pub fn from_url(url : &'_ str) -> Result<PairExecutorInfo, CommonError> {
+        if let Some(rest) = url.strip_prefix("odbc:") {
+            return parse_odbc(url, rest);
+        }
+
+        let (scheme, rest) = url.split_once("://").ok_or_else(|| dsn_error(url, "no scheme"))?;
+        match scheme {
+            "postgres" | "postgresql" => {
+                let (mut info, mut params) = parse_network(url, rest, 5432)?;
+                info.tls = parse_tls(url, &mut params, None)?;
+                info.backend = Some(PairBackendOptions::Postgres(PairPgOptions { application_name : params.remove("application_name") }));
+                reject_unknown(url, params)?;
+                Ok(info)
+            },
+            "redis" | "rediss" => {
+                let (mut info, mut params) = parse_network(url, rest, 6379)?;
+                let default_verify = if scheme == "rediss" { Some(PairTlsVerify::Full) } else { None };
+                info.tls = parse_tls(url, &mut params, default_verify)?;
+                reject_unknown(url, params)?;
+
+                if info.name.is_empty() {
+                    info.name = "0".to_string();
+                }
+                parse_number::<u32>(url, "database", info.name.as_str())?;
+                info.backend = Some(PairBackendOptions::Redis);
+                Ok(info)
+            },
+            "scylla" => {
+                let (mut info, mut params) = parse_network(url, rest, 9042)?;
+                info.tls = parse_tls(url, &mut params, None)?;
+                reject_unknown(url, params)?;
+
+                if info.name.is_empty() {
+                    return Err(dsn_error(url, "keyspace is required"));
+                }
+                info.backend = Some(PairBackendOptions::Scylla);
+                Ok(info)
+            },
+            "duckdb" => parse_duckdb(url, rest),
+            _ => Err(dsn_error(url, format!("unknown scheme {}", scheme)))
+        }
+    }
+}
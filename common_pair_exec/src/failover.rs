@@ -0,0 +1,125 @@
+use std::future::Future;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+use common_err::{CommonError, CommonErrorKind};
+use common_err::gen::CommonDefaultErrorKind;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum PairFailoverMode {
+    // last healthy address first, then the rest in the given order
+    #[default]
+    InOrder,
+    RoundRobin
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct PairFailoverOptions {
+    pub mode : PairFailoverMode,
+    // rejects replicas, pg_is_in_recovery() false / redis ROLE master
+    pub require_primary : bool,
+    // a failed address is tried last for this long
+    pub retry_after : Option<Duration>
+}
+
+// a bad tls or driver option fails the same way on every address
+fn is_option_error(err : &CommonError) -> bool {
+    err.func_ref().iter().any(|pos| {
+        pos.3.name() == CommonDefaultErrorKind::InitFailed.name() || pos.3.name() == CommonDefaultErrorKind::NoSupport.name()
+    })
+}
+
+// picks the address order for each new connection and remembers which node answered
+pub struct PairAddrSelector {
+    addrs : Vec<String>,
+    options : PairFailoverOptions,
+    healthy : AtomicUsize,
+    next : AtomicUsize,
+    failed_at : Mutex<Vec<Option<Instant>>>
+}
+
+impl PairAddrSelector {
+    pub fn new(addrs : Vec<String>, options : PairFailoverOptions) -> Self {
+        let failed_at = Mutex::new(vec![None; addrs.len()]);
+        PairAddrSelector { addrs, options, healthy : AtomicUsize::new(0), next : AtomicUsize::new(0), failed_at }
+    }
+
+    pub fn options(&self) -> PairFailoverOptions {
+        self.options
+    }
+
+    pub fn healthy_addr(&self) -> Option<&'_ str> {
+        self.addrs.get(self.healthy.load(Ordering::Relaxed)).map(|a| a.as_str())
+    }
+
+    fn order(&self) -> Vec<usize> {
+        let len = self.addrs.len();
+        let start = match self.options.mode {
+            PairFailoverMode::InOrder => self.healthy.load(Ordering::Relaxed),
+            PairFailoverMode::RoundRobin => self.next.fetch_add(1, Ordering::Relaxed)
+        } % len.max(1);
+
+        let mut order : Vec<usize> = (0..len).map(|i| (start + i) % len).collect();
+
+        if let Some(retry_after) = self.options.retry_after {
+            let now = Instant::now();
+            let failed_at = self.failed_at.lock().unwrap();
+            // stable, keeps the order among the recently failed ones
+            order.sort_by_key(|idx| failed_at[*idx].is_some_and(|t| now.duration_since(t) < retry_after));
+        }
+        order
+    }
+
+    fn mark(&self, idx : usize, ok : bool) {
+        if ok {
+            self.healthy.store(idx, Ordering::Relaxed);
+        }
+        self.failed_at.lock().unwrap()[idx] = if ok { None } else { Some(Instant::now()) };
+    }
+
+    fn all_failed(&self, errors : Vec<String>) -> CommonError {
+        CommonError::new(&CommonDefaultErrorKind::ConnectFail, format!("no address available [{}]", errors.join(", ")))
+    }
+
+    pub fn connect<T, F>(&self, mut f : F) -> Result<T, CommonError>
+    where F : FnMut(&'_ str) -> Result<T, CommonError> {
+        let mut errors = Vec::new();
+
+        for idx in self.order() {
+            match f(self.addrs[idx].as_str()) {
+                Ok(ok) => {
+                    self.mark(idx, true);
+                    return Ok(ok);
+                },
+                Err(e) if is_option_error(&e) => return Err(e),
+                Err(e) => {
+                    self.mark(idx, false);
+                    errors.push(format!("{} - {}", self.addrs[idx], e.get_cause()));
+                }
+            }
+        }
+
+        Err(self.all_failed(errors))
+    }
+
+    pub async fn connect_async<T, F, Fut>(&self, mut f : F) -> Result<T, CommonError>
+    where F : FnMut(String) -> Fut, Fut : Future<Output = Result<T, CommonError>> {
+        let mut errors = Vec::new();
+
+        for idx in self.order() {
+            match f(self.addrs[idx].clone()).await {
+                Ok(ok) => {
+                    self.mark(idx, true);
+                    return Ok(ok);
+                },
+                Err(e) if is_option_error(&e) => return Err(e),
+                Err(e) => {
+                    self.mark(idx, false);
+                    errors.push(format!("{} - {}", self.addrs[idx], e.get_cause()));
+                }
+            }
+        }
+
+        Err(self.all_failed(errors))
+    }
+}
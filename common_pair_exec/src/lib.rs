@@ -1,8 +1,45 @@
+mod async_exec;
+mod breaker;
+mod cancel;
+mod cursor;
+mod decimal;
+mod dsn;
+mod failover;
+mod named;
+mod pooled;
+mod result_set;
+mod retry;
+mod routing;
+mod row;
+mod tls;
+mod transaction;
+
 use std::collections::HashMap;
 use std::fmt::Display;
+use std::net::IpAddr;
+use chrono::{DateTime, FixedOffset, NaiveDate, NaiveTime};
 use std::sync::Arc;
-use common_core::collection::pool::{PoolItem, ThreadSafePool};
-use common_err::CommonError;
+use std::time::Duration;
+use common_core::collection::pool::{PoolItem, PoolKind, PoolPolicy, ThreadSafePool};
+use common_err::{CommonError, gen::CommonDefaultErrorKind};
+
+pub use async_exec::{create_blocking_async_pair_pool, AsyncPairExecutor, AsyncPairExecutorBox, AsyncPairExecutorPool, BlockingPairExecutor};
+pub use breaker::{PairCircuitBreaker, PairCircuitOptions, PairCircuitState};
+pub use cancel::{PairCallGuard, PairCallOptions, PairCancelHandle};
+pub use cursor::PairRowCursor;
+pub use decimal::PairDecimal;
+pub use dsn::{decode_url_component, encode_url_component, PairBackendOptions, PairOdbcOptions, PairPgOptions};
+pub use failover::{PairAddrSelector, PairFailoverMode, PairFailoverOptions};
+pub use named::{resolve_named_batch, resolve_named_param, PairInsertTarget, PairNamedQuery, PairPlaceholderStyle};
+pub use pooled::{is_connection_error, PooledPairExecutor};
+pub use result_set::{PairColumnInfo, PairExecuteOutcome, PairResultSet};
+pub use retry::{is_transient_error, with_retry, PairRetryPolicy};
+pub use routing::{classify_pair_statement, is_pair_dml_statement, PairRoute, PairRoutingPool, RoutingPairExecutor};
+pub use row::{get_pair_row_column, unknown_pair_variant, FromPairRow, FromPairValue, ToPairParams, ToPairValue};
+pub use common_pair_derive::{FromPairRow, FromPairValue, ToPairParams, ToPairValue};
+pub use tls::{install_pair_crypto_provider, pair_crypto_provider, PairTlsOptions, PairTlsVerify};
+pub use transaction::with_transaction;
+
 #[derive(Clone, Debug, PartialEq)]
 pub enum PairValueEnum {
     Double(f64),
@@ -12,6 +49,15 @@ pub enum PairValueEnum {
     Bin(Vec<u8>),
     Bool(bool),
     Float(f32),
+    SmallInt(i16),
+    TinyInt(i8),
+    Timestamp(DateTime<FixedOffset>),
+    Date(NaiveDate),
+    Time(NaiveTime),
+    Uuid(uuid::Uuid),
+    Decimal(PairDecimal),
+    Json(String),
+    Inet(IpAddr),
     Array(Vec<PairValueEnum>),
     Map(HashMap<String, PairValueEnum>),
     Null
@@ -28,7 +74,83 @@ pub struct PairExecutorInfo {
     pub user : String,
     pub password : String,
     pub timeout_sec : u32,
-    pub extend : Option<Vec<String>>
+    pub statement_cache_size : usize,
+    pub pool : PairPoolOptions,
+    pub failover : PairFailoverOptions,
+    pub tls : Option<PairTlsOptions>,
+    pub backend : Option<PairBackendOptions>
+}
+
+// applied by the sync pools, the async pools keep executors as they are
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct PairPoolOptions {
+    // pings idle executors before handing them out
+    pub test_on_borrow : bool,
+    pub idle_timeout : Option<Duration>,
+    pub max_lifetime : Option<Duration>,
+    pub max_uses : Option<u64>,
+    // connections opened when the pool is created and kept idle in the background
+    pub min_idle : usize,
+    pub kind : PoolKind
+}
+
+impl PairPoolOptions {
+    pub fn to_pool_policy(&self) -> PoolPolicy<Box<dyn PairExecutor + Send>> {
+        let validate : Option<Box<dyn Fn(&mut Box<dyn PairExecutor + Send>) -> bool + Send + Sync>> = if self.test_on_borrow {
+            Some(Box::new(|e : &mut Box<dyn PairExecutor + Send>| e.ping().is_ok()))
+        } else {
+            None
+        };
+
+        // a connection whose rollback fails is disposed, the next borrower never inherits a transaction
+        let reset : Box<dyn Fn(&mut Box<dyn PairExecutor + Send>) -> bool + Send + Sync> = Box::new(|e : &mut Box<dyn PairExecutor + Send>| {
+            !e.in_transaction() || e.rollback().is_ok()
+        });
+        PoolPolicy {
+            validate,
+            reset : Some(reset),
+            idle_timeout : self.idle_timeout,
+            max_lifetime : self.max_lifetime,
+            max_uses : self.max_uses,
+            min_idle : self.min_idle,
+            kind : self.kind,
+            ..Default::default()
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct PairStatementCacheStats {
+    pub hit : u64,
+    pub miss : u64,
+    pub size : usize,
+    pub capacity : usize
+}
+
+impl PairValueEnum {
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            PairValueEnum::Double(_) => "Double",
+            PairValueEnum::Int(_) => "Int",
+            PairValueEnum::BigInt(_) => "BigInt",
+            PairValueEnum::String(_) => "String",
+            PairValueEnum::Bin(_) => "Bin",
+            PairValueEnum::Bool(_) => "Bool",
+            PairValueEnum::Float(_) => "Float",
+            PairValueEnum::SmallInt(_) => "SmallInt",
+            PairValueEnum::TinyInt(_) => "TinyInt",
+            PairValueEnum::Timestamp(_) => "Timestamp",
+            PairValueEnum::Date(_) => "Date",
+            PairValueEnum::Time(_) => "Time",
+            PairValueEnum::Uuid(_) => "Uuid",
+            PairValueEnum::Decimal(_) => "Decimal",
+            PairValueEnum::Json(_) => "Json",
+            PairValueEnum::Inet(_) => "Inet",
+            PairValueEnum::Array(_) => "Array",
+            PairValueEnum::Map(_) => "Map",
+            PairValueEnum::Null => "Null",
+        }
+    }
 }
 
 impl Display for PairValueEnum {
@@ -40,6 +162,15 @@ impl Display for PairValueEnum {
             PairValueEnum::String(s) => s.to_string(),
             PairValueEnum::Bool(b) => b.to_string(),
             PairValueEnum::Float(f) => f.to_string(),
+            PairValueEnum::SmallInt(i) => i.to_string(),
+            PairValueEnum::TinyInt(i) => i.to_string(),
+            PairValueEnum::Timestamp(t) => t.to_rfc3339(),
+            PairValueEnum::Date(d) => d.to_string(),
+            PairValueEnum::Time(t) => t.to_string(),
+            PairValueEnum::Uuid(u) => u.to_string(),
+            PairValueEnum::Decimal(d) => d.to_string(),
+            PairValueEnum::Json(j) => j.to_string(),
+            PairValueEnum::Inet(i) => i.to_string(),
             PairValueEnum::Null => "NULL".to_string(),
             PairValueEnum::Bin(b) => String::from_utf8(b.clone()).unwrap(),
             PairValueEnum::Map(m) => format!("{:?}", m),
@@ -49,9 +180,61 @@ impl Display for PairValueEnum {
 }
 
 pub trait PairExecutor {
-    fn execute_pair(&mut self, query : &'_ str, param : &PairValueEnum) -> Result<PairValueEnum, CommonError>;
+    fn execute_pair(&mut self, query : &'_ str, param : &PairValueEnum) -> Result<PairValueEnum, CommonError> {
+        Ok(self.execute_pair_result_set(query, param)?.into_pair_map())
+    }
+    fn execute_pair_result_set(&mut self, query : &'_ str, param : &PairValueEnum) -> Result<PairResultSet, CommonError> {
+        let cursor = self.execute_pair_cursor(query, param)?;
+        PairResultSet::from_cursor(cursor)
+    }
+    fn execute_pair_outcome(&mut self, query : &'_ str, param : &PairValueEnum) -> Result<PairExecuteOutcome, CommonError> {
+        let result_set = self.execute_pair_result_set(query, param)?;
+        Ok(PairExecuteOutcome::new(None, result_set))
+    }
+    fn execute_pair_cursor<'a>(&'a mut self, query : &'_ str, param : &PairValueEnum) -> Result<Box<dyn PairRowCursor + 'a>, CommonError>;
     fn get_current_time(&mut self) -> Result<std::time::Duration, CommonError>;
+
+    // cheapest round trip the backend offers, used by test-on-borrow
+    fn ping(&mut self) -> Result<(), CommonError> {
+        self.get_current_time().map(|_| ())
+    }
+
+    // connection the driver already knows to be gone, checked without a round trip
+    fn is_closed(&mut self) -> bool {
+        false
+    }
+
+    // a deadline or cancel handle the backend cannot honour is refused rather than ignored
+    fn execute_pair_with(&mut self, query : &'_ str, param : &PairValueEnum, options : &PairCallOptions) -> Result<PairValueEnum, CommonError> {
+        if options.is_empty() {
+            return self.execute_pair(query, param);
+        }
+        CommonError::new(&CommonDefaultErrorKind::NoSupport, "execute with deadline or cancel").to_result()
+    }
+
+    fn execute_batch(&mut self, _query : &'_ str, _rows : &'_ [PairValueEnum]) -> Result<Vec<u64>, CommonError> {
+        CommonError::new(&CommonDefaultErrorKind::NoSupport, "execute batch").to_result()
+    }
+
+    fn statement_cache_stats(&self) -> Option<PairStatementCacheStats> {
+        None
+    }
+
+    // checked when the executor goes back to a pool, an open transaction is rolled back first
+    fn in_transaction(&self) -> bool {
+        false
+    }
+
+    fn begin(&mut self) -> Result<(), CommonError> {
+        CommonError::new(&CommonDefaultErrorKind::NoSupport, "begin transaction").to_result()
+    }
+    fn commit(&mut self) -> Result<(), CommonError> {
+        CommonError::new(&CommonDefaultErrorKind::NoSupport, "commit transaction").to_result()
+    }
+    fn rollback(&mut self) -> Result<(), CommonError> {
+        CommonError::new(&CommonDefaultErrorKind::NoSupport, "rollback transaction").to_result()
+    }
 }
 
-pub type PairExecutorBox = Box<dyn PoolItem<Box<dyn PairExecutor>>>;
-pub type PairExecutorPool = Arc<dyn ThreadSafePool<Box<dyn PairExecutor>,()>>;
\ No newline at end of file
+pub type PairExecutorBox = Box<dyn PoolItem<Box<dyn PairExecutor + Send>>>;
+pub type PairExecutorPool = Arc<dyn ThreadSafePool<Box<dyn PairExecutor + Send>,()>>;
\ No newline at end of file
@@ -0,0 +1,363 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+use common_err::{CommonError, gen::CommonDefaultErrorKind};
+use crate::PairValueEnum;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PairPlaceholderStyle {
+    // $1, $2 .. a repeated name reuses its number
+    Dollar,
+    // ? per occurrence
+    Question
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PairNamedQuery {
+    query : String,
+    names : Vec<String>
+}
+
+#[inline]
+fn is_ident_start(b : u8) -> bool {
+    b.is_ascii_alphabetic() || b == b'_'
+}
+
+#[inline]
+fn is_ident(b : u8) -> bool {
+    b.is_ascii_alphanumeric() || b == b'_'
+}
+
+fn unterminated<T>(what : &'_ str, pos : usize) -> Result<T, CommonError> {
+    CommonError::new(&CommonDefaultErrorKind::ParsingFail,
+                     format!("rewrite_named_query - unterminated {} at {}", what, pos)).to_result()
+}
+
+// returns the index right after the closing quote
+fn skip_quoted(b : &'_ [u8], start : usize, quote : u8, backslash : bool) -> Result<usize, CommonError> {
+    let mut i = start + 1;
+    while i < b.len() {
+        if backslash && b[i] == b'\\' {
+            i += 2;
+            continue;
+        }
+        if b[i] == quote {
+            // doubled quote is an escaped quote
+            if b.get(i + 1) == Some(&quote) {
+                i += 2;
+                continue;
+            }
+            return Ok(i + 1);
+        }
+        i += 1;
+    }
+
+    unterminated("quoted text", start)
+}
+
+fn skip_block_comment(b : &'_ [u8], start : usize) -> Result<usize, CommonError> {
+    let mut depth = 0;
+    let mut i = start;
+    while i + 1 < b.len() {
+        if b[i] == b'/' && b[i + 1] == b'*' {
+            depth += 1;
+            i += 2;
+        } else if b[i] == b'*' && b[i + 1] == b'/' {
+            depth -= 1;
+            i += 2;
+            if depth == 0 {
+                return Ok(i);
+            }
+        } else {
+            i += 1;
+        }
+    }
+
+    unterminated("block comment", start)
+}
+
+// $tag$ ... $tag$, None when the dollar does not open a quote ($1, a$b)
+fn skip_dollar_quote(b : &'_ [u8], start : usize) -> Result<Option<usize>, CommonError> {
+    if start > 0 && is_ident(b[start - 1]) {
+        return Ok(None);
+    }
+
+    let mut j = start + 1;
+    if j < b.len() && is_ident_start(b[j]) {
+        while j < b.len() && is_ident(b[j]) {
+            j += 1;
+        }
+    }
+    if j >= b.len() || b[j] != b'$' {
+        return Ok(None);
+    }
+
+    let tag = &b[start..=j];
+    let body = j + 1;
+    match b[body..].windows(tag.len()).position(|w| w == tag) {
+        Some(p) => Ok(Some(body + p + tag.len())),
+        None => unterminated("dollar quote", start)
+    }
+}
+
+// bare words outside literals, quoted identifiers and comments, lower cased. an unterminated quote ends the scan
+pub(crate) fn statement_keywords(query : &'_ str) -> Vec<String> {
+    let b = query.as_bytes();
+    let mut words = Vec::new();
+    let mut i = 0;
+
+    while i < b.len() {
+        let next = b.get(i + 1).copied();
+
+        let end = match b[i] {
+            b'\'' => {
+                let escape = i > 0 && (b[i - 1] == b'E' || b[i - 1] == b'e') && (i < 2 || !is_ident(b[i - 2]));
+                skip_quoted(b, i, b'\'', escape)
+            },
+            b'"' | b'`' => skip_quoted(b, i, b[i], false),
+            b'-' if next == Some(b'-') => {
+                Ok(b[i..].iter().position(|c| *c == b'\n').map(|p| i + p + 1).unwrap_or(b.len()))
+            },
+            b'/' if next == Some(b'*') => skip_block_comment(b, i),
+            b'$' => skip_dollar_quote(b, i).map(|end| end.unwrap_or(i + 1)),
+            c if is_ident_start(c) && (i == 0 || !is_ident(b[i - 1])) => {
+                let end = b[i..].iter().position(|c| !is_ident(*c)).map(|p| i + p).unwrap_or(b.len());
+                words.push(query[i..end].to_ascii_lowercase());
+                Ok(end)
+            },
+            _ => Ok(i + 1)
+        };
+
+        match end {
+            Ok(end) => i = end,
+            Err(_) => break
+        }
+    }
+
+    words
+}
+
+impl PairNamedQuery {
+    // rewrites :name markers outside literals, comments and ::casts
+    pub fn parse(query : &'_ str, style : PairPlaceholderStyle) -> Result<Self, CommonError> {
+        let b = query.as_bytes();
+        let mut rewrite = String::with_capacity(query.len());
+        let mut names : Vec<String> = Vec::new();
+        let mut copied = 0;
+        let mut i = 0;
+
+        while i < b.len() {
+            let next = b.get(i + 1).copied();
+
+            i = match b[i] {
+                b'\'' => {
+                    let escape = i > 0 && (b[i - 1] == b'E' || b[i - 1] == b'e') && (i < 2 || !is_ident(b[i - 2]));
+                    skip_quoted(b, i, b'\'', escape)?
+                },
+                b'"' | b'`' => skip_quoted(b, i, b[i], false)?,
+                b'-' if next == Some(b'-') => {
+                    b[i..].iter().position(|c| *c == b'\n').map(|p| i + p + 1).unwrap_or(b.len())
+                },
+                b'/' if next == Some(b'*') => skip_block_comment(b, i)?,
+                b'$' => skip_dollar_quote(b, i)?.unwrap_or(i + 1),
+                b':' if next == Some(b':') => i + 2,
+                b':' if next.map(is_ident_start).unwrap_or(false) && (i == 0 || !is_ident(b[i - 1])) => {
+                    let end = b[i + 1..].iter().position(|c| !is_ident(*c)).map(|p| i + 1 + p).unwrap_or(b.len());
+                    let name = &query[i + 1..end];
+
+                    rewrite.push_str(&query[copied..i]);
+                    match style {
+                        PairPlaceholderStyle::Dollar => {
+                            let idx = match names.iter().position(|n| n == name) {
+                                Some(idx) => idx,
+                                None => {
+                                    names.push(name.to_string());
+                                    names.len() - 1
+                                }
+                            };
+                            rewrite.push_str(format!("${}", idx + 1).as_str());
+                        },
+                        PairPlaceholderStyle::Question => {
+                            names.push(name.to_string());
+                            rewrite.push('?');
+                        }
+                    }
+                    copied = end;
+                    end
+                },
+                _ => i + 1
+            };
+        }
+        rewrite.push_str(&query[copied..]);
+
+        Ok(PairNamedQuery { query : rewrite, names })
+    }
+
+    pub fn query(&self) -> &'_ str { self.query.as_str() }
+    pub fn names(&self) -> &'_ [String] { self.names.as_slice() }
+
+    // named map into positional array, unused keys are ignored
+    pub fn bind(&self, param : &'_ HashMap<String, PairValueEnum>) -> Result<PairValueEnum, CommonError> {
+        self.names.iter().map(|n| {
+            param.get(n).cloned().ok_or_else(|| {
+                CommonError::new(&CommonDefaultErrorKind::NotMatchArgs, format!("PairNamedQuery.bind - param ':{}' not exists", n))
+            })
+        }).collect::<Result<Vec<PairValueEnum>, CommonError>>().map(PairValueEnum::Array)
+    }
+}
+
+// Map params are bound by name, anything else passes through untouched
+pub fn resolve_named_param<'a>(query : &'a str, param : &'a PairValueEnum,
+                               style : PairPlaceholderStyle) -> Result<(Cow<'a, str>, Cow<'a, PairValueEnum>), CommonError> {
+    match param {
+        PairValueEnum::Map(m) => {
+            let named = PairNamedQuery::parse(query, style)?;
+            let bind = named.bind(m)?;
+            Ok((Cow::Owned(named.query), Cow::Owned(bind)))
+        },
+        _ => Ok((Cow::Borrowed(query), Cow::Borrowed(param)))
+    }
+}
+
+pub fn resolve_named_batch<'a>(query : &'a str, rows : &'a [PairValueEnum],
+                               style : PairPlaceholderStyle) -> Result<(Cow<'a, str>, Cow<'a, [PairValueEnum]>), CommonError> {
+    if !rows.iter().any(|r| matches!(r, PairValueEnum::Map(_))) {
+        return Ok((Cow::Borrowed(query), Cow::Borrowed(rows)));
+    }
+
+    let named = PairNamedQuery::parse(query, style)?;
+    let binds = rows.iter().enumerate().map(|(idx, r)| match r {
+        PairValueEnum::Map(m) => named.bind(m),
+        _ => CommonError::new(&CommonDefaultErrorKind::NotMatchArgs,
+                              format!("resolve_named_batch - row {} is {}, every row must be a Map", idx, r.type_name())).to_result()
+    }).collect::<Result<Vec<PairValueEnum>, CommonError>>()?;
+
+    Ok((Cow::Owned(named.query), Cow::Owned(binds)))
+}
+
+// insert into [schema.]table (cols) values (placeholders), the shape a bulk loader can take over
+#[derive(Debug, Clone, PartialEq)]
+pub struct PairInsertTarget {
+    pub schema : Option<String>,
+    pub table : String,
+    pub columns : Vec<String>
+}
+
+enum InsertToken<'a> {
+    Word(&'a str),
+    Quoted(String),
+    Placeholder(Option<usize>),
+    Punct(u8)
+}
+
+fn insert_tokens(query : &'_ str) -> Option<Vec<InsertToken<'_>>> {
+    let b = query.as_bytes();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < b.len() {
+        let next = b.get(i + 1).copied();
+
+        match b[i] {
+            c if c.is_ascii_whitespace() => i += 1,
+            b'-' if next == Some(b'-') => {
+                i = b[i..].iter().position(|c| *c == b'\n').map(|p| i + p + 1).unwrap_or(b.len());
+            },
+            b'/' if next == Some(b'*') => i = skip_block_comment(b, i).ok()?,
+            b'"' => {
+                let end = skip_quoted(b, i, b'"', false).ok()?;
+                tokens.push(InsertToken::Quoted(query[i + 1..end - 1].replace("\"\"", "\"")));
+                i = end;
+            },
+            b'$' => {
+                let end = b[i + 1..].iter().position(|c| !c.is_ascii_digit()).map(|p| i + 1 + p).unwrap_or(b.len());
+                tokens.push(InsertToken::Placeholder(Some(query[i + 1..end].parse().ok()?)));
+                i = end;
+            },
+            b'?' => {
+                tokens.push(InsertToken::Placeholder(None));
+                i += 1;
+            },
+            b'(' | b')' | b',' | b'.' | b';' => {
+                tokens.push(InsertToken::Punct(b[i]));
+                i += 1;
+            },
+            c if is_ident_start(c) => {
+                let end = b[i..].iter().position(|c| !is_ident(*c)).map(|p| i + p).unwrap_or(b.len());
+                tokens.push(InsertToken::Word(&query[i..end]));
+                i = end;
+            },
+            _ => return None
+        }
+    }
+
+    Some(tokens)
+}
+
+impl PairInsertTarget {
+    // None for anything past the plain form, expressions, on conflict, returning or multi row values
+    pub fn parse(query : &'_ str, style : PairPlaceholderStyle) -> Option<Self> {
+        let tokens = insert_tokens(query)?;
+        let mut it = tokens.into_iter().peekable();
+
+        let keyword = |t : Option<InsertToken<'_>>, k : &'_ str| matches!(t, Some(InsertToken::Word(w)) if w.eq_ignore_ascii_case(k));
+        // unquoted names fold to lower case, quoted ones are kept as written
+        let ident = |t : Option<InsertToken<'_>>| match t {
+            Some(InsertToken::Word(w)) => Some(w.to_ascii_lowercase()),
+            Some(InsertToken::Quoted(q)) => Some(q),
+            _ => None
+        };
+        let punct = |t : Option<InsertToken<'_>>, p : u8| matches!(t, Some(InsertToken::Punct(c)) if c == p);
+
+        if !keyword(it.next(), "insert") || !keyword(it.next(), "into") {
+            return None;
+        }
+
+        let mut table = ident(it.next())?;
+        let mut schema = None;
+        if matches!(it.peek(), Some(InsertToken::Punct(b'.'))) {
+            it.next();
+            schema = Some(table);
+            table = ident(it.next())?;
+        }
+
+        if !punct(it.next(), b'(') {
+            return None;
+        }
+        let mut columns = Vec::new();
+        loop {
+            columns.push(ident(it.next())?);
+            match it.next() {
+                Some(InsertToken::Punct(b',')) => continue,
+                Some(InsertToken::Punct(b')')) => break,
+                _ => return None
+            }
+        }
+
+        if !keyword(it.next(), "values") || !punct(it.next(), b'(') {
+            return None;
+        }
+        for idx in 0..columns.len() {
+            let expected = match style {
+                PairPlaceholderStyle::Dollar => Some(idx + 1),
+                PairPlaceholderStyle::Question => None
+            };
+            match it.next() {
+                Some(InsertToken::Placeholder(p)) if p == expected => {},
+                _ => return None
+            }
+            let sep = if idx + 1 == columns.len() { b')' } else { b',' };
+            if !punct(it.next(), sep) {
+                return None;
+            }
+        }
+
+        if matches!(it.peek(), Some(InsertToken::Punct(b';'))) {
+            it.next();
+        }
+        if it.next().is_some() {
+            return None;
+        }
+
+        Some(PairInsertTarget { schema, table, columns })
+    }
+}
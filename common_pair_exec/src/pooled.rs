@@ -0,0 +1,162 @@
+use common_err::{CommonError, CommonErrorKind};
+use common_err::gen::CommonDefaultErrorKind;
+use crate::{PairCallOptions, PairColumnInfo, PairExecuteOutcome, PairExecutor, PairExecutorBox, PairExecutorPool, PairResultSet, PairRowCursor, PairStatementCacheStats, PairValueEnum};
+
+// backends report lost connections as ConnectFail anywhere in the error stack
+pub fn is_connection_error(err : &CommonError) -> bool {
+    let connect_fail = CommonDefaultErrorKind::ConnectFail.name();
+    err.func_ref().iter().any(|pos| pos.3.name() == connect_fail)
+}
+
+// disposes the executor on drop instead of restoring it once a connection error was seen,
+// an open transaction is rolled back first and the executor disposed if that fails
+pub struct PooledPairExecutor {
+    item : Option<PairExecutorBox>,
+    broken : bool
+}
+
+impl PooledPairExecutor {
+    pub fn new(item : PairExecutorBox) -> Self {
+        PooledPairExecutor { item : Some(item), broken : false }
+    }
+
+    pub fn from_pool(pool : &'_ PairExecutorPool) -> Result<Self, CommonError> {
+        Ok(Self::new(pool.get_owned(())?))
+    }
+
+    pub fn is_broken(&self) -> bool {
+        self.broken
+    }
+
+    // for failures the wrapper cannot see, e.g. a cursor dropped half read
+    pub fn mark_broken(&mut self) {
+        self.broken = true;
+    }
+
+    pub fn get_value(&mut self) -> &'_ mut Box<dyn PairExecutor + Send> {
+        self.item.as_mut().unwrap().get_value()
+    }
+
+    fn track<R>(&mut self, ret : Result<R, CommonError>) -> Result<R, CommonError> {
+        if let Err(e) = &ret {
+            if is_connection_error(e) {
+                self.broken = true;
+            }
+        }
+        ret
+    }
+}
+
+struct TrackedRowCursor<'a> {
+    inner : Box<dyn PairRowCursor + 'a>,
+    broken : &'a mut bool
+}
+
+impl PairRowCursor for TrackedRowCursor<'_> {
+    fn columns(&self) -> &'_ [PairColumnInfo] {
+        self.inner.columns()
+    }
+
+    fn next_row(&mut self) -> Result<Option<Vec<PairValueEnum>>, CommonError> {
+        let ret = self.inner.next_row();
+        if let Err(e) = &ret {
+            if is_connection_error(e) {
+                *self.broken = true;
+            }
+        }
+        ret
+    }
+}
+
+impl PairExecutor for PooledPairExecutor {
+    fn execute_pair(&mut self, query : &'_ str, param : &PairValueEnum) -> Result<PairValueEnum, CommonError> {
+        let ret = self.get_value().execute_pair(query, param);
+        self.track(ret)
+    }
+
+    fn execute_pair_result_set(&mut self, query : &'_ str, param : &PairValueEnum) -> Result<PairResultSet, CommonError> {
+        let ret = self.get_value().execute_pair_result_set(query, param);
+        self.track(ret)
+    }
+
+    fn execute_pair_outcome(&mut self, query : &'_ str, param : &PairValueEnum) -> Result<PairExecuteOutcome, CommonError> {
+        let ret = self.get_value().execute_pair_outcome(query, param);
+        self.track(ret)
+    }
+
+    fn execute_pair_cursor<'a>(&'a mut self, query : &'_ str, param : &PairValueEnum) -> Result<Box<dyn PairRowCursor + 'a>, CommonError> {
+        let PooledPairExecutor { item, broken } = self;
+
+        match item.as_mut().unwrap().get_value().execute_pair_cursor(query, param) {
+            Ok(inner) => Ok(Box::new(TrackedRowCursor { inner, broken })),
+            Err(e) => {
+                *broken |= is_connection_error(&e);
+                Err(e)
+            }
+        }
+    }
+
+    fn execute_pair_with(&mut self, query : &'_ str, param : &PairValueEnum, options : &PairCallOptions) -> Result<PairValueEnum, CommonError> {
+        let ret = self.get_value().execute_pair_with(query, param, options);
+        self.track(ret)
+    }
+
+    fn get_current_time(&mut self) -> Result<std::time::Duration, CommonError> {
+        let ret = self.get_value().get_current_time();
+        self.track(ret)
+    }
+
+    fn ping(&mut self) -> Result<(), CommonError> {
+        let ret = self.get_value().ping();
+        self.track(ret)
+    }
+
+    fn is_closed(&mut self) -> bool {
+        self.broken || self.get_value().is_closed()
+    }
+
+    fn execute_batch(&mut self, query : &'_ str, rows : &'_ [PairValueEnum]) -> Result<Vec<u64>, CommonError> {
+        let ret = self.get_value().execute_batch(query, rows);
+        self.track(ret)
+    }
+
+    fn statement_cache_stats(&self) -> Option<PairStatementCacheStats> {
+        self.item.as_ref().and_then(|item| item.get_value_ref().statement_cache_stats())
+    }
+
+    fn in_transaction(&self) -> bool {
+        self.item.as_ref().map_or(false, |item| item.get_value_ref().in_transaction())
+    }
+
+    fn begin(&mut self) -> Result<(), CommonError> {
+        let ret = self.get_value().begin();
+        self.track(ret)
+    }
+
+    fn commit(&mut self) -> Result<(), CommonError> {
+        let ret = self.get_value().commit();
+        self.track(ret)
+    }
+
+    fn rollback(&mut self) -> Result<(), CommonError> {
+        let ret = self.get_value().rollback();
+        self.track(ret)
+    }
+}
+
+impl Drop for PooledPairExecutor {
+    fn drop(&mut self) {
+        if let Some(mut item) = self.item.take() {
+            let exec = item.get_value();
+            if exec.in_transaction() && exec.rollback().is_err() {
+                self.broken = true;
+            }
+
+            if self.broken || item.get_value().is_closed() {
+                item.dispose();
+            } else {
+                item.restoration();
+            }
+        }
+    }
+}
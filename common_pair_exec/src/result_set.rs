@@ -0,0 +1,116 @@
+use std::collections::HashMap;
+use common_err::{CommonError, gen::CommonDefaultErrorKind};
+use crate::{FromPairRow, PairRowCursor, PairValueEnum};
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PairColumnInfo {
+    pub name : String,
+    pub type_name : String,
+    pub nullable : bool
+}
+
+impl PairColumnInfo {
+    pub fn new<S : AsRef<str>, T : AsRef<str>>(name : S, type_name : T, nullable : bool) -> Self {
+        PairColumnInfo {
+            name : name.as_ref().to_string(),
+            type_name : type_name.as_ref().to_string(),
+            nullable
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PairResultSet {
+    columns : Vec<PairColumnInfo>,
+    rows : Vec<Vec<PairValueEnum>>
+}
+
+impl PairResultSet {
+    pub fn new(columns : Vec<PairColumnInfo>) -> Self {
+        PairResultSet { columns, rows : Vec::new() }
+    }
+
+    pub fn from_cursor(mut cursor : Box<dyn PairRowCursor + '_>) -> Result<Self, CommonError> {
+        let mut ret = PairResultSet::new(cursor.columns().to_vec());
+
+        while let Some(row) = cursor.next_row()? {
+            ret.push_row(row)?;
+        }
+
+        Ok(ret)
+    }
+
+    pub fn push_row(&mut self, row : Vec<PairValueEnum>) -> Result<(), CommonError> {
+        if row.len() != self.columns.len() {
+            return CommonError::new(&CommonDefaultErrorKind::NotMatchArgs,
+                                    format!("row cols count {}/{}", row.len(), self.columns.len())).to_result();
+        }
+        self.rows.push(row);
+        Ok(())
+    }
+
+    pub fn columns(&self) -> &'_ [PairColumnInfo] { self.columns.as_slice() }
+    pub fn column_names(&self) -> Vec<&'_ str> { self.columns.iter().map(|c| c.name.as_str()).collect() }
+    pub fn column_index(&self, name : &'_ str) -> Option<usize> { self.columns.iter().position(|c| c.name == name) }
+    pub fn rows(&self) -> &'_ [Vec<PairValueEnum>] { self.rows.as_slice() }
+    pub fn row_count(&self) -> usize { self.rows.len() }
+    pub fn is_empty(&self) -> bool { self.rows.is_empty() }
+
+    pub fn get(&self, row_idx : usize, col_idx : usize) -> Option<&'_ PairValueEnum> {
+        self.rows.get(row_idx).and_then(|r| r.get(col_idx))
+    }
+
+    pub fn get_by_name(&self, row_idx : usize, name : &'_ str) -> Option<&'_ PairValueEnum> {
+        self.get(row_idx, self.column_index(name)?)
+    }
+
+    pub fn decode<T : FromPairRow>(&self) -> Result<Vec<T>, CommonError> {
+        self.rows.iter().enumerate().map(|(idx, row)| {
+            T::from_pair_row(self.columns.as_slice(), row.as_slice()).map_err(|e| {
+                CommonError::extend(&CommonDefaultErrorKind::NotMatchArgs, format!("PairResultSet.decode - row {}", idx), e)
+            })
+        }).collect()
+    }
+
+    pub fn into_rows(self) -> Vec<Vec<PairValueEnum>> { self.rows }
+
+    // legacy execute_pair shape, duplicate column names keep the last column
+    pub fn into_pair_map(self) -> PairValueEnum {
+        if self.columns.is_empty() || self.rows.is_empty() {
+            return PairValueEnum::Null;
+        }
+
+        let mut cache : Vec<Vec<PairValueEnum>> = vec![Vec::with_capacity(self.rows.len()); self.columns.len()];
+        for row in self.rows {
+            for (idx, data) in row.into_iter().enumerate() {
+                cache[idx].push(data);
+            }
+        }
+
+        let mut convert_m = HashMap::with_capacity(self.columns.len());
+        for (col, data) in self.columns.into_iter().zip(cache.into_iter()) {
+            convert_m.insert(col.name, PairValueEnum::Array(data));
+        }
+
+        PairValueEnum::Map(convert_m)
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PairExecuteOutcome {
+    // None for queries and when the backend cannot report a count for the statement
+    pub rows_affected : Option<u64>,
+    pub result_set : PairResultSet
+}
+
+impl PairExecuteOutcome {
+    pub fn new(rows_affected : Option<u64>, result_set : PairResultSet) -> Self {
+        PairExecuteOutcome { rows_affected, result_set }
+    }
+}
+
+impl From<PairResultSet> for PairValueEnum {
+    fn from(value : PairResultSet) -> Self {
+        value.into_pair_map()
+    }
+}
@@ -0,0 +1,68 @@
+use std::time::Duration;
+use common_core::utils::func::generate_random_duration;
+use common_err::{CommonError, CommonErrorKind};
+use common_err::gen::CommonDefaultErrorKind;
+use crate::{is_connection_error, PairExecutor, PairExecutorPool, PooledPairExecutor};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PairRetryPolicy {
+    // including the first call
+    pub max_attempts : u32,
+    pub base_delay : Duration,
+    pub max_delay : Duration,
+    // picks the delay from 0..backoff so waiting callers spread out
+    pub jitter : bool
+}
+
+impl Default for PairRetryPolicy {
+    fn default() -> Self {
+        PairRetryPolicy { max_attempts : 3, base_delay : Duration::from_millis(50), max_delay : Duration::from_secs(2), jitter : true }
+    }
+}
+
+impl PairRetryPolicy {
+    pub fn backoff(&self, attempt : u32) -> Duration {
+        let exp = self.base_delay.saturating_mul(1u32 << attempt.saturating_sub(1).min(16));
+        let delay = exp.min(self.max_delay);
+
+        if self.jitter { generate_random_duration(delay) } else { delay }
+    }
+}
+
+// backends map timeouts, overload and rolled back conflicts to Transient, lost connections to ConnectFail
+pub fn is_transient_error(err : &CommonError) -> bool {
+    let transient = CommonDefaultErrorKind::Transient.name();
+    is_connection_error(err) || err.func_ref().iter().any(|pos| pos.3.name() == transient)
+}
+
+// every attempt checks out its own connection, one that broke is disposed by PooledPairExecutor.
+// a call that is not idempotent is only retried when no connection could be checked out,
+// after the statement was sent it may have been applied already
+pub fn with_retry<T, F>(pool : &'_ PairExecutorPool, policy : &'_ PairRetryPolicy, idempotent : bool, mut f : F) -> Result<T, CommonError>
+where F : FnMut(&mut dyn PairExecutor) -> Result<T, CommonError> {
+    let mut attempt = 0;
+
+    loop {
+        attempt += 1;
+
+        let (ret, sent) = match PooledPairExecutor::from_pool(pool) {
+            Ok(mut conn) => (f(&mut conn), true),
+            Err(e) => (Err(e), false)
+        };
+
+        let err = match ret {
+            Ok(ok) => return Ok(ok),
+            Err(e) => e
+        };
+
+        if attempt >= policy.max_attempts || !is_transient_error(&err) || (sent && !idempotent) {
+            return if attempt > 1 {
+                CommonError::extend(&CommonDefaultErrorKind::ExecuteFail, format!("with_retry - failed after {} attempts", attempt), err).to_result()
+            } else {
+                Err(err)
+            };
+        }
+
+        std::thread::sleep(policy.backoff(attempt));
+    }
+}
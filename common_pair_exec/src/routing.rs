@@ -0,0 +1,173 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+use common_err::CommonError;
+use crate::named::statement_keywords;
+use crate::{PairCallOptions, PairExecuteOutcome, PairExecutor, PairExecutorPool, PairResultSet, PairRowCursor, PairValueEnum, PooledPairExecutor};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PairRoute {
+    Primary,
+    Replica
+}
+
+const READ_KEYWORDS : [&'static str; 5] = ["select", "show", "explain", "values", "table"];
+const WRITE_KEYWORDS : [&'static str; 4] = ["insert", "update", "delete", "merge"];
+
+// plain reads go to a replica, anything that writes or locks rows goes to the primary
+pub fn classify_pair_statement(query : &'_ str) -> PairRoute {
+    let words = statement_keywords(query);
+
+    let first = match words.first() {
+        Some(first) => first.as_str(),
+        None => return PairRoute::Primary
+    };
+
+    let read = match first {
+        // a cte may wrap insert ... returning
+        "with" => !words.iter().any(|w| WRITE_KEYWORDS.contains(&w.as_str())),
+        _ => READ_KEYWORDS.contains(&first)
+    };
+    let locks = words.windows(2).any(|w| w[0] == "for" && (w[1] == "update" || w[1] == "share"));
+
+    if read && !locks { PairRoute::Replica } else { PairRoute::Primary }
+}
+
+// insert, update, delete or merge that answers with a changed row count rather than rows
+pub fn is_pair_dml_statement(query : &'_ str) -> bool {
+    let words = statement_keywords(query);
+    let is_write = |w : &String| WRITE_KEYWORDS.contains(&w.as_str());
+
+    let write = match words.first().map(|w| w.as_str()) {
+        Some("with") => words.iter().any(is_write),
+        Some(first) => WRITE_KEYWORDS.contains(&first),
+        None => false
+    };
+
+    write && !words.iter().any(|w| w == "returning")
+}
+
+pub struct PairRoutingPool {
+    primary : PairExecutorPool,
+    replicas : Vec<PairExecutorPool>,
+    next : AtomicUsize
+}
+
+impl PairRoutingPool {
+    pub fn new(primary : PairExecutorPool, replicas : Vec<PairExecutorPool>) -> Arc<Self> {
+        Arc::new(PairRoutingPool { primary, replicas, next : AtomicUsize::new(0) })
+    }
+
+    // connections are checked out on first use
+    pub fn get_executor(self : &'_ Arc<Self>) -> RoutingPairExecutor {
+        RoutingPairExecutor { pool : self.clone(), primary : None, replica : None, in_transaction : false, route : None }
+    }
+
+    // round robin, a replica without a free connection is skipped
+    fn checkout_replica(&self) -> Option<PooledPairExecutor> {
+        let len = self.replicas.len();
+        let start = self.next.fetch_add(1, Ordering::Relaxed);
+
+        (0..len).find_map(|i| PooledPairExecutor::from_pool(&self.replicas[(start + i) % len]).ok())
+    }
+}
+
+pub struct RoutingPairExecutor {
+    pool : Arc<PairRoutingPool>,
+    primary : Option<PooledPairExecutor>,
+    replica : Option<PooledPairExecutor>,
+    in_transaction : bool,
+    route : Option<PairRoute>
+}
+
+impl RoutingPairExecutor {
+    // overrides the statement classification until reset with None
+    pub fn set_route(&mut self, route : Option<PairRoute>) {
+        self.route = route;
+    }
+
+    pub fn resolve_route(&self, query : &'_ str) -> PairRoute {
+        if self.in_transaction {
+            return PairRoute::Primary;
+        }
+        self.route.unwrap_or_else(|| classify_pair_statement(query))
+    }
+
+    // falls back to the primary when no replica can hand out a connection
+    fn executor(&mut self, route : PairRoute) -> Result<&'_ mut PooledPairExecutor, CommonError> {
+        if route == PairRoute::Replica {
+            if self.replica.is_none() {
+                self.replica = self.pool.checkout_replica();
+            }
+            if self.replica.is_some() {
+                return Ok(self.replica.as_mut().unwrap());
+            }
+        }
+
+        if self.primary.is_none() {
+            self.primary = Some(PooledPairExecutor::from_pool(&self.pool.primary)?);
+        }
+        Ok(self.primary.as_mut().unwrap())
+    }
+
+    fn routed(&mut self, query : &'_ str) -> Result<&'_ mut PooledPairExecutor, CommonError> {
+        let route = self.resolve_route(query);
+        self.executor(route)
+    }
+}
+
+impl PairExecutor for RoutingPairExecutor {
+    fn execute_pair(&mut self, query : &'_ str, param : &PairValueEnum) -> Result<PairValueEnum, CommonError> {
+        self.routed(query)?.execute_pair(query, param)
+    }
+
+    fn execute_pair_result_set(&mut self, query : &'_ str, param : &PairValueEnum) -> Result<PairResultSet, CommonError> {
+        self.routed(query)?.execute_pair_result_set(query, param)
+    }
+
+    fn execute_pair_outcome(&mut self, query : &'_ str, param : &PairValueEnum) -> Result<PairExecuteOutcome, CommonError> {
+        self.routed(query)?.execute_pair_outcome(query, param)
+    }
+
+    fn execute_pair_cursor<'a>(&'a mut self, query : &'_ str, param : &PairValueEnum) -> Result<Box<dyn PairRowCursor + 'a>, CommonError> {
+        self.routed(query)?.execute_pair_cursor(query, param)
+    }
+
+    fn execute_pair_with(&mut self, query : &'_ str, param : &PairValueEnum, options : &PairCallOptions) -> Result<PairValueEnum, CommonError> {
+        self.routed(query)?.execute_pair_with(query, param, options)
+    }
+
+    fn get_current_time(&mut self) -> Result<Duration, CommonError> {
+        self.executor(PairRoute::Primary)?.get_current_time()
+    }
+
+    fn ping(&mut self) -> Result<(), CommonError> {
+        self.executor(PairRoute::Primary)?.ping()
+    }
+
+    fn execute_batch(&mut self, query : &'_ str, rows : &'_ [PairValueEnum]) -> Result<Vec<u64>, CommonError> {
+        self.executor(PairRoute::Primary)?.execute_batch(query, rows)
+    }
+
+    fn in_transaction(&self) -> bool {
+        self.in_transaction
+    }
+
+    fn begin(&mut self) -> Result<(), CommonError> {
+        self.executor(PairRoute::Primary)?.begin()?;
+        self.in_transaction = true;
+        Ok(())
+    }
+
+    fn commit(&mut self) -> Result<(), CommonError> {
+        let ret = self.executor(PairRoute::Primary)?.commit();
+        self.in_transaction = false;
+        ret
+    }
+
+    fn rollback(&mut self) -> Result<(), CommonError> {
+        let ret = self.executor(PairRoute::Primary)?.rollback();
+        self.in_transaction = false;
+        ret
+    }
+}
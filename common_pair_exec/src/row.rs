@@ -0,0 +1,316 @@
+use std::net::IpAddr;
+use chrono::{DateTime, FixedOffset, NaiveDate, NaiveDateTime, NaiveTime, Utc};
+use common_err::{CommonError, gen::CommonDefaultErrorKind};
+use crate::{PairColumnInfo, PairDecimal, PairValueEnum};
+
+pub trait FromPairValue : Sized {
+    fn from_pair_value(value : &'_ PairValueEnum) -> Result<Self, CommonError>;
+}
+
+pub trait ToPairValue {
+    fn to_pair_value(&self) -> PairValueEnum;
+}
+
+pub trait FromPairRow : Sized {
+    fn from_pair_row(columns : &'_ [PairColumnInfo], row : &'_ [PairValueEnum]) -> Result<Self, CommonError>;
+}
+
+pub trait ToPairParams {
+    // positional parameters in field order
+    fn to_pair_params(&self) -> PairValueEnum;
+    // Map keyed by column name, for :name queries
+    fn to_pair_named_params(&self) -> PairValueEnum;
+}
+
+fn not_match<T>(target : &'_ str, value : &'_ PairValueEnum) -> Result<T, CommonError> {
+    CommonError::new(&CommonDefaultErrorKind::NotMatchArgs,
+                     format!("FromPairValue - can't convert {} to {}", value.type_name(), target)).to_result()
+}
+
+fn parse_text<T, E : std::fmt::Display>(target : &'_ str, text : &'_ str, ret : Result<T, E>) -> Result<T, CommonError> {
+    ret.map_err(|e| {
+        CommonError::new(&CommonDefaultErrorKind::ParsingFail, format!("FromPairValue - can't parse {:.64} to {} - {}", text, target, e))
+    })
+}
+
+// used by the FromPairRow derive
+pub fn get_pair_row_column<T : FromPairValue>(type_name : &'_ str, columns : &'_ [PairColumnInfo],
+                                              row : &'_ [PairValueEnum], name : &'_ str) -> Result<T, CommonError> {
+    let idx = columns.iter().position(|c| c.name == name).ok_or_else(|| {
+        CommonError::new(&CommonDefaultErrorKind::NoData, format!("{} - column '{}' not exists", type_name, name))
+    })?;
+
+    let value = row.get(idx).ok_or_else(|| {
+        CommonError::new(&CommonDefaultErrorKind::NotMatchArgs, format!("{} - row cols count {}/{}", type_name, row.len(), columns.len()))
+    })?;
+
+    T::from_pair_value(value).map_err(|e| {
+        CommonError::extend(&CommonDefaultErrorKind::NotMatchArgs, format!("{} - column '{}'", type_name, name), e)
+    })
+}
+
+macro_rules! impl_pair_int {
+    ($t : ty) => {
+        impl FromPairValue for $t {
+            fn from_pair_value(value : &'_ PairValueEnum) -> Result<Self, CommonError> {
+                let wide : i128 = match value {
+                    PairValueEnum::TinyInt(i) => *i as i128,
+                    PairValueEnum::SmallInt(i) => *i as i128,
+                    PairValueEnum::Int(i) => *i as i128,
+                    PairValueEnum::BigInt(i) => *i as i128,
+                    PairValueEnum::Decimal(d) if d.scale() == 0 => d.value(),
+                    _ => return not_match(stringify!($t), value)
+                };
+
+                <$t>::try_from(wide).map_err(|_| {
+                    CommonError::new(&CommonDefaultErrorKind::OverFlowMemory,
+                                     format!("FromPairValue - {} overflows {}", wide, stringify!($t)))
+                })
+            }
+        }
+    };
+    ($t : ty, $variant : ident) => {
+        impl_pair_int!($t);
+
+        impl ToPairValue for $t {
+            fn to_pair_value(&self) -> PairValueEnum {
+                PairValueEnum::$variant((*self).into())
+            }
+        }
+    };
+}
+
+impl_pair_int!(i8, TinyInt);
+impl_pair_int!(i16, SmallInt);
+impl_pair_int!(i32, Int);
+impl_pair_int!(i64, BigInt);
+impl_pair_int!(u8, SmallInt);
+impl_pair_int!(u16, Int);
+impl_pair_int!(u32, BigInt);
+impl_pair_int!(u64);
+
+impl ToPairValue for u64 {
+    fn to_pair_value(&self) -> PairValueEnum {
+        match i64::try_from(*self) {
+            Ok(i) => PairValueEnum::BigInt(i),
+            Err(_) => PairValueEnum::Decimal(PairDecimal::new(*self as i128, 0))
+        }
+    }
+}
+
+impl FromPairValue for f64 {
+    fn from_pair_value(value : &'_ PairValueEnum) -> Result<Self, CommonError> {
+        match value {
+            PairValueEnum::Double(f) => Ok(*f),
+            PairValueEnum::Float(f) => Ok(*f as f64),
+            PairValueEnum::TinyInt(i) => Ok(*i as f64),
+            PairValueEnum::SmallInt(i) => Ok(*i as f64),
+            PairValueEnum::Int(i) => Ok(*i as f64),
+            PairValueEnum::BigInt(i) => Ok(*i as f64),
+            PairValueEnum::Decimal(d) => Ok(d.to_f64()),
+            _ => not_match("f64", value)
+        }
+    }
+}
+
+impl FromPairValue for f32 {
+    fn from_pair_value(value : &'_ PairValueEnum) -> Result<Self, CommonError> {
+        match value {
+            PairValueEnum::Float(f) => Ok(*f),
+            _ => f64::from_pair_value(value).map(|f| f as f32)
+        }
+    }
+}
+
+impl FromPairValue for bool {
+    fn from_pair_value(value : &'_ PairValueEnum) -> Result<Self, CommonError> {
+        match value {
+            PairValueEnum::Bool(b) => Ok(*b),
+            // drivers without a boolean type answer 0/1
+            PairValueEnum::TinyInt(_) | PairValueEnum::SmallInt(_) | PairValueEnum::Int(_) | PairValueEnum::BigInt(_) => {
+                match i64::from_pair_value(value)? {
+                    0 => Ok(false),
+                    1 => Ok(true),
+                    _ => not_match("bool", value)
+                }
+            },
+            _ => not_match("bool", value)
+        }
+    }
+}
+
+impl FromPairValue for String {
+    fn from_pair_value(value : &'_ PairValueEnum) -> Result<Self, CommonError> {
+        match value {
+            PairValueEnum::String(s) | PairValueEnum::Json(s) => Ok(s.clone()),
+            _ => not_match("String", value)
+        }
+    }
+}
+
+impl FromPairValue for Vec<u8> {
+    fn from_pair_value(value : &'_ PairValueEnum) -> Result<Self, CommonError> {
+        match value {
+            PairValueEnum::Bin(b) => Ok(b.clone()),
+            PairValueEnum::String(s) => Ok(s.as_bytes().to_vec()),
+            _ => not_match("Vec<u8>", value)
+        }
+    }
+}
+
+impl FromPairValue for DateTime<FixedOffset> {
+    fn from_pair_value(value : &'_ PairValueEnum) -> Result<Self, CommonError> {
+        match value {
+            PairValueEnum::Timestamp(t) => Ok(*t),
+            PairValueEnum::String(s) => parse_text("DateTime", s, DateTime::parse_from_rfc3339(s)),
+            _ => not_match("DateTime", value)
+        }
+    }
+}
+
+impl FromPairValue for DateTime<Utc> {
+    fn from_pair_value(value : &'_ PairValueEnum) -> Result<Self, CommonError> {
+        DateTime::<FixedOffset>::from_pair_value(value).map(|t| t.with_timezone(&Utc))
+    }
+}
+
+impl FromPairValue for NaiveDateTime {
+    fn from_pair_value(value : &'_ PairValueEnum) -> Result<Self, CommonError> {
+        DateTime::<FixedOffset>::from_pair_value(value).map(|t| t.naive_utc())
+    }
+}
+
+impl FromPairValue for NaiveDate {
+    fn from_pair_value(value : &'_ PairValueEnum) -> Result<Self, CommonError> {
+        match value {
+            PairValueEnum::Date(d) => Ok(*d),
+            PairValueEnum::String(s) => parse_text("NaiveDate", s, s.parse::<NaiveDate>()),
+            _ => not_match("NaiveDate", value)
+        }
+    }
+}
+
+impl FromPairValue for NaiveTime {
+    fn from_pair_value(value : &'_ PairValueEnum) -> Result<Self, CommonError> {
+        match value {
+            PairValueEnum::Time(t) => Ok(*t),
+            PairValueEnum::String(s) => parse_text("NaiveTime", s, s.parse::<NaiveTime>()),
+            _ => not_match("NaiveTime", value)
+        }
+    }
+}
+
+impl FromPairValue for uuid::Uuid {
+    fn from_pair_value(value : &'_ PairValueEnum) -> Result<Self, CommonError> {
+        match value {
+            PairValueEnum::Uuid(u) => Ok(*u),
+            PairValueEnum::String(s) => parse_text("Uuid", s, uuid::Uuid::parse_str(s)),
+            _ => not_match("Uuid", value)
+        }
+    }
+}
+
+impl FromPairValue for PairDecimal {
+    fn from_pair_value(value : &'_ PairValueEnum) -> Result<Self, CommonError> {
+        match value {
+            PairValueEnum::Decimal(d) => Ok(*d),
+            PairValueEnum::String(s) => s.parse::<PairDecimal>(),
+            PairValueEnum::TinyInt(_) | PairValueEnum::SmallInt(_) | PairValueEnum::Int(_) | PairValueEnum::BigInt(_) => {
+                Ok(PairDecimal::new(i64::from_pair_value(value)? as i128, 0))
+            },
+            _ => not_match("PairDecimal", value)
+        }
+    }
+}
+
+impl FromPairValue for IpAddr {
+    fn from_pair_value(value : &'_ PairValueEnum) -> Result<Self, CommonError> {
+        match value {
+            PairValueEnum::Inet(i) => Ok(*i),
+            PairValueEnum::String(s) => parse_text("IpAddr", s, s.parse::<IpAddr>()),
+            _ => not_match("IpAddr", value)
+        }
+    }
+}
+
+impl FromPairValue for PairValueEnum {
+    fn from_pair_value(value : &'_ PairValueEnum) -> Result<Self, CommonError> {
+        Ok(value.clone())
+    }
+}
+
+impl<T : FromPairValue> FromPairValue for Option<T> {
+    fn from_pair_value(value : &'_ PairValueEnum) -> Result<Self, CommonError> {
+        match value {
+            PairValueEnum::Null => Ok(None),
+            _ => T::from_pair_value(value).map(Some)
+        }
+    }
+}
+
+macro_rules! impl_to_pair_value {
+    ($t : ty, $variant : ident) => {
+        impl ToPairValue for $t {
+            fn to_pair_value(&self) -> PairValueEnum {
+                PairValueEnum::$variant(self.clone())
+            }
+        }
+    };
+}
+
+impl_to_pair_value!(f64, Double);
+impl_to_pair_value!(f32, Float);
+impl_to_pair_value!(bool, Bool);
+impl_to_pair_value!(String, String);
+impl_to_pair_value!(Vec<u8>, Bin);
+impl_to_pair_value!(DateTime<FixedOffset>, Timestamp);
+impl_to_pair_value!(NaiveDate, Date);
+impl_to_pair_value!(NaiveTime, Time);
+impl_to_pair_value!(uuid::Uuid, Uuid);
+impl_to_pair_value!(PairDecimal, Decimal);
+impl_to_pair_value!(IpAddr, Inet);
+
+impl ToPairValue for str {
+    fn to_pair_value(&self) -> PairValueEnum {
+        PairValueEnum::String(self.to_string())
+    }
+}
+
+impl ToPairValue for DateTime<Utc> {
+    fn to_pair_value(&self) -> PairValueEnum {
+        PairValueEnum::Timestamp(self.fixed_offset())
+    }
+}
+
+impl ToPairValue for NaiveDateTime {
+    fn to_pair_value(&self) -> PairValueEnum {
+        PairValueEnum::Timestamp(self.and_utc().fixed_offset())
+    }
+}
+
+impl ToPairValue for PairValueEnum {
+    fn to_pair_value(&self) -> PairValueEnum {
+        self.clone()
+    }
+}
+
+impl<T : ToPairValue + ?Sized> ToPairValue for &'_ T {
+    fn to_pair_value(&self) -> PairValueEnum {
+        (**self).to_pair_value()
+    }
+}
+
+impl<T : ToPairValue> ToPairValue for Option<T> {
+    fn to_pair_value(&self) -> PairValueEnum {
+        match self {
+            Some(v) => v.to_pair_value(),
+            None => PairValueEnum::Null
+        }
+    }
+}
+
+// used by the FromPairValue derive on enums
+pub fn unknown_pair_variant<T>(type_name : &'_ str, text : &'_ str) -> Result<T, CommonError> {
+    CommonError::new(&CommonDefaultErrorKind::NotMatchArgs,
+                     format!("{} - unknown variant '{:.64}'", type_name, text)).to_result()
+}
@@ -0,0 +1,145 @@
+use std::sync::Arc;
+use rustls::{CertificateError, ClientConfig, DigitallySignedStruct, RootCertStore, SignatureScheme};
+use rustls::client::WebPkiServerVerifier;
+use rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
+use rustls::crypto::CryptoProvider;
+use rustls::pki_types::{CertificateDer, PrivateKeyDer, ServerName, UnixTime};
+use rustls::pki_types::pem::PemObject;
+use common_err::CommonError;
+use common_err::gen::CommonDefaultErrorKind;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum PairTlsVerify {
+    // chain and host name
+    #[default]
+    Full,
+    // chain only, for certificates issued to another name than the address
+    CaOnly,
+    // accepts any certificate, for local tests
+    None
+}
+
+// files are PEM, without a CA bundle the webpki roots are trusted
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PairTlsOptions {
+    pub ca_file : Option<String>,
+    pub cert_file : Option<String>,
+    pub key_file : Option<String>,
+    pub verify : PairTlsVerify,
+    // sent as SNI and verified instead of the host of the address
+    // scylla dials nodes by ip, its driver verifies the name but sends no SNI
+    // redis always sends and verifies the address host, any other name is rejected, use CaOnly there
+    pub server_name : Option<String>
+}
+
+fn tls_error<E : std::fmt::Display>(what : &'_ str, path : &'_ str, e : E) -> CommonError {
+    CommonError::new(&CommonDefaultErrorKind::InitFailed, format!("PairTlsOptions - {} - {} - {}", what, path, e))
+}
+
+// the workspace enables more than one rustls backend, so the provider is always named
+pub fn pair_crypto_provider() -> Arc<CryptoProvider> {
+    Arc::new(rustls::crypto::ring::default_provider())
+}
+
+// for drivers that build their rustls config with the process default provider
+pub fn install_pair_crypto_provider() {
+    let _ = rustls::crypto::ring::default_provider().install_default();
+}
+
+#[derive(Debug)]
+struct PairCertVerifier {
+    inner : Arc<WebPkiServerVerifier>,
+    verify : PairTlsVerify,
+    server_name : Option<ServerName<'static>>
+}
+
+impl ServerCertVerifier for PairCertVerifier {
+    fn verify_server_cert(&self, end_entity : &CertificateDer<'_>, intermediates : &[CertificateDer<'_>], server_name : &ServerName<'_>,
+                          ocsp_response : &[u8], now : UnixTime) -> Result<ServerCertVerified, rustls::Error> {
+        let server_name = self.server_name.as_ref().unwrap_or(server_name);
+
+        match (self.verify, self.inner.verify_server_cert(end_entity, intermediates, server_name, ocsp_response, now)) {
+            (PairTlsVerify::None, _) => Ok(ServerCertVerified::assertion()),
+            (PairTlsVerify::CaOnly, Err(rustls::Error::InvalidCertificate(CertificateError::NotValidForName | CertificateError::NotValidForNameContext { .. }))) => {
+                Ok(ServerCertVerified::assertion())
+            },
+            (_, ret) => ret
+        }
+    }
+
+    fn verify_tls12_signature(&self, message : &[u8], cert : &CertificateDer<'_>, dss : &DigitallySignedStruct) -> Result<HandshakeSignatureValid, rustls::Error> {
+        self.inner.verify_tls12_signature(message, cert, dss)
+    }
+
+    fn verify_tls13_signature(&self, message : &[u8], cert : &CertificateDer<'_>, dss : &DigitallySignedStruct) -> Result<HandshakeSignatureValid, rustls::Error> {
+        self.inner.verify_tls13_signature(message, cert, dss)
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.inner.supported_verify_schemes()
+    }
+}
+
+impl PairTlsOptions {
+    pub fn read_file(path : &'_ str) -> Result<Vec<u8>, CommonError> {
+        std::fs::read(path).map_err(|e| tls_error("read", path, e))
+    }
+
+    fn read_certs(path : &'_ str) -> Result<Vec<CertificateDer<'static>>, CommonError> {
+        let certs = CertificateDer::pem_file_iter(path).map_err(|e| tls_error("cert", path, e))?
+            .collect::<Result<Vec<_>, _>>().map_err(|e| tls_error("cert", path, e))?;
+
+        if certs.is_empty() {
+            return Err(tls_error("cert", path, "no certificate"));
+        }
+        Ok(certs)
+    }
+
+    // both files or neither
+    pub fn client_cert_files(&self) -> Result<Option<(&'_ str, &'_ str)>, CommonError> {
+        match (self.cert_file.as_deref(), self.key_file.as_deref()) {
+            (Some(cert), Some(key)) => Ok(Some((cert, key))),
+            (None, None) => Ok(None),
+            _ => CommonError::new(&CommonDefaultErrorKind::InitFailed, "PairTlsOptions - client cert and key must be set together").to_result()
+        }
+    }
+
+    fn root_store(&self) -> Result<RootCertStore, CommonError> {
+        let mut roots = RootCertStore::empty();
+        match self.ca_file.as_deref() {
+            Some(path) => {
+                for cert in Self::read_certs(path)? {
+                    roots.add(cert).map_err(|e| tls_error("ca", path, e))?;
+                }
+            },
+            None => roots.extend(webpki_roots::TLS_SERVER_ROOTS.iter().cloned())
+        }
+        Ok(roots)
+    }
+
+    pub fn client_config(&self) -> Result<ClientConfig, CommonError> {
+        let provider = pair_crypto_provider();
+
+        let inner = WebPkiServerVerifier::builder_with_provider(Arc::new(self.root_store()?), provider.clone()).build()
+            .map_err(|e| tls_error("verifier", self.ca_file.as_deref().unwrap_or("webpki roots"), e))?;
+
+        let server_name = match self.server_name.as_deref() {
+            Some(name) => Some(ServerName::try_from(name.to_string()).map_err(|e| tls_error("server_name", name, e))?),
+            None => None
+        };
+
+        let builder = ClientConfig::builder_with_provider(provider)
+            .with_safe_default_protocol_versions()
+            .map_err(|e| tls_error("protocol", "default", e))?
+            .dangerous()
+            .with_custom_certificate_verifier(Arc::new(PairCertVerifier { inner, verify : self.verify, server_name }));
+
+        match self.client_cert_files()? {
+            Some((cert, key)) => {
+                let key_der = PrivateKeyDer::from_pem_file(key).map_err(|e| tls_error("key", key, e))?;
+                builder.with_client_auth_cert(Self::read_certs(cert)?, key_der).map_err(|e| tls_error("client auth", cert, e))
+            },
+            None => Ok(builder.with_no_client_auth())
+        }
+    }
+}
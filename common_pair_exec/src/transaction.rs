@@ -0,0 +1,32 @@
+use std::panic::{catch_unwind, resume_unwind, AssertUnwindSafe};
+use common_err::{CommonError, gen::CommonDefaultErrorKind};
+use crate::PairExecutor;
+
+pub fn with_transaction<T, F>(conn : &mut dyn PairExecutor, f : F) -> Result<T, CommonError>
+where F : FnOnce(&mut dyn PairExecutor) -> Result<T, CommonError> {
+    conn.begin().map_err(|e| {
+        CommonError::extend(&CommonDefaultErrorKind::ExecuteFail, "with_transaction - begin failed", e)
+    })?;
+
+    let ret = catch_unwind(AssertUnwindSafe(|| f(&mut *conn)));
+
+    match ret {
+        Ok(Ok(ok)) => {
+            conn.commit().map_err(|e| {
+                CommonError::extend(&CommonDefaultErrorKind::ExecuteFail, "with_transaction - commit failed", e)
+            })?;
+            Ok(ok)
+        },
+        Ok(Err(err)) => {
+            if let Err(rollback_err) = conn.rollback() {
+                return CommonError::extend(&CommonDefaultErrorKind::ExecuteFail,
+                                           format!("with_transaction - rollback failed : {}", rollback_err.get_cause()), err).to_result();
+            }
+            Err(err)
+        },
+        Err(panic) => {
+            let _ = conn.rollback();
+            resume_unwind(panic)
+        }
+    }
+}
@@ -0,0 +1,92 @@
+use std::time::Duration;
+use common_err::CommonError;
+use common_pair_exec::{create_blocking_async_pair_pool, AsyncPairExecutor, BlockingPairExecutor, PairColumnInfo, PairExecutor,
+                       PairRowCursor, PairValueEnum};
+
+struct EchoCursor {
+    cols : Vec<PairColumnInfo>,
+    row : Option<Vec<PairValueEnum>>
+}
+
+impl PairRowCursor for EchoCursor {
+    fn columns(&self) -> &'_ [PairColumnInfo] {
+        self.cols.as_slice()
+    }
+
+    fn next_row(&mut self) -> Result<Option<Vec<PairValueEnum>>, CommonError> {
+        Ok(self.row.take())
+    }
+}
+
+// answers every query with one row holding the query text and the calling thread name, "slow" takes 200ms
+struct EchoExecutor {
+    in_transaction : bool
+}
+
+impl PairExecutor for EchoExecutor {
+    fn execute_pair_cursor<'a>(&'a mut self, query : &'_ str, _param : &PairValueEnum) -> Result<Box<dyn PairRowCursor + 'a>, CommonError> {
+        if query == "slow" {
+            std::thread::sleep(Duration::from_millis(200));
+        }
+        let thread = std::thread::current().name().unwrap_or("").to_string();
+        Ok(Box::new(EchoCursor {
+            cols : vec![PairColumnInfo::new("query", "text", false), PairColumnInfo::new("thread", "text", false)],
+            row : Some(vec![PairValueEnum::String(query.to_string()), PairValueEnum::String(thread)])
+        }))
+    }
+
+    fn get_current_time(&mut self) -> Result<Duration, CommonError> {
+        Ok(Duration::from_secs(1))
+    }
+
+    fn begin(&mut self) -> Result<(), CommonError> {
+        self.in_transaction = true;
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn test_blocking_executor() -> Result<(), CommonError> {
+    let mut conn = BlockingPairExecutor::new(Box::new(EchoExecutor { in_transaction : false }));
+
+    let ret = conn.execute_pair_result_set("select 1", &PairValueEnum::Null).await?;
+    assert_eq!(ret.get(0, 0), Some(&PairValueEnum::String("select 1".to_string())));
+    assert_ne!(ret.get(0, 1), Some(&PairValueEnum::String(std::thread::current().name().unwrap_or("").to_string())));
+
+    assert_eq!(conn.get_current_time().await?, Duration::from_secs(1));
+    conn.begin().await?;
+    assert!(conn.commit().await.is_err());
+    assert!(conn.execute_batch("insert", &[]).await.is_err());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_blocking_executor_cancelled() -> Result<(), CommonError> {
+    let mut conn = BlockingPairExecutor::new(Box::new(EchoExecutor { in_transaction : false }));
+
+    // the dropped call still finishes on the blocking thread, the next one waits for it
+    assert!(tokio::time::timeout(Duration::from_millis(10), conn.execute_pair("slow", &PairValueEnum::Null)).await.is_err());
+    let ret = conn.execute_pair_result_set("select 1", &PairValueEnum::Null).await?;
+    assert_eq!(ret.get(0, 0), Some(&PairValueEnum::String("select 1".to_string())));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_blocking_pool() -> Result<(), CommonError> {
+    let p = create_blocking_async_pair_pool("test".to_string(), Box::new(|_ : ()| {
+        Ok(Box::new(EchoExecutor { in_transaction : false }) as Box<dyn PairExecutor + Send>)
+    }), 1);
+
+    {
+        let mut item = p.get_owned(()).await?;
+        let ret = item.get_value().execute_pair("select 2", &PairValueEnum::Null).await?;
+        assert!(matches!(ret, PairValueEnum::Map(_)));
+    }
+
+    let _item = p.get_owned(()).await?;
+    assert_eq!(p.alloc_size(), 1);
+
+    Ok(())
+}
@@ -0,0 +1,163 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::time::Duration;
+use common_core::collection::observer::ThreadSafeObserver;
+use common_core::collection::pool::{get_thread_safe_pool, PoolKind};
+use common_err::{CommonError, CommonErrorKind};
+use common_err::gen::CommonDefaultErrorKind;
+use common_pair_exec::{PairCircuitBreaker, PairCircuitOptions, PairCircuitState, PairExecutor, PairExecutorPool, PairRowCursor, PairValueEnum};
+
+struct EchoExecutor;
+
+impl PairExecutor for EchoExecutor {
+    fn execute_pair_cursor<'a>(&'a mut self, _query : &'_ str, _param : &PairValueEnum) -> Result<Box<dyn PairRowCursor + 'a>, CommonError> {
+        CommonError::new(&CommonDefaultErrorKind::NoSupport, "cursor").to_result()
+    }
+
+    fn execute_pair(&mut self, query : &'_ str, _param : &PairValueEnum) -> Result<PairValueEnum, CommonError> {
+        if query == "bad" {
+            return CommonError::new(&CommonDefaultErrorKind::ExecuteFail, "syntax error").to_result();
+        }
+        Ok(PairValueEnum::String(query.to_string()))
+    }
+
+    fn get_current_time(&mut self) -> Result<Duration, CommonError> {
+        Ok(Duration::from_secs(1))
+    }
+}
+
+// the generator stands in for a connect that fails while `down` is set
+fn flaky_pool(down : Arc<AtomicBool>, connects : Arc<AtomicUsize>) -> PairExecutorPool {
+    get_thread_safe_pool("breaker".to_string(), Box::new(move |_ : ()| {
+        connects.fetch_add(1, Ordering::SeqCst);
+        if down.load(Ordering::SeqCst) {
+            return CommonError::new(&CommonDefaultErrorKind::ConnectFail, "connection refused").to_result();
+        }
+        Ok(Box::new(EchoExecutor) as Box<dyn PairExecutor + Send>)
+    }), 2, PoolKind::Owned)
+}
+
+fn has_kind(err : &CommonError, kind : &CommonDefaultErrorKind) -> bool {
+    err.func_ref().iter().any(|pos| pos.3.name() == kind.name())
+}
+
+#[test]
+fn test_breaker_open_and_recover() -> Result<(), CommonError> {
+    let (down, connects) = (Arc::new(AtomicBool::new(true)), Arc::new(AtomicUsize::new(0)));
+    let options = PairCircuitOptions { failure_threshold : 3, cool_down : Duration::from_millis(100), ..Default::default() };
+    let breaker = PairCircuitBreaker::new("pg".to_string(), flaky_pool(down.clone(), connects.clone()), options);
+    let observer = ThreadSafeObserver::subscribe(breaker.subject());
+
+    for _ in 0..3 {
+        let err = breaker.get_executor().err().unwrap();
+        assert!(has_kind(&err, &CommonDefaultErrorKind::ConnectFail));
+    }
+    assert_eq!(breaker.state(), PairCircuitState::Open);
+    assert_eq!(observer.update(), Some(PairCircuitState::Open));
+
+    // rejected without reaching the generator
+    let err = breaker.call(|conn| conn.execute_pair("select", &PairValueEnum::Null)).unwrap_err();
+    assert!(has_kind(&err, &CommonDefaultErrorKind::CircuitOpen));
+    assert_eq!(connects.load(Ordering::SeqCst), 3);
+
+    // a failed probe opens the circuit again
+    std::thread::sleep(Duration::from_millis(120));
+    assert!(breaker.get_executor().is_err());
+    assert_eq!(connects.load(Ordering::SeqCst), 4);
+    assert_eq!(breaker.state(), PairCircuitState::Open);
+
+    down.store(false, Ordering::SeqCst);
+    std::thread::sleep(Duration::from_millis(120));
+    let ret = breaker.call(|conn| conn.execute_pair("select", &PairValueEnum::Null))?;
+    assert_eq!(ret, PairValueEnum::String("select".to_string()));
+    assert_eq!(breaker.state(), PairCircuitState::Closed);
+    assert_eq!(observer.update(), Some(PairCircuitState::Closed));
+    assert_eq!(observer.update(), None);
+
+    Ok(())
+}
+
+#[test]
+fn test_breaker_half_open_probe() -> Result<(), CommonError> {
+    let (down, connects) = (Arc::new(AtomicBool::new(true)), Arc::new(AtomicUsize::new(0)));
+    let options = PairCircuitOptions { failure_threshold : 1, cool_down : Duration::from_millis(50), half_open_probes : 1 };
+    let breaker = PairCircuitBreaker::new("redis".to_string(), flaky_pool(down.clone(), connects.clone()), options);
+
+    assert!(breaker.get_executor().is_err());
+    assert_eq!(breaker.state(), PairCircuitState::Open);
+
+    down.store(false, Ordering::SeqCst);
+    std::thread::sleep(Duration::from_millis(60));
+
+    let observer = ThreadSafeObserver::subscribe(breaker.subject());
+    let ret = breaker.call(|conn| {
+        // the probe holds the only slot
+        let err = breaker.get_executor().err().unwrap();
+        assert!(has_kind(&err, &CommonDefaultErrorKind::CircuitOpen));
+        assert_eq!(breaker.state(), PairCircuitState::HalfOpen);
+        conn.execute_pair("probe", &PairValueEnum::Null)
+    })?;
+    assert_eq!(ret, PairValueEnum::String("probe".to_string()));
+    assert_eq!(breaker.state(), PairCircuitState::Closed);
+    assert_eq!(observer.update(), Some(PairCircuitState::Closed));
+
+    Ok(())
+}
+
+#[test]
+fn test_breaker_ignores_server_errors() -> Result<(), CommonError> {
+    let (down, connects) = (Arc::new(AtomicBool::new(false)), Arc::new(AtomicUsize::new(0)));
+    let options = PairCircuitOptions { failure_threshold : 2, ..Default::default() };
+    let breaker = PairCircuitBreaker::new("scylla".to_string(), flaky_pool(down, connects), options);
+
+    for _ in 0..4 {
+        let err = breaker.call(|conn| conn.execute_pair("bad", &PairValueEnum::Null)).unwrap_err();
+        assert!(has_kind(&err, &CommonDefaultErrorKind::ExecuteFail));
+    }
+    assert_eq!(breaker.state(), PairCircuitState::Closed);
+
+    Ok(())
+}
+
+#[test]
+fn test_breaker_probe_server_error() -> Result<(), CommonError> {
+    let (down, connects) = (Arc::new(AtomicBool::new(true)), Arc::new(AtomicUsize::new(0)));
+    let options = PairCircuitOptions { failure_threshold : 1, cool_down : Duration::from_millis(50), half_open_probes : 1 };
+    let breaker = PairCircuitBreaker::new("pg".to_string(), flaky_pool(down.clone(), connects), options);
+
+    assert!(breaker.get_executor().is_err());
+    assert_eq!(breaker.state(), PairCircuitState::Open);
+
+    // the server answered the probe with an error, it is up
+    down.store(false, Ordering::SeqCst);
+    std::thread::sleep(Duration::from_millis(60));
+    let err = breaker.call(|conn| conn.execute_pair("bad", &PairValueEnum::Null)).unwrap_err();
+    assert!(has_kind(&err, &CommonDefaultErrorKind::ExecuteFail));
+    assert_eq!(breaker.state(), PairCircuitState::Closed);
+
+    Ok(())
+}
+
+#[test]
+fn test_breaker_probe_panic() -> Result<(), CommonError> {
+    let (down, connects) = (Arc::new(AtomicBool::new(true)), Arc::new(AtomicUsize::new(0)));
+    let options = PairCircuitOptions { failure_threshold : 1, cool_down : Duration::from_millis(50), half_open_probes : 1 };
+    let breaker = PairCircuitBreaker::new("odbc".to_string(), flaky_pool(down.clone(), connects), options);
+
+    assert!(breaker.get_executor().is_err());
+
+    down.store(false, Ordering::SeqCst);
+    std::thread::sleep(Duration::from_millis(60));
+    let ret = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        breaker.call(|_| -> Result<(), CommonError> { panic!("probe panicked") })
+    }));
+    assert!(ret.is_err());
+    assert_eq!(breaker.state(), PairCircuitState::HalfOpen);
+
+    // the slot of the panicked probe is free again
+    let ret = breaker.call(|conn| conn.execute_pair("probe", &PairValueEnum::Null))?;
+    assert_eq!(ret, PairValueEnum::String("probe".to_string()));
+    assert_eq!(breaker.state(), PairCircuitState::Closed);
+
+    Ok(())
+}
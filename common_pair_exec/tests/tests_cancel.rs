@@ -0,0 +1,126 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+use common_err::{CommonError, CommonErrorKind};
+use common_err::gen::CommonDefaultErrorKind;
+use common_pair_exec::{PairCallOptions, PairCancelHandle, PairExecutor, PairRowCursor, PairValueEnum};
+
+// "sleep" runs until the registered canceller interrupts it, like a long query on the server
+struct SleepExecutor;
+
+impl PairExecutor for SleepExecutor {
+    fn execute_pair_cursor<'a>(&'a mut self, _query : &'_ str, _param : &PairValueEnum) -> Result<Box<dyn PairRowCursor + 'a>, CommonError> {
+        CommonError::new(&CommonDefaultErrorKind::NoSupport, "cursor").to_result()
+    }
+
+    fn execute_pair(&mut self, query : &'_ str, _param : &PairValueEnum) -> Result<PairValueEnum, CommonError> {
+        Ok(PairValueEnum::String(query.to_string()))
+    }
+
+    fn execute_pair_with(&mut self, query : &'_ str, param : &PairValueEnum, options : &PairCallOptions) -> Result<PairValueEnum, CommonError> {
+        let guard = options.start()?;
+        let interrupted = Arc::new(AtomicBool::new(false));
+        let flag = interrupted.clone();
+        guard.set_canceller(move || flag.store(true, Ordering::SeqCst))?;
+
+        let started = Instant::now();
+        let ret = loop {
+            if query != "sleep" {
+                break self.execute_pair(query, param);
+            }
+            if interrupted.load(Ordering::SeqCst) {
+                break CommonError::new(&CommonDefaultErrorKind::ExecuteFail, "interrupted").to_result();
+            }
+            if started.elapsed() > Duration::from_secs(5) {
+                break Ok(PairValueEnum::Null);
+            }
+            std::thread::sleep(Duration::from_millis(5));
+        };
+        guard.finish(ret)
+    }
+
+    fn get_current_time(&mut self) -> Result<Duration, CommonError> {
+        Ok(Duration::from_secs(1))
+    }
+}
+
+struct PlainExecutor;
+
+impl PairExecutor for PlainExecutor {
+    fn execute_pair_cursor<'a>(&'a mut self, _query : &'_ str, _param : &PairValueEnum) -> Result<Box<dyn PairRowCursor + 'a>, CommonError> {
+        CommonError::new(&CommonDefaultErrorKind::NoSupport, "cursor").to_result()
+    }
+
+    fn execute_pair(&mut self, _query : &'_ str, _param : &PairValueEnum) -> Result<PairValueEnum, CommonError> {
+        Ok(PairValueEnum::Null)
+    }
+
+    fn get_current_time(&mut self) -> Result<Duration, CommonError> {
+        Ok(Duration::from_secs(1))
+    }
+}
+
+fn has_kind(err : &CommonError, kind : &CommonDefaultErrorKind) -> bool {
+    err.func_ref().iter().any(|pos| pos.3.name() == kind.name())
+}
+
+#[test]
+fn test_call_deadline() -> Result<(), CommonError> {
+    let mut conn = SleepExecutor;
+
+    let started = Instant::now();
+    let err = conn.execute_pair_with("sleep", &PairValueEnum::Null, &PairCallOptions::with_timeout(Duration::from_millis(50))).unwrap_err();
+    assert!(started.elapsed() < Duration::from_secs(2));
+    assert!(has_kind(&err, &CommonDefaultErrorKind::Timeout));
+
+    // a later deadline armed first must not hold back an earlier one
+    let late = PairCallOptions::with_timeout(Duration::from_secs(30));
+    let _late_guard = late.start()?;
+    let err = conn.execute_pair_with("sleep", &PairValueEnum::Null, &PairCallOptions::with_timeout(Duration::from_millis(50))).unwrap_err();
+    assert!(has_kind(&err, &CommonDefaultErrorKind::Timeout));
+
+    let ret = conn.execute_pair_with("fast", &PairValueEnum::Null, &PairCallOptions::with_timeout(Duration::from_secs(1)))?;
+    assert_eq!(ret, PairValueEnum::String("fast".to_string()));
+
+    let expired = PairCallOptions { deadline : Some(Instant::now()), cancel : None };
+    let err = conn.execute_pair_with("fast", &PairValueEnum::Null, &expired).unwrap_err();
+    assert!(has_kind(&err, &CommonDefaultErrorKind::Timeout));
+
+    Ok(())
+}
+
+#[test]
+fn test_call_cancel() -> Result<(), CommonError> {
+    let mut conn = SleepExecutor;
+    let cancel = PairCancelHandle::new();
+
+    let remote = cancel.clone();
+    let t = std::thread::spawn(move || {
+        std::thread::sleep(Duration::from_millis(50));
+        remote.cancel();
+    });
+
+    let options = PairCallOptions::default().with_cancel(cancel.clone());
+    let err = conn.execute_pair_with("sleep", &PairValueEnum::Null, &options).unwrap_err();
+    t.join().unwrap();
+    assert!(has_kind(&err, &CommonDefaultErrorKind::Cancelled));
+
+    // the handle stays cancelled
+    let err = conn.execute_pair_with("fast", &PairValueEnum::Null, &options).unwrap_err();
+    assert!(has_kind(&err, &CommonDefaultErrorKind::Cancelled));
+    assert!(cancel.is_cancelled());
+
+    Ok(())
+}
+
+#[test]
+fn test_call_no_support() -> Result<(), CommonError> {
+    let mut conn = PlainExecutor;
+
+    assert_eq!(conn.execute_pair_with("select", &PairValueEnum::Null, &PairCallOptions::default())?, PairValueEnum::Null);
+
+    let err = conn.execute_pair_with("select", &PairValueEnum::Null, &PairCallOptions::with_timeout(Duration::from_secs(1))).unwrap_err();
+    assert!(has_kind(&err, &CommonDefaultErrorKind::NoSupport));
+
+    Ok(())
+}
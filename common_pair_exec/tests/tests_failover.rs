@@ -0,0 +1,80 @@
+use std::time::Duration;
+use common_err::CommonError;
+use common_err::gen::CommonDefaultErrorKind;
+use common_pair_exec::{is_connection_error, PairAddrSelector, PairFailoverMode, PairFailoverOptions};
+
+fn addrs() -> Vec<String> {
+    vec!["a".to_string(), "b".to_string(), "c".to_string()]
+}
+
+// connects to the first address not listed as down, returns every address tried
+fn try_connect(selector : &PairAddrSelector, down : &[&str]) -> (Result<String, CommonError>, Vec<String>) {
+    let mut tried = Vec::new();
+    let ret = selector.connect(|addr| {
+        tried.push(addr.to_string());
+        if down.contains(&addr) {
+            CommonError::new(&CommonDefaultErrorKind::ConnectFail, "connection refused").to_result()
+        } else {
+            Ok(addr.to_string())
+        }
+    });
+    (ret, tried)
+}
+
+#[test]
+fn test_failover_in_order() -> Result<(), CommonError> {
+    let selector = PairAddrSelector::new(addrs(), PairFailoverOptions::default());
+
+    assert_eq!(try_connect(&selector, &[]).0?, "a");
+    assert_eq!(try_connect(&selector, &["a"]).0?, "b");
+    assert_eq!(selector.healthy_addr(), Some("b"));
+
+    // the healthy node stays first even after the old one comes back
+    let (ret, tried) = try_connect(&selector, &[]);
+    assert_eq!(ret?, "b");
+    assert_eq!(tried, vec!["b"]);
+
+    let (ret, tried) = try_connect(&selector, &["a", "b", "c"]);
+    assert!(is_connection_error(&ret.unwrap_err()));
+    assert_eq!(tried, vec!["b", "c", "a"]);
+
+    Ok(())
+}
+
+#[test]
+fn test_failover_round_robin() -> Result<(), CommonError> {
+    let selector = PairAddrSelector::new(addrs(), PairFailoverOptions { mode : PairFailoverMode::RoundRobin, ..Default::default() });
+
+    let picked = (0..4).map(|_| try_connect(&selector, &[]).0).collect::<Result<Vec<String>, CommonError>>()?;
+    assert_eq!(picked, vec!["a", "b", "c", "a"]);
+
+    assert_eq!(try_connect(&selector, &["b"]).0?, "c");
+
+    Ok(())
+}
+
+#[test]
+fn test_failover_retry_after() -> Result<(), CommonError> {
+    let selector = PairAddrSelector::new(addrs(), PairFailoverOptions {
+        mode : PairFailoverMode::RoundRobin,
+        retry_after : Some(Duration::from_millis(100)),
+        ..Default::default()
+    });
+
+    assert_eq!(try_connect(&selector, &["a"]).0?, "b");
+
+    // a failed recently, so it is tried last even on its round robin turn
+    let (ret, tried) = try_connect(&selector, &["a"]);
+    assert_eq!(ret?, "b");
+    assert_eq!(tried, vec!["b"]);
+
+    std::thread::sleep(Duration::from_millis(150));
+    let (ret, tried) = try_connect(&selector, &[]);
+    assert_eq!(ret?, "c");
+    assert_eq!(tried, vec!["c"]);
+    let (ret, tried) = try_connect(&selector, &[]);
+    assert_eq!(ret?, "a");
+    assert_eq!(tried, vec!["a"]);
+
+    Ok(())
+}
@@ -0,0 +1,81 @@
+use std::collections::HashMap;
+use common_err::CommonError;
+use common_pair_exec::{resolve_named_batch, resolve_named_param, PairInsertTarget, PairNamedQuery, PairPlaceholderStyle, PairValueEnum};
+
+#[test]
+fn test_named_rewrite() -> Result<(), CommonError> {
+    let query = "select id::text, ':skip' as s, $tag$ :in_dollar $tag$ /* :in /* nested */ comment */ from t -- :line
+    where id = :id and name = E'it\\'s :escaped' and (owner = :id or \"col:x\" = :name_2) and a[1:2] is not null";
+
+    let dollar = PairNamedQuery::parse(query, PairPlaceholderStyle::Dollar)?;
+    assert_eq!(dollar.names(), &["id".to_string(), "name_2".to_string()]);
+    assert!(dollar.query().contains("where id = $1 and"));
+    assert!(dollar.query().contains("(owner = $1 or \"col:x\" = $2)"));
+    assert!(dollar.query().contains("id::text, ':skip'"));
+    assert!(dollar.query().contains("$tag$ :in_dollar $tag$"));
+    assert!(dollar.query().contains("-- :line"));
+    assert!(dollar.query().contains("E'it\\'s :escaped'"));
+    assert!(dollar.query().contains("a[1:2]"));
+
+    let question = PairNamedQuery::parse(query, PairPlaceholderStyle::Question)?;
+    assert_eq!(question.names(), &["id".to_string(), "id".to_string(), "name_2".to_string()]);
+    assert!(question.query().contains("where id = ? and"));
+    assert!(question.query().contains("(owner = ? or \"col:x\" = ?)"));
+
+    assert!(PairNamedQuery::parse("select 'open", PairPlaceholderStyle::Question).is_err());
+    assert!(PairNamedQuery::parse("select /* open", PairPlaceholderStyle::Question).is_err());
+
+    Ok(())
+}
+
+#[test]
+fn test_named_bind() -> Result<(), CommonError> {
+    let param = PairValueEnum::Map(HashMap::from([
+        ("id".to_string(), PairValueEnum::BigInt(1)),
+        ("name".to_string(), PairValueEnum::String("kim".to_string())),
+        ("unused".to_string(), PairValueEnum::Null)
+    ]));
+
+    let (query, bind) = resolve_named_param("update t set name = :name where id = :id or parent = :id", &param, PairPlaceholderStyle::Question)?;
+    assert_eq!(query, "update t set name = ? where id = ? or parent = ?");
+    assert_eq!(bind.into_owned(), PairValueEnum::Array(vec![
+        PairValueEnum::String("kim".to_string()), PairValueEnum::BigInt(1), PairValueEnum::BigInt(1)
+    ]));
+
+    let positional = PairValueEnum::Array(vec![PairValueEnum::BigInt(1)]);
+    let (query, bind) = resolve_named_param("select :id", &positional, PairPlaceholderStyle::Dollar)?;
+    assert_eq!(query, "select :id");
+    assert_eq!(bind.as_ref(), &positional);
+
+    let missing = resolve_named_param("select :missing", &param, PairPlaceholderStyle::Dollar);
+    assert!(missing.unwrap_err().get_cause().contains(":missing"));
+
+    let batch = vec![param.clone(), param.clone()];
+    let (query, rows) = resolve_named_batch("insert into t values(:id, :name)", batch.as_slice(), PairPlaceholderStyle::Dollar)?;
+    assert_eq!(query, "insert into t values($1, $2)");
+    assert_eq!(rows.len(), 2);
+    assert!(resolve_named_batch("insert into t values(:id)", &[param, positional], PairPlaceholderStyle::Dollar).is_err());
+
+    Ok(())
+}
+
+#[test]
+fn test_insert_target() {
+    let target = PairInsertTarget::parse("/* load */ INSERT INTO Public.\"Large Data\"(id, \"Name\") VALUES ($1, $2);", PairPlaceholderStyle::Dollar);
+    assert_eq!(target, Some(PairInsertTarget {
+        schema : Some("public".to_string()),
+        table : "Large Data".to_string(),
+        columns : vec!["id".to_string(), "Name".to_string()]
+    }));
+
+    let target = PairInsertTarget::parse("insert into t (a, b) values (?, ?)", PairPlaceholderStyle::Question).unwrap();
+    assert_eq!(target.schema, None);
+    assert_eq!(target.columns, vec!["a".to_string(), "b".to_string()]);
+
+    assert!(PairInsertTarget::parse("insert into t (a, b) values ($2, $1)", PairPlaceholderStyle::Dollar).is_none());
+    assert!(PairInsertTarget::parse("insert into t (a, b) values ($1, 'x')", PairPlaceholderStyle::Dollar).is_none());
+    assert!(PairInsertTarget::parse("insert into t (a) values ($1) returning a", PairPlaceholderStyle::Dollar).is_none());
+    assert!(PairInsertTarget::parse("insert into t (a) values ($1), ($2)", PairPlaceholderStyle::Dollar).is_none());
+    assert!(PairInsertTarget::parse("insert into t values ($1)", PairPlaceholderStyle::Dollar).is_none());
+    assert!(PairInsertTarget::parse("update t set a = $1", PairPlaceholderStyle::Dollar).is_none());
+}
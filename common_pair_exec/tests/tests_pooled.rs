@@ -0,0 +1,174 @@
+use std::time::Duration;
+use common_core::collection::pool::{get_thread_safe_pool, get_thread_safe_pool_with_policy, PoolKind};
+use common_err::CommonError;
+use common_err::gen::CommonDefaultErrorKind;
+use common_pair_exec::{is_connection_error, PairExecutor, PairExecutorPool, PairPoolOptions, PairRowCursor, PairStatementCacheStats, PairValueEnum, PooledPairExecutor};
+
+// fails queries by name, "lost" as a connection error and anything else as an execute error.
+// "poison" makes the next rollback fail
+struct FlakyExecutor {
+    closed : bool,
+    in_transaction : bool,
+    poisoned : bool
+}
+
+impl PairExecutor for FlakyExecutor {
+    fn execute_pair_cursor<'a>(&'a mut self, query : &'_ str, _param : &PairValueEnum) -> Result<Box<dyn PairRowCursor + 'a>, CommonError> {
+        match query {
+            "lost" => CommonError::new(&CommonDefaultErrorKind::ConnectFail, "connection reset").to_result(),
+            "close" => {
+                self.closed = true;
+                CommonError::new(&CommonDefaultErrorKind::ExecuteFail, "server closed the connection").to_result()
+            },
+            "poison" => {
+                self.poisoned = true;
+                CommonError::new(&CommonDefaultErrorKind::ExecuteFail, "transaction aborted").to_result()
+            },
+            _ => CommonError::new(&CommonDefaultErrorKind::ExecuteFail, "syntax error").to_result()
+        }
+    }
+
+    fn get_current_time(&mut self) -> Result<Duration, CommonError> {
+        Ok(Duration::from_secs(1))
+    }
+
+    fn is_closed(&mut self) -> bool {
+        self.closed
+    }
+
+    fn statement_cache_stats(&self) -> Option<PairStatementCacheStats> {
+        Some(PairStatementCacheStats { hit : 3, miss : 1, size : 1, capacity : 16 })
+    }
+
+    fn in_transaction(&self) -> bool {
+        self.in_transaction
+    }
+
+    fn begin(&mut self) -> Result<(), CommonError> {
+        self.in_transaction = true;
+        Ok(())
+    }
+
+    fn rollback(&mut self) -> Result<(), CommonError> {
+        if self.poisoned {
+            return CommonError::new(&CommonDefaultErrorKind::ExecuteFail, "rollback failed").to_result();
+        }
+        self.in_transaction = false;
+        Ok(())
+    }
+}
+
+fn new_flaky() -> Result<Box<dyn PairExecutor + Send>, CommonError> {
+    Ok(Box::new(FlakyExecutor { closed : false, in_transaction : false, poisoned : false }))
+}
+
+fn flaky_pool() -> PairExecutorPool {
+    get_thread_safe_pool("flaky".to_string(), Box::new(|_ : ()| new_flaky()), 2, PoolKind::Owned)
+}
+
+#[test]
+fn test_connection_error_kind() {
+    let lost = CommonError::new(&CommonDefaultErrorKind::ConnectFail, "connection reset");
+    assert!(is_connection_error(&CommonError::extend(&CommonDefaultErrorKind::ExecuteFail, "", lost)));
+    assert!(!is_connection_error(&CommonError::new(&CommonDefaultErrorKind::ExecuteFail, "syntax error")));
+}
+
+#[test]
+fn test_pooled_executor_restore_and_dispose() -> Result<(), CommonError> {
+    let p = flaky_pool();
+
+    {
+        let mut conn = PooledPairExecutor::from_pool(&p)?;
+        assert!(conn.execute_pair("select", &PairValueEnum::Null).is_err());
+        assert!(!conn.is_broken());
+    }
+    let stats = p.stats();
+    assert_eq!((stats.idle, stats.returns, stats.disposed), (1, 1, 0));
+
+    {
+        let mut conn = PooledPairExecutor::from_pool(&p)?;
+        assert!(conn.execute_pair("lost", &PairValueEnum::Null).is_err());
+        assert!(conn.is_broken());
+    }
+    let stats = p.stats();
+    assert_eq!((stats.idle, stats.disposed), (0, 1));
+    assert_eq!(p.alloc_size(), 0);
+
+    Ok(())
+}
+
+#[test]
+fn test_pooled_executor_driver_closed() -> Result<(), CommonError> {
+    let p = flaky_pool();
+
+    {
+        let mut conn = PooledPairExecutor::from_pool(&p)?;
+        assert!(conn.execute_pair("close", &PairValueEnum::Null).is_err());
+        assert!(!conn.is_broken());
+        assert!(conn.is_closed());
+    }
+    assert_eq!(p.stats().disposed, 1);
+
+    {
+        let mut conn = PooledPairExecutor::from_pool(&p)?;
+        conn.mark_broken();
+    }
+    assert_eq!(p.stats().disposed, 2);
+    assert_eq!(p.alloc_size(), 0);
+
+    Ok(())
+}
+
+#[test]
+fn test_pooled_executor_cache_stats() -> Result<(), CommonError> {
+    let p = flaky_pool();
+
+    let conn = PooledPairExecutor::from_pool(&p)?;
+    assert_eq!(conn.statement_cache_stats(), Some(PairStatementCacheStats { hit : 3, miss : 1, size : 1, capacity : 16 }));
+
+    Ok(())
+}
+
+#[test]
+fn test_pooled_executor_open_transaction() -> Result<(), CommonError> {
+    let p = flaky_pool();
+
+    {
+        let mut conn = PooledPairExecutor::from_pool(&p)?;
+        conn.begin()?;
+        assert!(conn.in_transaction());
+    }
+    let mut conn = PooledPairExecutor::from_pool(&p)?;
+    assert!(!conn.in_transaction());
+    assert_eq!(p.stats().disposed, 0);
+
+    // a rollback that fails leaves the transaction open, the connection is not pooled again
+    conn.begin()?;
+    assert!(conn.execute_pair("poison", &PairValueEnum::Null).is_err());
+    drop(conn);
+    assert_eq!(p.stats().disposed, 1);
+    assert_eq!(p.alloc_size(), 0);
+
+    Ok(())
+}
+
+#[test]
+fn test_pool_item_open_transaction() -> Result<(), CommonError> {
+    let p : PairExecutorPool = get_thread_safe_pool_with_policy("flaky".to_string(), Box::new(|_ : ()| new_flaky()), 1,
+                                                                PairPoolOptions::default().to_pool_policy());
+
+    {
+        let mut item = p.get_owned(())?;
+        item.get_value().begin()?;
+    }
+    let mut item = p.get_owned(())?;
+    assert!(!item.get_value().in_transaction());
+
+    item.get_value().begin()?;
+    assert!(item.get_value().execute_pair("poison", &PairValueEnum::Null).is_err());
+    drop(item);
+    assert_eq!(p.stats().disposed, 1);
+    assert_eq!(p.alloc_size(), 0);
+
+    Ok(())
+}
@@ -0,0 +1,109 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+use common_core::collection::pool::{get_thread_safe_pool, PoolKind};
+use common_err::CommonError;
+use common_err::gen::CommonDefaultErrorKind;
+use common_pair_exec::{is_transient_error, with_retry, PairExecutor, PairExecutorPool, PairRetryPolicy, PairRowCursor, PairValueEnum};
+
+// every call fails with the given kind until `fail_calls` calls were made on any connection
+struct CountingExecutor {
+    calls : Arc<AtomicUsize>,
+    fail_calls : usize,
+    fail_kind : &'static CommonDefaultErrorKind
+}
+
+impl PairExecutor for CountingExecutor {
+    fn execute_pair_cursor<'a>(&'a mut self, _query : &'_ str, _param : &PairValueEnum) -> Result<Box<dyn PairRowCursor + 'a>, CommonError> {
+        CommonError::new(&CommonDefaultErrorKind::NoSupport, "cursor").to_result()
+    }
+
+    fn execute_pair(&mut self, _query : &'_ str, _param : &PairValueEnum) -> Result<PairValueEnum, CommonError> {
+        let call = self.calls.fetch_add(1, Ordering::SeqCst) + 1;
+        if call <= self.fail_calls {
+            return CommonError::new(self.fail_kind, format!("call {} failed", call)).to_result();
+        }
+        Ok(PairValueEnum::BigInt(call as i64))
+    }
+
+    fn get_current_time(&mut self) -> Result<Duration, CommonError> {
+        Ok(Duration::from_secs(1))
+    }
+}
+
+fn counting_pool(calls : Arc<AtomicUsize>, fail_calls : usize, fail_kind : &'static CommonDefaultErrorKind) -> PairExecutorPool {
+    get_thread_safe_pool("retry".to_string(), Box::new(move |_ : ()| {
+        Ok(Box::new(CountingExecutor { calls : calls.clone(), fail_calls, fail_kind }) as Box<dyn PairExecutor + Send>)
+    }), 1, PoolKind::Owned)
+}
+
+fn fast_policy() -> PairRetryPolicy {
+    PairRetryPolicy { base_delay : Duration::from_millis(1), ..Default::default() }
+}
+
+#[test]
+fn test_retry_backoff() {
+    let policy = PairRetryPolicy { max_delay : Duration::from_millis(150), jitter : false, ..Default::default() };
+    let delays : Vec<Duration> = (1..=4).map(|attempt| policy.backoff(attempt)).collect();
+    assert_eq!(delays, [50, 100, 150, 150].map(Duration::from_millis));
+
+    let policy = PairRetryPolicy::default();
+    assert!((0..32).all(|_| policy.backoff(2) <= Duration::from_millis(100)));
+}
+
+#[test]
+fn test_retry_transient() -> Result<(), CommonError> {
+    let calls = Arc::new(AtomicUsize::new(0));
+    let p = counting_pool(calls.clone(), 2, &CommonDefaultErrorKind::Transient);
+
+    let ret = with_retry(&p, &fast_policy(), true, |conn| conn.execute_pair("select", &PairValueEnum::Null))?;
+    assert_eq!(ret, PairValueEnum::BigInt(3));
+    // server side failures keep the connection
+    assert_eq!(p.stats().created, 1);
+
+    calls.store(0, Ordering::SeqCst);
+    let err = with_retry(&p, &fast_policy(), false, |conn| conn.execute_pair("insert", &PairValueEnum::Null)).unwrap_err();
+    assert!(is_transient_error(&err));
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+    Ok(())
+}
+
+#[test]
+fn test_retry_reconnect() -> Result<(), CommonError> {
+    let calls = Arc::new(AtomicUsize::new(0));
+    let p = counting_pool(calls.clone(), 1, &CommonDefaultErrorKind::ConnectFail);
+
+    with_retry(&p, &fast_policy(), true, |conn| conn.execute_pair("select", &PairValueEnum::Null))?;
+    let stats = p.stats();
+    assert_eq!((stats.created, stats.disposed), (2, 1));
+
+    calls.store(0, Ordering::SeqCst);
+    let err = with_retry(&p, &PairRetryPolicy { max_attempts : 1, ..fast_policy() }, true, |conn| conn.execute_pair("select", &PairValueEnum::Null)).unwrap_err();
+    assert!(is_transient_error(&err));
+
+    Ok(())
+}
+
+#[test]
+fn test_retry_checkout_and_permanent() -> Result<(), CommonError> {
+    let attempts = Arc::new(AtomicUsize::new(0));
+    let gen_attempts = attempts.clone();
+    let p : PairExecutorPool = get_thread_safe_pool("retry".to_string(), Box::new(move |_ : ()| {
+        if gen_attempts.fetch_add(1, Ordering::SeqCst) == 0 {
+            return CommonError::new(&CommonDefaultErrorKind::ConnectFail, "connection refused").to_result();
+        }
+        Ok(Box::new(CountingExecutor { calls : Arc::new(AtomicUsize::new(0)), fail_calls : 0, fail_kind : &CommonDefaultErrorKind::Transient }) as Box<dyn PairExecutor + Send>)
+    }), 1, PoolKind::Owned);
+
+    // nothing was sent yet, so even a write is retried
+    with_retry(&p, &fast_policy(), false, |conn| conn.execute_pair("insert", &PairValueEnum::Null))?;
+    assert_eq!(attempts.load(Ordering::SeqCst), 2);
+
+    let calls = Arc::new(AtomicUsize::new(0));
+    let p = counting_pool(calls.clone(), 5, &CommonDefaultErrorKind::ExecuteFail);
+    assert!(with_retry(&p, &fast_policy(), true, |conn| conn.execute_pair("select", &PairValueEnum::Null)).is_err());
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+    Ok(())
+}
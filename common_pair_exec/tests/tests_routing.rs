@@ -0,0 +1,145 @@
+use std::time::Duration;
+use common_core::collection::pool::{get_thread_safe_pool, PoolKind};
+use common_err::CommonError;
+use common_err::gen::CommonDefaultErrorKind;
+use common_pair_exec::{classify_pair_statement, is_pair_dml_statement, with_transaction, PairColumnInfo, PairExecutor, PairExecutorPool, PairRoute,
+                       PairRoutingPool, PairRowCursor, PairValueEnum};
+
+struct NodeCursor {
+    cols : Vec<PairColumnInfo>,
+    row : Option<Vec<PairValueEnum>>
+}
+
+impl PairRowCursor for NodeCursor {
+    fn columns(&self) -> &'_ [PairColumnInfo] {
+        self.cols.as_slice()
+    }
+
+    fn next_row(&mut self) -> Result<Option<Vec<PairValueEnum>>, CommonError> {
+        Ok(self.row.take())
+    }
+}
+
+// answers every query with the name of the node it ran on
+struct NodeExecutor {
+    node : String
+}
+
+impl PairExecutor for NodeExecutor {
+    fn execute_pair_cursor<'a>(&'a mut self, _query : &'_ str, _param : &PairValueEnum) -> Result<Box<dyn PairRowCursor + 'a>, CommonError> {
+        Ok(Box::new(NodeCursor {
+            cols : vec![PairColumnInfo::new("node", "text", false)],
+            row : Some(vec![PairValueEnum::String(self.node.clone())])
+        }))
+    }
+
+    fn get_current_time(&mut self) -> Result<Duration, CommonError> {
+        Ok(Duration::from_secs(1))
+    }
+
+    fn begin(&mut self) -> Result<(), CommonError> {
+        Ok(())
+    }
+
+    fn commit(&mut self) -> Result<(), CommonError> {
+        Ok(())
+    }
+}
+
+fn node_pool(node : &'static str) -> PairExecutorPool {
+    get_thread_safe_pool(node.to_string(), Box::new(move |_ : ()| {
+        Ok(Box::new(NodeExecutor { node : node.to_string() }) as Box<dyn PairExecutor + Send>)
+    }), 2, PoolKind::Owned)
+}
+
+fn run_on(conn : &mut dyn PairExecutor, query : &'_ str) -> Result<String, CommonError> {
+    match conn.execute_pair_result_set(query, &PairValueEnum::Null)?.get(0, 0) {
+        Some(PairValueEnum::String(node)) => Ok(node.clone()),
+        _ => CommonError::new(&CommonDefaultErrorKind::NoData, "no node").to_result()
+    }
+}
+
+#[test]
+fn test_classify_statement() {
+    assert_eq!(classify_pair_statement("SELECT * FROM t"), PairRoute::Replica);
+    assert_eq!(classify_pair_statement("  -- list\n/* hint */ (select 1) union (select 2)"), PairRoute::Replica);
+    assert_eq!(classify_pair_statement("with a as (select 1) select * from a"), PairRoute::Replica);
+    assert_eq!(classify_pair_statement("select * from t for update"), PairRoute::Primary);
+    assert_eq!(classify_pair_statement("with a as (delete from t returning *) select * from a"), PairRoute::Primary);
+    assert_eq!(classify_pair_statement("insert into t values (1)"), PairRoute::Primary);
+    assert_eq!(classify_pair_statement("create table t(id int)"), PairRoute::Primary);
+    assert_eq!(classify_pair_statement(""), PairRoute::Primary);
+
+    // only real keywords route, not text inside literals, quoted identifiers or comments
+    assert_eq!(classify_pair_statement("select * from t where k = 'update'"), PairRoute::Replica);
+    assert_eq!(classify_pair_statement("select 'for update' from t"), PairRoute::Replica);
+    assert_eq!(classify_pair_statement("select \"delete\" from t /* insert */ -- merge"), PairRoute::Replica);
+    assert_eq!(classify_pair_statement("with \"it's\" as (delete from t returning *) select * from \"it's\""), PairRoute::Primary);
+    assert_eq!(classify_pair_statement("with a as (select 'x''y' as v) update t set v = (select v from a)"), PairRoute::Primary);
+}
+
+#[test]
+fn test_dml_statement() {
+    assert!(is_pair_dml_statement("UPDATE t SET v = 1"));
+    assert!(is_pair_dml_statement("/* batch */ insert into t values (1)"));
+    assert!(is_pair_dml_statement("with a as (select 1) delete from t where id in (select * from a)"));
+    assert!(!is_pair_dml_statement("delete from t returning id"));
+    assert!(!is_pair_dml_statement("select count(*) as \"Count\" from t"));
+    assert!(!is_pair_dml_statement("create table t(id int)"));
+    assert!(!is_pair_dml_statement(""));
+
+    // keywords inside literals, quoted identifiers and comments do not count
+    assert!(!is_pair_dml_statement("SELECT * FROM t WHERE k = 'delete'"));
+    assert!(!is_pair_dml_statement("select \"update\" from t -- insert later"));
+    assert!(!is_pair_dml_statement("select 1 /* delete from t */"));
+    assert!(!is_pair_dml_statement("select $body$ insert into t $body$"));
+    assert!(!is_pair_dml_statement("with a as (select 'merge' as m) select * from a"));
+    assert!(is_pair_dml_statement("update t set v = 'returning' where k = 'it''s'"));
+    assert!(is_pair_dml_statement("insert into \"returning\" values (1)"));
+}
+
+#[test]
+fn test_routing_read_write() -> Result<(), CommonError> {
+    let p = PairRoutingPool::new(node_pool("primary"), vec![node_pool("replica_a"), node_pool("replica_b")]);
+
+    let mut conn = p.get_executor();
+    assert_eq!(run_on(&mut conn, "select 1")?, "replica_a");
+    assert_eq!(run_on(&mut conn, "insert into t values (1)")?, "primary");
+    // the session keeps its replica
+    assert_eq!(run_on(&mut conn, "select 1")?, "replica_a");
+
+    conn.set_route(Some(PairRoute::Primary));
+    assert_eq!(run_on(&mut conn, "select 1")?, "primary");
+    conn.set_route(None);
+
+    // the next session is balanced to the other replica
+    let mut other = p.get_executor();
+    assert_eq!(run_on(&mut other, "select 1")?, "replica_b");
+
+    Ok(())
+}
+
+#[test]
+fn test_routing_transaction_pinned() -> Result<(), CommonError> {
+    let p = PairRoutingPool::new(node_pool("primary"), vec![node_pool("replica_a")]);
+    let mut conn = p.get_executor();
+
+    let node = with_transaction(&mut conn, |tx| run_on(tx, "select 1"))?;
+    assert_eq!(node, "primary");
+    assert_eq!(run_on(&mut conn, "select 1")?, "replica_a");
+
+    Ok(())
+}
+
+#[test]
+fn test_routing_replica_fallback() -> Result<(), CommonError> {
+    let down : PairExecutorPool = get_thread_safe_pool("down".to_string(), Box::new(|_ : ()| {
+        CommonError::new(&CommonDefaultErrorKind::ConnectFail, "connection refused").to_result()
+    }), 1, PoolKind::Owned);
+    let p = PairRoutingPool::new(node_pool("primary"), vec![down]);
+
+    let mut conn = p.get_executor();
+    assert_eq!(run_on(&mut conn, "select 1")?, "primary");
+
+    Ok(())
+}
@@ -0,0 +1,92 @@
+use common_err::CommonError;
+use common_pair_exec::{FromPairRow, FromPairValue, PairColumnInfo, PairDecimal, PairResultSet, PairValueEnum, ToPairParams, ToPairValue};
+
+#[derive(Debug, Clone, Copy, PartialEq, FromPairValue, ToPairValue)]
+enum UserState {
+    Active,
+    #[pair(rename = "blocked")]
+    Blocked
+}
+
+#[derive(Debug, PartialEq, FromPairRow, ToPairParams)]
+struct User {
+    id : i64,
+    name : String,
+    #[pair(rename = "user_state")]
+    state : UserState,
+    score : Option<PairDecimal>,
+    #[pair(skip)]
+    cached : bool
+}
+
+fn user_result_set() -> PairResultSet {
+    let mut rs = PairResultSet::new(vec![
+        PairColumnInfo::new("name", "text", false),
+        PairColumnInfo::new("id", "int4", false),
+        PairColumnInfo::new("user_state", "text", false),
+        PairColumnInfo::new("score", "numeric", true),
+    ]);
+
+    rs.push_row(vec![PairValueEnum::String("kim".to_string()), PairValueEnum::Int(1),
+                     PairValueEnum::String("Active".to_string()), PairValueEnum::Decimal(PairDecimal::new(1050, 2))]).unwrap();
+    rs.push_row(vec![PairValueEnum::String("lee".to_string()), PairValueEnum::BigInt(2),
+                     PairValueEnum::String("blocked".to_string()), PairValueEnum::Null]).unwrap();
+    rs
+}
+
+#[test]
+fn test_decode_row() -> Result<(), CommonError> {
+    let users = user_result_set().decode::<User>()?;
+
+    assert_eq!(users, vec![
+        User { id : 1, name : "kim".to_string(), state : UserState::Active, score : Some(PairDecimal::new(1050, 2)), cached : false },
+        User { id : 2, name : "lee".to_string(), state : UserState::Blocked, score : None, cached : false },
+    ]);
+
+    assert_eq!(users[1].to_pair_params(), PairValueEnum::Array(vec![
+        PairValueEnum::BigInt(2), PairValueEnum::String("lee".to_string()),
+        PairValueEnum::String("blocked".to_string()), PairValueEnum::Null
+    ]));
+
+    Ok(())
+}
+
+#[test]
+fn test_decode_row_mismatch() {
+    let mut rs = user_result_set();
+    rs.push_row(vec![PairValueEnum::Null, PairValueEnum::Int(3),
+                     PairValueEnum::String("Active".to_string()), PairValueEnum::Null]).unwrap();
+    let err = rs.decode::<User>().unwrap_err();
+    assert!(format!("{:?}", err).contains("column 'name'"));
+
+    let mut rs = user_result_set();
+    rs.push_row(vec![PairValueEnum::String("park".to_string()), PairValueEnum::Int(3),
+                     PairValueEnum::String("unknown".to_string()), PairValueEnum::Null]).unwrap();
+    let err = rs.decode::<User>().unwrap_err();
+    assert!(format!("{:?}", err).contains("unknown variant 'unknown'"));
+
+    let rs = PairResultSet::new(vec![PairColumnInfo::new("id", "int4", false)]);
+    let err = User::from_pair_row(rs.columns(), &[PairValueEnum::Int(1)]).unwrap_err();
+    assert!(err.get_cause().contains("column 'name' not exists"));
+}
+
+#[test]
+fn test_pair_value_coercion() -> Result<(), CommonError> {
+    assert_eq!(i64::from_pair_value(&PairValueEnum::Int(7))?, 7);
+    assert_eq!(i16::from_pair_value(&PairValueEnum::BigInt(-300))?, -300);
+    assert!(i8::from_pair_value(&PairValueEnum::BigInt(300)).is_err());
+    assert!(u32::from_pair_value(&PairValueEnum::Int(-1)).is_err());
+    assert_eq!(f64::from_pair_value(&PairValueEnum::Decimal(PairDecimal::new(-125, 2)))?, -1.25);
+    assert_eq!(bool::from_pair_value(&PairValueEnum::TinyInt(1))?, true);
+    assert_eq!(Option::<String>::from_pair_value(&PairValueEnum::Null)?, None);
+    assert!(String::from_pair_value(&PairValueEnum::Null).is_err());
+    assert_eq!(uuid::Uuid::from_pair_value(&PairValueEnum::String("67e55044-10b1-426f-9247-bb680e5fe0c8".to_string()))?.to_string(),
+               "67e55044-10b1-426f-9247-bb680e5fe0c8");
+
+    assert_eq!(u64::MAX.to_pair_value(), PairValueEnum::Decimal(PairDecimal::new(u64::MAX as i128, 0)));
+    assert_eq!(u64::from_pair_value(&u64::MAX.to_pair_value())?, u64::MAX);
+    assert_eq!("-0.05".parse::<PairDecimal>()?, PairDecimal::new(-5, 2));
+    assert_eq!(PairDecimal::new(-5, 2).to_string(), "-0.05");
+
+    Ok(())
+}
@@ -0,0 +1,116 @@
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::Arc;
+use rustls::{ClientConnection, ServerConfig, ServerConnection, StreamOwned};
+use rustls::pki_types::{CertificateDer, PrivateKeyDer, ServerName};
+use rustls::pki_types::pem::PemObject;
+use rustls::server::WebPkiClientVerifier;
+use common_err::{CommonError, CommonErrorKind};
+use common_err::gen::CommonDefaultErrorKind;
+use common_pair_exec::{pair_crypto_provider, PairTlsOptions, PairTlsVerify};
+
+fn cert_path(name : &'_ str) -> String {
+    format!("{}/tests/tls/{}", env!("CARGO_MANIFEST_DIR"), name)
+}
+
+fn server_config(client_auth : bool) -> Arc<ServerConfig> {
+    let certs = CertificateDer::pem_file_iter(cert_path("server.crt")).unwrap().collect::<Result<Vec<_>, _>>().unwrap();
+    let key = PrivateKeyDer::from_pem_file(cert_path("server.key")).unwrap();
+    let builder = ServerConfig::builder_with_provider(pair_crypto_provider()).with_safe_default_protocol_versions().unwrap();
+
+    let builder = if client_auth {
+        let mut roots = rustls::RootCertStore::empty();
+        roots.add(CertificateDer::from_pem_file(cert_path("ca.crt")).unwrap()).unwrap();
+        let verifier = WebPkiClientVerifier::builder_with_provider(Arc::new(roots), pair_crypto_provider()).build().unwrap();
+        builder.with_client_cert_verifier(verifier)
+    } else {
+        builder.with_no_client_auth()
+    };
+    Arc::new(builder.with_single_cert(certs, key).unwrap())
+}
+
+// dials 127.0.0.1 by ip like the drivers do and echoes one line back
+fn handshake(options : &'_ PairTlsOptions, client_auth : bool) -> Result<(), CommonError> {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let config = server_config(client_auth);
+
+    let server = std::thread::spawn(move || {
+        let (sock, _) = listener.accept().unwrap();
+        let mut tls = StreamOwned::new(ServerConnection::new(config).unwrap(), sock);
+        let mut buf = [0u8; 4];
+        if tls.read_exact(&mut buf).is_ok() {
+            let _ = tls.write_all(&buf);
+            let _ = tls.flush();
+        }
+    });
+
+    let client_config = Arc::new(options.client_config()?);
+    let server_name = ServerName::try_from("127.0.0.1").unwrap();
+    let mut tls = StreamOwned::new(ClientConnection::new(client_config, server_name).unwrap(), TcpStream::connect(addr).unwrap());
+
+    let mut buf = [0u8; 4];
+    let ret = tls.write_all(b"ping").and_then(|_| tls.read_exact(&mut buf)).map_err(|e| {
+        CommonError::new(&CommonDefaultErrorKind::ConnectFail, format!("handshake - {}", e))
+    });
+    drop(tls);
+    server.join().unwrap();
+
+    ret.map(|_| assert_eq!(&buf, b"ping"))
+}
+
+fn options(ca : &'_ str, verify : PairTlsVerify) -> PairTlsOptions {
+    PairTlsOptions { ca_file : Some(cert_path(ca)), verify, ..Default::default() }
+}
+
+fn has_kind(err : &CommonError, kind : &CommonDefaultErrorKind) -> bool {
+    err.func_ref().iter().any(|pos| pos.3.name() == kind.name())
+}
+
+#[test]
+fn test_tls_verify_modes() -> Result<(), CommonError> {
+    // the certificate is issued to localhost only
+    assert!(handshake(&options("ca.crt", PairTlsVerify::Full), false).is_err());
+
+    let by_name = PairTlsOptions { server_name : Some("localhost".to_string()), ..options("ca.crt", PairTlsVerify::Full) };
+    handshake(&by_name, false)?;
+
+    handshake(&options("ca.crt", PairTlsVerify::CaOnly), false)?;
+
+    // the chain is still checked without the name
+    assert!(handshake(&options("other_ca.crt", PairTlsVerify::CaOnly), false).is_err());
+    handshake(&options("other_ca.crt", PairTlsVerify::None), false)?;
+
+    Ok(())
+}
+
+#[test]
+fn test_tls_client_cert() -> Result<(), CommonError> {
+    assert!(handshake(&options("ca.crt", PairTlsVerify::CaOnly), true).is_err());
+
+    let with_cert = PairTlsOptions {
+        cert_file : Some(cert_path("client.crt")),
+        key_file : Some(cert_path("client.key")),
+        ..options("ca.crt", PairTlsVerify::CaOnly)
+    };
+    handshake(&with_cert, true)?;
+
+    Ok(())
+}
+
+#[test]
+fn test_tls_invalid_options() {
+    let no_key = PairTlsOptions { cert_file : Some(cert_path("client.crt")), ..options("ca.crt", PairTlsVerify::Full) };
+    let err = no_key.client_config().unwrap_err();
+    assert!(has_kind(&err, &CommonDefaultErrorKind::InitFailed));
+
+    let err = options("missing.crt", PairTlsVerify::Full).client_config().unwrap_err();
+    assert!(has_kind(&err, &CommonDefaultErrorKind::InitFailed));
+
+    // not a certificate
+    let err = options("client.key", PairTlsVerify::Full).client_config().unwrap_err();
+    assert!(has_kind(&err, &CommonDefaultErrorKind::InitFailed));
+
+    // the webpki roots stand in for a missing bundle
+    assert!(PairTlsOptions::default().client_config().is_ok());
+}
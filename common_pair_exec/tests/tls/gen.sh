@@ -0,0 +1,22 @@
+#!/bin/sh
+# regenerates the test certificates, the server one is issued to localhost only
+set -e
+cd "$(dirname "$0")"
+
+openssl req -x509 -newkey rsa:2048 -nodes -days 36500 -subj "/CN=pair test ca" \
+    -addext "basicConstraints=critical,CA:TRUE" -addext "keyUsage=critical,keyCertSign,cRLSign" \
+    -keyout ca.key -out ca.crt
+
+openssl req -newkey rsa:2048 -nodes -subj "/CN=localhost" -keyout server.key -out server.csr
+printf "subjectAltName=DNS:localhost\nextendedKeyUsage=serverAuth\n" > server.ext
+openssl x509 -req -in server.csr -CA ca.crt -CAkey ca.key -CAcreateserial -days 36500 -extfile server.ext -out server.crt
+
+openssl req -newkey rsa:2048 -nodes -subj "/CN=pair" -keyout client.key -out client.csr
+printf "extendedKeyUsage=clientAuth\n" > client.ext
+openssl x509 -req -in client.csr -CA ca.crt -CAkey ca.key -CAcreateserial -days 36500 -extfile client.ext -out client.crt
+
+# a second authority the tests expect to be rejected
+openssl req -x509 -newkey rsa:2048 -nodes -days 36500 -subj "/CN=other ca" \
+    -addext "basicConstraints=critical,CA:TRUE" -keyout other_ca.key -out other_ca.crt
+
+rm -f ca.key ca.srl server.csr server.ext client.csr client.ext other_ca.key
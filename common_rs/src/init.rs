@@ -1,6 +1,8 @@
 pub(crate) mod signal;
+pub(crate) mod shutdown;
 pub mod logger;
 
+use std::time::Duration;
 use common_err::CommonError;
 use common_err::gen::CommonDefaultErrorKind;
 
@@ -8,6 +10,8 @@ pub use crate::init::logger::LoggerConf;
 
 pub struct InitConfig {
     pub logger_conf : LoggerConf,
+    // SIGINT runs shutdown() with this deadline, None leaves shutdown to the application
+    pub shutdown_timeout : Option<Duration>,
 }
 pub fn convert_str_to_log_level(log_level : &'_ str) -> common_logger::LogLevel {
     match log_level {
@@ -22,5 +26,11 @@ pub fn init_common(cfg : InitConfig) -> Result<(), CommonError>{
     logger::init_once(cfg.logger_conf).map_err(|e| {
         CommonError::extend(&CommonDefaultErrorKind::InitFailed, "can't success init_once", e)
     })?;
+
+    if let Some(timeout) = cfg.shutdown_timeout {
+        shutdown::watch_signal(timeout).map_err(|e| {
+            CommonError::extend(&CommonDefaultErrorKind::InitFailed, "can't start shutdown watcher", e)
+        })?;
+    }
     Ok(())
 }
\ No newline at end of file
@@ -0,0 +1,75 @@
+use std::sync::{Arc, LazyLock, Mutex, Once};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use common_core::collection::pool::ThreadSafePool;
+use common_err::CommonError;
+use common_err::gen::CommonDefaultErrorKind;
+
+use crate::init::signal;
+
+pub type ShutdownFn = Box<dyn FnOnce(Duration) -> Result<(), CommonError> + Send>;
+
+static WATCH_ONCE : Once = Once::new();
+const SIGNAL_POLL_INTERVAL : Duration = Duration::from_millis(100);
+
+static SHUTDOWN_LIST : LazyLock<Mutex<Vec<(String, ShutdownFn)>>> = LazyLock::new(|| {
+    Mutex::new(Vec::new())
+});
+
+pub fn register_shutdown(name : String, f : ShutdownFn) {
+    SHUTDOWN_LIST.lock().unwrap().push((name, f));
+}
+
+// only a weak reference is kept, a pool dropped earlier is skipped
+pub fn register_pool_shutdown<T : 'static, P : 'static>(name : String, pool : &'_ Arc<dyn ThreadSafePool<T,P>>) {
+    let pool = Arc::downgrade(pool);
+    register_shutdown(name, Box::new(move |timeout| {
+        match pool.upgrade() {
+            Some(pool) => pool.close(timeout),
+            None => Ok(())
+        }
+    }));
+}
+
+// runs in reverse registration order, every entry shares the same deadline
+pub fn shutdown(timeout : Duration) -> Result<(), CommonError> {
+    let deadline = Instant::now() + timeout;
+    let list = std::mem::take(&mut *SHUTDOWN_LIST.lock().unwrap());
+    let mut failed = Vec::new();
+
+    for (name, f) in list.into_iter().rev() {
+        let remain = deadline.saturating_duration_since(Instant::now());
+        if let Err(e) = f(remain) {
+            failed.push(format!("{} - {}", name, e.get_cause()));
+        }
+    }
+
+    if failed.is_empty() {
+        Ok(())
+    } else {
+        CommonError::new(&CommonDefaultErrorKind::Etc, format!("shutdown failed [{}]", failed.join(", "))).to_result()
+    }
+}
+
+// the signal handler only sets a flag, the registry is closed from this thread once SIGINT is seen
+pub(crate) fn watch_signal(timeout : Duration) -> Result<(), CommonError> {
+    let mut ret = Ok(());
+
+    WATCH_ONCE.call_once(|| {
+        let spawned = thread::Builder::new().name(String::from("shutdown-watch")).spawn(move || {
+            while !signal::is_set_signal(signal::SIGINT) {
+                thread::sleep(SIGNAL_POLL_INTERVAL);
+            }
+
+            if let Err(e) = shutdown(timeout) {
+                crate::log_error!("shutdown", "{}", e.get_cause());
+            }
+        });
+
+        if let Err(e) = spawned {
+            ret = CommonError::new(&CommonDefaultErrorKind::SystemCallFail, format!("shutdown watcher - {}", e)).to_result();
+        }
+    });
+    ret
+}
@@ -25,6 +25,10 @@ pub mod signal {
     }
 }
 
+pub mod shutdown {
+    pub use crate::init::shutdown::{register_pool_shutdown, register_shutdown, shutdown, ShutdownFn};
+}
+
 pub mod logger {
     #[macro_export]
     macro_rules! log_debug {
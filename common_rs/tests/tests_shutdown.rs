@@ -0,0 +1,66 @@
+use std::sync::Arc;
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Duration, Instant};
+use common_rs::c_core::collection::pool::{get_thread_safe_pool, PoolKind, ThreadSafePool};
+use common_rs::c_err::CommonError;
+use common_rs::init::{init_common, InitConfig, LoggerConf};
+use common_rs::shutdown::{register_pool_shutdown, shutdown};
+use common_rs::signal::{is_set_signal, SIGINT};
+
+fn registered_pool(name : &'static str) -> Arc<dyn ThreadSafePool<i32, ()>> {
+    let p = get_thread_safe_pool(String::from(name), Box::new(|_ : ()| Ok(1)), 2, PoolKind::Owned);
+    register_pool_shutdown(String::from(name), &p);
+    p
+}
+
+// checks an item out on another thread and returns it after hold
+fn hold_item(p : &'_ Arc<dyn ThreadSafePool<i32, ()>>, hold : Duration) -> thread::JoinHandle<()> {
+    let (tx, rx) = mpsc::channel();
+    let p = p.clone();
+    let holder = thread::spawn(move || {
+        let item = p.get_owned(()).unwrap();
+        tx.send(()).unwrap();
+        thread::sleep(hold);
+        item.restoration();
+    });
+    rx.recv().unwrap();
+    holder
+}
+
+// the registry and the signal flags are process wide, the cases run one after another
+#[test]
+fn test_shutdown() -> Result<(), CommonError> {
+    let p = registered_pool("outstanding");
+    let holder = hold_item(&p, Duration::from_millis(50));
+
+    let started = Instant::now();
+    shutdown(Duration::from_secs(2))?;
+    assert!(started.elapsed() >= Duration::from_millis(40));
+    assert!(started.elapsed() < Duration::from_secs(2));
+    assert_eq!(p.alloc_size(), 0);
+    assert!(p.get_owned(()).is_err());
+    holder.join().unwrap();
+
+    let p = registered_pool("late");
+    let holder = hold_item(&p, Duration::from_millis(300));
+    assert!(shutdown(Duration::from_millis(50)).is_err());
+    assert!(p.get_owned(()).is_err());
+    holder.join().unwrap();
+
+    let p = registered_pool("signal");
+    p.get_owned(())?.restoration();
+    init_common(InitConfig { logger_conf : LoggerConf::Console, shutdown_timeout : Some(Duration::from_secs(1)) })?;
+    unsafe {
+        libc::raise(SIGINT);
+    }
+
+    let started = Instant::now();
+    while p.get_owned(()).is_ok() {
+        assert!(started.elapsed() < Duration::from_secs(2));
+        thread::sleep(Duration::from_millis(20));
+    }
+    assert!(is_set_signal(SIGINT));
+    assert_eq!(p.stats().idle, 0);
+    Ok(())
+}